@@ -0,0 +1,58 @@
+//! Creates the app's directories once at startup, before any feature writes
+//! under them.
+
+use std::fs;
+use std::path::PathBuf;
+use log::{info, warn};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use crate::icons::CUSTOM_ASSET_DIR;
+
+/// Resolved directories; `None` where the path couldn't be resolved or
+/// created, in which case the features using it degrade.
+#[derive(Debug, Clone, Default, Serialize)]
+#[allow(dead_code)]
+pub struct AppDirs {
+    pub config: Option<PathBuf>,
+    pub data: Option<PathBuf>,
+    pub log: Option<PathBuf>,
+    pub assets: Option<PathBuf>,
+}
+
+/// Resolves and creates the config, data, log and asset directories. Without
+/// a config dir, settings are kept in memory only.
+pub fn init(app: &AppHandle) -> AppDirs {
+    let path = app.path();
+    let config = ensure("config", path.app_config_dir());
+    let assets = config.as_ref().and_then(|dir| ensure("asset", Ok(dir.join(CUSTOM_ASSET_DIR))));
+    let dirs = AppDirs {
+        data: ensure("data", path.app_data_dir()),
+        log: ensure("log", path.app_log_dir()),
+        config,
+        assets,
+    };
+    if dirs.config.is_none() {
+        warn!("No usable config directory, settings will not be saved this session");
+    }
+    dirs
+}
+
+fn ensure(kind: &str, resolved: tauri::Result<PathBuf>) -> Option<PathBuf> {
+    let dir = match resolved {
+        Ok(dir) => dir,
+        Err(e) => {
+            warn!("Failed to resolve {} dir: {}", kind, e);
+            return None;
+        }
+    };
+    match fs::create_dir_all(&dir) {
+        Ok(()) => {
+            info!("Using {} dir {}", kind, dir.display());
+            Some(dir)
+        }
+        Err(e) => {
+            warn!("Failed to create {} dir {}: {}", kind, dir.display(), e);
+            None
+        }
+    }
+}
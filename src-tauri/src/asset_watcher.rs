@@ -0,0 +1,150 @@
+//! Watches the custom asset folder in the config dir and reloads the tray
+//! icons and sounds when files there change, so new assets apply without a
+//! restart. An invalid file is skipped and the previous asset stays in use.
+
+use tauri::{AppHandle, Manager};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crossbeam::channel::{select, unbounded, RecvTimeoutError};
+use log::{info, warn};
+use notify::{RecursiveMode, Watcher};
+use crate::core::state::SharedState;
+use crate::core::supervisor;
+use crate::icons;
+use crate::sound;
+use crate::tray::apply_tray_icon_style;
+
+const WORKER_NAME: &str = "asset-watcher";
+// Copying or saving a file fires several events; reload once they settle
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AssetKind {
+    Icon,
+    Sound,
+}
+
+impl AssetKind {
+    fn of(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "png" | "ico" => Some(AssetKind::Icon),
+            "wav" => Some(AssetKind::Sound),
+            _ => None,
+        }
+    }
+
+    fn validate(&self, path: &Path) -> Result<(), String> {
+        match self {
+            AssetKind::Icon => icons::validate_icon(path),
+            AssetKind::Sound => sound::validate_sound(path),
+        }
+    }
+}
+
+/// Loads the custom sounds, then watches the asset folder for changes.
+#[allow(dead_code)]
+pub fn start_asset_watcher(app: &AppHandle) {
+    let Some(dir) = icons::custom_asset_dir(app) else {
+        return;
+    };
+    // Created up front so there is something to watch and to drop files into
+    if let Err(e) = fs::create_dir_all(&dir) {
+        warn!("Failed to create asset folder {}: {}", dir.display(), e);
+        return;
+    }
+    sound::reload_custom(app, &dir);
+
+    let app = app.clone();
+    supervisor::spawn(WORKER_NAME, move |token| {
+        let (sender, events) = unbounded();
+        let mut watcher = match notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        }) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!("Failed to start asset watcher: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            warn!("Failed to watch {}: {}", dir.display(), e);
+            return;
+        }
+
+        loop {
+            let mut changed = HashSet::new();
+            let mut collect = |event: notify::Result<notify::Event>| match event {
+                Ok(event) => changed.extend(event.paths),
+                Err(e) => warn!("Asset watcher error: {}", e),
+            };
+            select! {
+                recv(token.channel()) -> _ => break,
+                recv(events) -> event => match event {
+                    Ok(event) => collect(event),
+                    Err(_) => break,
+                },
+            }
+            loop {
+                match events.recv_timeout(SETTLE_TIME) {
+                    Ok(event) => collect(event),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            if token.is_stopped() {
+                break;
+            }
+            reload(&app, &dir, &changed);
+        }
+    });
+}
+
+fn reload(app: &AppHandle, dir: &Path, changed: &HashSet<PathBuf>) {
+    let of_kind = |kind: AssetKind| changed.iter().filter(move |path| AssetKind::of(path) == Some(kind));
+
+    if of_kind(AssetKind::Icon).next().is_some() {
+        // Removed files fall back to the bundled icons; present ones must decode
+        let invalid = of_kind(AssetKind::Icon)
+            .filter(|path| path.is_file())
+            .find_map(|path| AssetKind::Icon.validate(path).err().map(|e| (path, e)));
+        if let Some((path, e)) = invalid {
+            warn!("Ignoring invalid icon {}: {}", path.display(), e);
+        } else if let Some(state) = app.try_state::<SharedState>() {
+            apply_tray_icon_style(app, state.get_settings().tray_icon_style);
+            info!("Reloaded tray icons from {}", dir.display());
+        }
+    }
+
+    // Validated per file, keeping the previous copy of an invalid one
+    if of_kind(AssetKind::Sound).next().is_some() {
+        sound::reload_custom(app, dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_validated_by_type() {
+        assert_eq!(AssetKind::of(Path::new("assets/tray-enabled.PNG")), Some(AssetKind::Icon));
+        assert_eq!(AssetKind::of(Path::new("assets/enable.wav")), Some(AssetKind::Sound));
+        assert_eq!(AssetKind::of(Path::new("assets/notes.txt")), None);
+
+        // A valid sound is not an icon and the other way around
+        let dir = std::env::temp_dir().join("enable-touchpad-asset-kinds");
+        fs::create_dir_all(&dir).unwrap();
+        let wav = dir.join("enable.wav");
+        let mut bytes = b"RIFF\x24\x00\x00\x00WAVEfmt ".to_vec();
+        bytes.resize(44, 0);
+        fs::write(&wav, bytes).unwrap();
+        assert!(AssetKind::Sound.validate(&wav).is_ok());
+        assert!(AssetKind::Icon.validate(&wav).is_err());
+        let icon = Path::new(env!("CARGO_MANIFEST_DIR")).join("icons/tray-enabled.png");
+        assert!(AssetKind::Icon.validate(&icon).is_ok());
+        assert!(AssetKind::Sound.validate(&icon).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
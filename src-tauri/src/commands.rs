@@ -1,41 +1,305 @@
-use tauri::command;
-use serde::{Deserialize, Serialize};
+use tauri::{command, ipc::Invoke, AppHandle, Emitter, State, Wry};
+use crossbeam::channel::select;
+use tauri_plugin_opener::OpenerExt;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use log::{info, warn};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::core::about::AboutInfo;
+use crate::core::diagnostics_bundle;
+use crate::core::error_report;
+use crate::core::led_indicator;
+use crate::core::hotkey_manager::{BenchmarkResult, HotkeyConflict, HotkeyManager};
+use crate::core::command_queue::{ActionSource, TouchpadAction};
+use crate::core::device_registry::Device;
+use crate::core::logging;
+use crate::core::mouse_detector;
+use crate::core::mouse_watcher;
+use crate::core::network_sync;
+use crate::core::fullscreen;
+use crate::core::hotkey_capture;
+use crate::core::hotkey_presets::{self, HotkeyPreset};
+use crate::core::input_controller::{self, set_allow_disable_without_mouse, set_applescript_retries, set_command_timeout, set_evdev_backend, set_macos_state_source, BackendProbeReport, ControllerError, DeviceInfo, ScrollMethod};
+use crate::core::settings::{system_settings_path, Settings};
+use crate::core::status_line;
+use crate::core::state::{SharedState, StateChange, TouchpadState, PERMISSION_ALERT};
+use crate::core::supervisor;
+use crate::app_dirs::AppDirs;
+use crate::pause;
+use crate::tray;
+use crate::window;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize)]
 #[allow(dead_code)]
-pub struct Settings {
-    pub enable_on_mouse_disconnect: bool,
-    pub disable_on_mouse_connect: bool,
-    pub enable_hotkey: String,
-    pub disable_hotkey: String,
-    pub show_osd: bool,
+pub struct Capabilities {
+    pub global_hotkeys: bool,
+    pub tray_toggle: bool,
+    /// False when only the per-user driver setting can be changed, e.g. on
+    /// Windows without admin rights.
+    pub device_disable: bool,
+    /// Whether an LED was found for `led_indicator`.
+    pub led_indicator: bool,
+}
+
+/// The most recent controller failure, for a persistent "last error" line.
+#[derive(Serialize)]
+#[allow(dead_code)]
+pub struct LastError {
+    pub message: String,
+    /// Error kind, e.g. "Timeout".
+    pub kind: String,
+    /// When it happened, in milliseconds since the Unix epoch.
+    pub at: u64,
+}
+
+/// Invoke handler shared by the binary and library entry points, so both
+/// expose the same command set.
+pub fn handler() -> impl Fn(Invoke<Wry>) -> bool + Send + Sync + 'static {
+    tauri::generate_handler![
+        get_settings,
+        save_settings,
+        check_permissions,
+        request_permissions,
+        get_capabilities,
+        open_error_report,
+        clear_error_report,
+        mouse_connected,
+        diagnostic_cycle,
+        effective_config,
+        simulate_hotkey,
+        pause_for,
+        set_paused,
+        start_hotkey_capture,
+        cancel_hotkey_capture,
+        hotkey_conflicts,
+        status_line,
+        open_system_touchpad_settings,
+        get_setting,
+        set_setting,
+        get_touchpad_state,
+        state_history,
+        toggle_touchpad,
+        last_error,
+        redetect_device,
+        detected_mice,
+        hotkey_status,
+        validate_settings_file,
+        capture_backend_diagnostics,
+        get_sensitivity,
+        set_sensitivity,
+        enable_temporarily,
+        cancel_temporary_enable,
+        get_scroll_method,
+        set_scroll_method,
+        benchmark_toggle,
+        list_hotkey_presets,
+        apply_hotkey_preset,
+        probe_backends,
+        app_paths,
+        system_settings,
+        about_info,
+        export_diagnostics_bundle,
+        simulate_mouse_event,
+        get_recent_logs,
+        start_log_stream,
+        stop_log_stream,
+        create_profile,
+        rename_profile,
+        delete_profile,
+        duplicate_profile,
+        import_settings
+    ]
 }
 
 #[command]
 #[allow(dead_code)]
-pub fn get_settings() -> Settings {
-    // Placeholder implementation - in a real app this would read from a config file
-    Settings {
-        enable_on_mouse_disconnect: true,
-        disable_on_mouse_connect: true,
-        enable_hotkey: "Ctrl+Shift+T".to_string(),
-        disable_hotkey: "Ctrl+Shift+Y".to_string(),
-        show_osd: true,
-    }
+pub async fn get_touchpad_state(hotkey_manager: State<'_, Arc<HotkeyManager>>) -> Result<TouchpadState, String> {
+    hotkey_manager.controller().get_state_async().await.map_err(|e| e.to_string())
 }
 
+/// Recent state changes, oldest first, including ones made outside the app.
 #[command]
 #[allow(dead_code)]
-pub fn save_settings(_settings: Settings) -> Result<(), String> {
-    // Placeholder implementation - in a real app this would save to a config file
-    Ok(())
+pub fn state_history(state: State<'_, SharedState>) -> Vec<StateChange> {
+    state.history.entries()
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn toggle_touchpad(hotkey_manager: State<'_, Arc<HotkeyManager>>) {
+    hotkey_manager.request(TouchpadAction::Toggle, ActionSource::Frontend);
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn get_settings(state: State<'_, SharedState>) -> Settings {
+    state.get_stored_settings().normalize_hotkeys()
+}
+
+/// Settings actually in effect, after profile and live overrides.
+#[command]
+#[allow(dead_code)]
+pub fn effective_config(state: State<'_, SharedState>) -> Settings {
+    state.get_settings().normalize_hotkeys()
+}
+
+/// Returns a warning for each value that was clamped into range.
+#[command]
+#[allow(dead_code)]
+pub fn save_settings(
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+    mut settings: Settings,
+) -> Result<Vec<String>, String> {
+    let warnings = settings.validate_and_clamp();
+    settings.validate()?;
+    state.set_settings(settings);
+    apply_stored_settings(&state, &hotkey_manager)?;
+    Ok(warnings)
+}
+
+/// Lints a settings file before import; see `Settings::lint_file`.
+#[command]
+#[allow(dead_code)]
+pub fn validate_settings_file(path: String) -> Result<Vec<String>, String> {
+    Settings::lint_file(Path::new(&path))
+}
+
+/// Replaces the settings with those in a file, e.g. exported on another
+/// machine. Returns a warning for each value that was clamped into range.
+#[command]
+#[allow(dead_code)]
+pub fn import_settings(
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+    path: String,
+) -> Result<Vec<String>, String> {
+    let contents = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let mut settings: Settings = serde_json::from_str(&contents).map_err(|e| format!("Invalid settings file: {}", e))?;
+    let warnings = settings.validate_and_clamp();
+    settings.validate()?;
+    state.set_settings(settings);
+    apply_stored_settings(&state, &hotkey_manager)?;
+    info!("Imported settings from {}", path);
+    Ok(warnings)
 }
 
 #[command]
 #[allow(dead_code)]
-pub fn check_permissions() -> bool {
-    // Placeholder implementation - in a real app this would check system permissions
-    true
+pub fn get_setting(state: State<'_, SharedState>, key: String) -> Result<Value, String> {
+    state.get_stored_settings().get_field(&key)
+}
+
+/// Updates a single stored setting, validated like `save_settings`.
+#[command]
+#[allow(dead_code)]
+pub fn set_setting(
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+    key: String,
+    value: Value,
+) -> Result<(), String> {
+    state.update_setting(&key, value)?;
+    apply_stored_settings(&state, &hotkey_manager)
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn create_profile(
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+    name: String,
+) -> Result<(), String> {
+    state.modify_settings(|settings| settings.create_profile(&name))?;
+    apply_stored_settings(&state, &hotkey_manager)
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn rename_profile(
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+    old: String,
+    new: String,
+) -> Result<(), String> {
+    state.modify_settings(|settings| settings.rename_profile(&old, &new))?;
+    apply_stored_settings(&state, &hotkey_manager)
+}
+
+/// Deletes a profile; if it was active, the base settings apply again.
+#[command]
+#[allow(dead_code)]
+pub fn delete_profile(
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+    name: String,
+) -> Result<(), String> {
+    state.modify_settings(|settings| settings.delete_profile(&name))?;
+    apply_stored_settings(&state, &hotkey_manager)
+}
+
+/// Copies a profile and returns the copy's name.
+#[command]
+#[allow(dead_code)]
+pub fn duplicate_profile(
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+    name: String,
+) -> Result<String, String> {
+    let copy = state.modify_settings(|settings| settings.duplicate_profile(&name))?;
+    apply_stored_settings(&state, &hotkey_manager)?;
+    Ok(copy)
+}
+
+// Pushes the stored settings to the running services and persists them
+fn apply_stored_settings(state: &SharedState, hotkey_manager: &Arc<HotkeyManager>) -> Result<(), String> {
+    apply_effective_settings(state, hotkey_manager);
+    state.save_settings()
+}
+
+/// Pushes the effective settings to the running services, e.g. after the
+/// power source switched the power profile.
+pub fn apply_effective_settings(state: &SharedState, hotkey_manager: &Arc<HotkeyManager>) {
+    let effective = state.get_settings();
+    set_command_timeout(Duration::from_millis(effective.command_timeout_ms));
+    logging::set_log_format(effective.log_format);
+    set_allow_disable_without_mouse(effective.allow_disable_without_mouse);
+    // Switching backends can switch the managed device, and with it the
+    // name in the tray tooltip
+    if set_evdev_backend(effective.evdev_backend) {
+        if let Err(e) = hotkey_manager.redetect_device() {
+            warn!("Re-detection after the backend change failed: {}", e);
+        }
+    }
+    set_macos_state_source(effective.macos_state_source);
+    set_applescript_retries(effective.applescript_retries);
+    mouse_detector::set_ignored_devices(effective.ignored_mouse_devices.clone());
+    hotkey_manager.apply_device_settings(&effective);
+    hotkey_manager.update_hotkeys();
+    tray::apply_tray_icon_style(&state.app_handle, effective.tray_icon_style);
+    if !effective.led_indicator {
+        led_indicator::restore();
+    }
+    network_sync::configure(hotkey_manager, &effective);
+    fullscreen::configure(&state.app_handle, hotkey_manager, &effective);
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn check_permissions(state: State<'_, SharedState>) -> bool {
+    let granted = permissions_granted();
+    // Stops the tray flashing once the user has fixed it, unless it flashes
+    // for something else
+    if granted && state.alert().as_deref() == Some(PERMISSION_ALERT) {
+        state.clear_alert();
+    }
+    granted
+}
+
+pub fn permissions_granted() -> bool {
+    input_controller::permissions_granted()
 }
 
 #[command]
@@ -43,4 +307,503 @@ pub fn check_permissions() -> bool {
 pub fn request_permissions() -> Result<(), String> {
     // Placeholder implementation - in a real app this would request system permissions
     Ok(())
-}
\ No newline at end of file
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn get_capabilities(
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+) -> Capabilities {
+    Capabilities {
+        global_hotkeys: state.hotkeys_available(),
+        tray_toggle: true,
+        device_disable: hotkey_manager.can_disable_device(),
+        led_indicator: led_indicator::available(),
+    }
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn open_error_report(app: AppHandle) -> Result<(), String> {
+    let path = error_report::report_path().ok_or("Error report is not available")?;
+    if !path.exists() {
+        return Err("No errors have been recorded".to_string());
+    }
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn clear_error_report() -> Result<(), String> {
+    error_report::clear().map_err(|e| e.to_string())
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn mouse_connected() -> bool {
+    mouse_detector::is_mouse_connected()
+}
+
+#[command]
+#[allow(dead_code)]
+pub async fn diagnostic_cycle(app: AppHandle, hotkey_manager: State<'_, Arc<HotkeyManager>>) -> Result<Vec<String>, String> {
+    let manager = hotkey_manager.inner().clone();
+    let mut report = AboutInfo::collect(&app).lines();
+    // Runs for a couple of seconds, keep it off the main thread
+    let cycle = tauri::async_runtime::spawn_blocking(move || manager.diagnostic_cycle())
+        .await
+        .map_err(|e| e.to_string())?;
+    report.extend(cycle);
+    Ok(report)
+}
+
+/// Runs a hotkey action through the same queue as real key presses, for UI testing.
+#[command]
+#[allow(dead_code)]
+pub fn simulate_hotkey(
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+    action: String,
+) -> Result<(), String> {
+    if !cfg!(debug_assertions) && !state.get_settings().allow_hotkey_simulation {
+        return Err("Hotkey simulation is disabled".to_string());
+    }
+
+    let action = match action.as_str() {
+        "toggle" => TouchpadAction::Toggle,
+        "enable" => TouchpadAction::Enable,
+        "disable" => TouchpadAction::Disable,
+        "toggle_trackpoint" => TouchpadAction::ToggleTrackpoint,
+        "toggle_both" => TouchpadAction::ToggleBoth,
+        other => {
+            return Err(format!(
+                "Unknown action: {} (expected toggle, enable, disable, toggle_trackpoint or toggle_both)",
+                other
+            ))
+        }
+    };
+    hotkey_manager.request(action, ActionSource::Hotkey);
+    Ok(())
+}
+
+/// Injects a mouse connect or disconnect into the auto-toggle rules, for
+/// trying them out without a physical mouse.
+#[command]
+#[allow(dead_code)]
+pub fn simulate_mouse_event(
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+    connected: bool,
+) -> Result<(), String> {
+    if !cfg!(debug_assertions) && !state.get_settings().allow_mouse_simulation {
+        return Err("Mouse simulation is disabled".to_string());
+    }
+    mouse_watcher::simulate(&hotkey_manager, connected);
+    Ok(())
+}
+
+/// Pauses auto-toggling for `minutes`, replacing any running pause.
+#[command]
+#[allow(dead_code)]
+pub fn pause_for(app: AppHandle, minutes: u64) -> Result<(), String> {
+    if minutes == 0 {
+        return Err("minutes must be greater than 0".to_string());
+    }
+    let seconds = minutes.checked_mul(60).ok_or("minutes is out of range")?;
+    pause::pause_for(&app, Duration::from_secs(seconds))
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn set_paused(app: AppHandle, paused: bool) {
+    pause::set_paused(&app, paused);
+}
+
+const HOTKEY_CAPTURE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Waits for the user to press a key combination and returns it as an
+/// accelerator string ready for `save_settings`.
+#[command]
+#[allow(dead_code)]
+pub async fn start_hotkey_capture() -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(|| hotkey_capture::capture(HOTKEY_CAPTURE_TIMEOUT))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn cancel_hotkey_capture() {
+    hotkey_capture::cancel();
+}
+
+/// Each configured hotkey and whether its registration is currently active.
+#[command]
+#[allow(dead_code)]
+pub fn hotkey_status(hotkey_manager: State<'_, Arc<HotkeyManager>>) -> Vec<(String, bool)> {
+    hotkey_manager.hotkey_status()
+}
+
+/// Hotkeys that failed to register or are known to be reserved by the OS.
+#[command]
+#[allow(dead_code)]
+pub fn hotkey_conflicts(state: State<'_, SharedState>) -> Vec<HotkeyConflict> {
+    state.hotkey_conflicts()
+}
+
+/// Formats the current status for bars and scripts, see `status_line::format_status`.
+#[command]
+#[allow(dead_code)]
+pub async fn status_line(
+    template: Option<String>,
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+) -> Result<String, String> {
+    let template = template.as_deref().unwrap_or(status_line::DEFAULT_TEMPLATE);
+    let current = hotkey_manager.controller().get_state_async().await.ok();
+    Ok(status_line::format_status(template, current, &state.get_device_name(), state.is_paused()))
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn open_system_touchpad_settings(app: AppHandle) -> Result<(), String> {
+    window::open_system_touchpad_settings(&app)
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn last_error(state: State<'_, SharedState>) -> Option<LastError> {
+    let (at, error) = state.last_error()?;
+    let at = SystemTime::now()
+        .checked_sub(at.elapsed())
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_millis() as u64);
+    Some(LastError {
+        message: error.to_string(),
+        kind: format!("{:?}", error),
+        at,
+    })
+}
+
+/// Re-runs device detection without a restart and returns the device now managed.
+#[command]
+#[allow(dead_code)]
+pub async fn redetect_device(hotkey_manager: State<'_, Arc<HotkeyManager>>) -> Result<DeviceInfo, String> {
+    let manager = hotkey_manager.inner().clone();
+    let result = tauri::async_runtime::spawn_blocking(move || manager.redetect_device())
+        .await
+        .map_err(|e| e.to_string())?;
+    result.map_err(|e| match e {
+        ControllerError::LinuxDeviceNotFound => "No touchpad or trackpoint found".to_string(),
+        e => e.to_string(),
+    })
+}
+
+/// External pointing devices currently detected, including ignored ones, so
+/// the user can pick which to add to `ignored_mouse_devices`.
+#[command]
+#[allow(dead_code)]
+pub async fn detected_mice() -> Result<Vec<Device>, String> {
+    tauri::async_runtime::spawn_blocking(mouse_detector::detect_mice)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Failed to enumerate pointing devices".to_string())
+}
+
+/// Raw output of the read-only backend queries behind `get_state`, for
+/// support reports.
+#[command]
+#[allow(dead_code)]
+pub async fn capture_backend_diagnostics(hotkey_manager: State<'_, Arc<HotkeyManager>>) -> Result<String, String> {
+    let manager = hotkey_manager.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || manager.backend_diagnostics())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Pointer speed normalized to -1.0..=1.0, 0.0 being the platform default.
+#[command]
+#[allow(dead_code)]
+pub async fn get_sensitivity(hotkey_manager: State<'_, Arc<HotkeyManager>>) -> Result<f64, String> {
+    hotkey_manager.controller().get_sensitivity_async().await.map_err(|e| e.to_string())
+}
+
+/// Sets the pointer speed, clamped to -1.0..=1.0, and returns the applied value.
+#[command]
+#[allow(dead_code)]
+pub async fn set_sensitivity(hotkey_manager: State<'_, Arc<HotkeyManager>>, value: f64) -> Result<f64, String> {
+    let manager = hotkey_manager.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || manager.set_sensitivity(value))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Enables the touchpad for `seconds`, then disables it again unless the
+/// user toggles it or cancels first.
+#[command]
+#[allow(dead_code)]
+pub fn enable_temporarily(hotkey_manager: State<'_, Arc<HotkeyManager>>, seconds: u64) -> Result<(), String> {
+    if seconds == 0 {
+        return Err("seconds must be greater than 0".to_string());
+    }
+    hotkey_manager.request(TouchpadAction::EnableTemporarily(seconds), ActionSource::Frontend);
+    Ok(())
+}
+
+/// Keeps a temporarily enabled touchpad on.
+#[command]
+#[allow(dead_code)]
+pub fn cancel_temporary_enable(hotkey_manager: State<'_, Arc<HotkeyManager>>) {
+    hotkey_manager.request(TouchpadAction::CancelTemporaryEnable, ActionSource::Frontend);
+}
+
+#[derive(Serialize)]
+#[allow(dead_code)]
+pub struct ScrollMethodInfo {
+    pub current: Option<ScrollMethod>,
+    pub available: Vec<ScrollMethod>,
+}
+
+/// The active scroll method and the ones the hardware supports.
+#[command]
+#[allow(dead_code)]
+pub async fn get_scroll_method(hotkey_manager: State<'_, Arc<HotkeyManager>>) -> Result<ScrollMethodInfo, String> {
+    let controller = hotkey_manager.controller();
+    Ok(ScrollMethodInfo {
+        current: controller.get_scroll_method_async().await.ok(),
+        available: controller.scroll_methods_async().await.map_err(|e| e.to_string())?,
+    })
+}
+
+#[command]
+#[allow(dead_code)]
+pub async fn set_scroll_method(hotkey_manager: State<'_, Arc<HotkeyManager>>, method: ScrollMethod) -> Result<(), String> {
+    let manager = hotkey_manager.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || manager.set_scroll_method(method))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Times repeated disable/enable cycles to surface slow backends. The
+/// touchpad is left enabled.
+#[command]
+#[allow(dead_code)]
+pub async fn benchmark_toggle(hotkey_manager: State<'_, Arc<HotkeyManager>>, iterations: u32) -> Result<BenchmarkResult, String> {
+    let manager = hotkey_manager.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || manager.benchmark_toggle(iterations))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn list_hotkey_presets() -> Vec<HotkeyPreset> {
+    hotkey_presets::PRESETS.to_vec()
+}
+
+/// Sets the enable/disable/settings hotkeys from a preset and re-registers
+/// them. Returns warnings for hotkeys known to be taken by the system.
+#[command]
+#[allow(dead_code)]
+pub fn apply_hotkey_preset(
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+    name: String,
+) -> Result<Vec<String>, String> {
+    let preset = hotkey_presets::find(&name).ok_or_else(|| format!("Unknown hotkey preset: {}", name))?;
+    let mut settings = state.get_stored_settings();
+    preset.apply_to(&mut settings);
+    settings.validate()?;
+    state.set_settings(settings);
+    apply_stored_settings(&state, &hotkey_manager)?;
+    Ok(preset.warnings())
+}
+
+/// Re-probes every backend and reports why each was chosen or skipped.
+#[command]
+#[allow(dead_code)]
+pub async fn probe_backends(
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+) -> Result<BackendProbeReport, String> {
+    let state = state.inner().clone();
+    let manager = hotkey_manager.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let previous = input_controller::preferred_backend();
+        let report = state.select_backend(true);
+        // Picks up the device of the new backend and refreshes the tooltip
+        if report.selected != previous {
+            if let Err(e) = manager.redetect_device() {
+                warn!("Re-detection after the backend change failed: {}", e);
+            }
+        }
+        report
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Directories resolved and created at startup, for diagnostics.
+#[command]
+#[allow(dead_code)]
+pub fn app_paths(dirs: State<'_, AppDirs>) -> AppDirs {
+    dirs.inner().clone()
+}
+
+/// Where system-wide defaults are read from.
+#[derive(Serialize)]
+#[allow(dead_code)]
+pub struct SystemSettings {
+    pub path: String,
+    /// Values currently taken from the system file rather than the user's.
+    pub values: Map<String, Value>,
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn system_settings(state: State<'_, SharedState>) -> SystemSettings {
+    SystemSettings {
+        path: system_settings_path().display().to_string(),
+        values: state.settings_from_system(),
+    }
+}
+
+/// Version, platform, backend and which features came up, for bug reports.
+#[command]
+#[allow(dead_code)]
+pub fn about_info(app: AppHandle) -> AboutInfo {
+    AboutInfo::collect(&app)
+}
+
+/// Largest error report included in a diagnostics bundle; older entries are dropped.
+const BUNDLE_REPORT_LIMIT: usize = 256 * 1024;
+
+/// Zips the about info, redacted settings, the current state, backend
+/// details and recent logs into one file to attach to bug reports, without
+/// switching the touchpad. Returns the path written.
+#[command]
+#[allow(dead_code)]
+pub async fn export_diagnostics_bundle(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    hotkey_manager: State<'_, Arc<HotkeyManager>>,
+    path: String,
+) -> Result<String, String> {
+    let about = AboutInfo::collect(&app).lines().join("\n");
+    let settings = state.get_settings().redacted()?;
+    let settings = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    let manager = hotkey_manager.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut entries = vec![
+            ("about.txt", about),
+            ("settings.json", settings),
+            ("status.txt", manager.diagnostic_report().join("\n")),
+            ("backend.txt", manager.backend_diagnostics()),
+            ("log.txt", logging::recent_lines().join("\n")),
+        ];
+        if let Some(report) = error_report::report_path().and_then(|p| std::fs::read_to_string(p).ok()) {
+            entries.push(("error-report.txt", truncate_start(report, BUNDLE_REPORT_LIMIT)));
+        }
+        let written = diagnostics_bundle::write_bundle(Path::new(&path), &entries)?;
+        info!("Wrote diagnostics bundle to {}", written.display());
+        Ok(written.display().to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Keeps the last `limit` bytes of `text`, starting on a line boundary.
+fn truncate_start(text: String, limit: usize) -> String {
+    if text.len() <= limit {
+        return text;
+    }
+    let mut start = text.len() - limit;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    let tail = &text[start..];
+    let tail = tail.split_once('\n').map_or(tail, |(_, rest)| rest);
+    format!("[truncated]\n{}", tail)
+}
+
+const LOG_STREAM_WORKER: &str = "log-stream";
+
+/// The last `count` log lines, oldest first.
+#[command]
+#[allow(dead_code)]
+pub fn get_recent_logs(count: usize) -> Vec<String> {
+    let lines = logging::recent_lines();
+    let skip = lines.len().saturating_sub(count);
+    lines.into_iter().skip(skip).collect()
+}
+
+/// Emits each new log line as a "log-line" event, for a log panel while it
+/// is open. Calling it again restarts the stream.
+#[command]
+#[allow(dead_code)]
+pub fn start_log_stream(app: AppHandle) {
+    supervisor::stop(LOG_STREAM_WORKER);
+    let lines = logging::subscribe();
+    supervisor::spawn(LOG_STREAM_WORKER, move |token| loop {
+        let line = select! {
+            recv(lines) -> line => match line {
+                Ok(line) => line,
+                Err(_) => break,
+            },
+            recv(token.channel()) -> _ => break,
+        };
+        // No logging on failure, that would feed the stream itself
+        let _ = app.emit("log-line", line);
+    });
+}
+
+#[command]
+#[allow(dead_code)]
+pub fn stop_log_stream() {
+    logging::unsubscribe();
+    supervisor::stop(LOG_STREAM_WORKER);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Command names in the `generate_handler!` list of `handler`
+    fn registered_commands() -> Vec<String> {
+        let source = include_str!("commands.rs");
+        let list = source.split("generate_handler![").nth(1).and_then(|rest| rest.split(']').next()).unwrap();
+        list.split(',').map(str::trim).filter(|name| !name.is_empty()).map(String::from).collect()
+    }
+
+    #[test]
+    fn handler_list_builds() {
+        let _handler = handler();
+        let commands = registered_commands();
+        assert!(commands.iter().any(|name| name == "get_settings"));
+        assert!(!commands.iter().any(|name| name == "greet"));
+    }
+
+    #[test]
+    fn every_registered_command_is_allowed() {
+        let capability: Value = serde_json::from_str(include_str!("../capabilities/default.json")).unwrap();
+        let allowed: Vec<&str> = capability["commands"]["allow"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        let commands = registered_commands();
+        for name in &commands {
+            assert!(allowed.contains(&name.as_str()), "{} is not in capabilities/default.json", name);
+        }
+        for name in allowed {
+            assert!(commands.iter().any(|command| command == name), "{} is allowed but not registered", name);
+        }
+    }
+}
@@ -1,4 +1,38 @@
 pub mod input_controller;
 pub mod state;
 pub mod hotkey_manager;
-pub mod mouse_emulator;
\ No newline at end of file
+pub mod mouse_emulator;
+pub mod settings;
+pub mod error_report;
+pub mod dnd;
+pub mod command_queue;
+pub mod mouse_detector;
+pub mod schedule;
+pub mod mouse_watcher;
+pub mod key_listener;
+pub mod keypad_grab;
+pub mod hotkey_capture;
+pub mod status_line;
+#[cfg(target_os = "windows")]
+pub mod device_notify;
+#[cfg(target_os = "windows")]
+pub mod registry_notify;
+pub mod double_tap;
+pub mod device_registry;
+pub mod supervisor;
+pub mod mouse_keys;
+pub mod cursor_confine;
+pub mod fullscreen;
+pub mod logging;
+pub mod corner_toggle;
+pub mod hotkey_presets;
+pub mod webhook;
+pub mod accelerator;
+pub mod about;
+pub mod diagnostics_bundle;
+pub mod led_indicator;
+pub mod press_confirm;
+pub mod power;
+pub mod network_sync;
+pub mod resume;
+pub mod theme;
@@ -0,0 +1,104 @@
+//! Build and runtime facts for bug reports, gathered without side effects.
+
+use std::sync::Arc;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use crate::core::hotkey_manager::HotkeyManager;
+use crate::core::input_controller::TouchpadController;
+use crate::core::state::SharedState;
+use crate::osd::OSDManager;
+use crate::tray::TRAY_ID;
+
+#[derive(Debug, Clone, Serialize)]
+#[allow(dead_code)]
+pub struct AboutInfo {
+    pub version: String,
+    /// "debug" or "release".
+    pub profile: String,
+    pub os: String,
+    pub os_version: String,
+    pub arch: String,
+    pub backend: Option<String>,
+    pub device: Option<String>,
+    pub hotkeys: bool,
+    pub osd: bool,
+    pub tray: bool,
+}
+
+impl AboutInfo {
+    pub fn collect(app: &AppHandle) -> Self {
+        let manager = app.try_state::<Arc<HotkeyManager>>();
+        let state = app.try_state::<SharedState>();
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            profile: if cfg!(debug_assertions) { "debug" } else { "release" }.to_string(),
+            os: std::env::consts::OS.to_string(),
+            os_version: os_version().unwrap_or_else(|| "unknown".to_string()),
+            arch: std::env::consts::ARCH.to_string(),
+            backend: manager.as_ref().map(|manager| manager.controller().backend_name()),
+            device: state.as_ref().map(|state| state.get_device_name()),
+            hotkeys: state.as_ref().is_some_and(|state| state.hotkeys_available()),
+            osd: app.try_state::<Arc<OSDManager>>().is_some(),
+            tray: app.tray_by_id(TRAY_ID).is_some(),
+        }
+    }
+
+    /// One "key: value" line per field, for plain-text reports.
+    pub fn lines(&self) -> Vec<String> {
+        let optional = |value: &Option<String>| value.clone().unwrap_or_else(|| "none".to_string());
+        vec![
+            format!("Version: {} ({})", self.version, self.profile),
+            format!("OS: {} {} ({})", self.os, self.os_version, self.arch),
+            format!("Backend: {}", optional(&self.backend)),
+            format!("Device: {}", optional(&self.device)),
+            format!("Hotkeys: {}, OSD: {}, tray: {}", self.hotkeys, self.osd, self.tray),
+        ]
+    }
+}
+
+// Read from version files where the OS has them, so it stays fast
+#[cfg(target_os = "linux")]
+fn os_version() -> Option<String> {
+    let release = std::fs::read_to_string("/etc/os-release").ok();
+    let name = release.as_deref().and_then(|contents| {
+        contents
+            .lines()
+            .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+            .map(|value| value.trim_matches('"').to_string())
+    });
+    let kernel = std::fs::read_to_string("/proc/sys/kernel/osrelease").ok();
+    match (name, kernel) {
+        (Some(name), Some(kernel)) => Some(format!("{} (kernel {})", name, kernel.trim())),
+        (name, kernel) => name.or(kernel.map(|k| k.trim().to_string())),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn os_version() -> Option<String> {
+    let plist = std::fs::read_to_string("/System/Library/CoreServices/SystemVersion.plist").ok()?;
+    let (_, rest) = plist.split_once("<key>ProductVersion</key>")?;
+    let (_, rest) = rest.split_once("<string>")?;
+    rest.split_once("</string>").map(|(version, _)| version.to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn os_version() -> Option<String> {
+    use crate::core::input_controller::{command_timeout, run_with_timeout};
+    let (_, output) = run_with_timeout(
+        std::process::Command::new("reg")
+            .args(["query", r"HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion", "/v", "CurrentBuild"]),
+        command_timeout(),
+    )
+    .ok()?;
+    // "    CurrentBuild    REG_SZ    22631"
+    output
+        .lines()
+        .find(|line| line.trim_start().starts_with("CurrentBuild"))
+        .and_then(|line| line.split_whitespace().last())
+        .map(|build| format!("build {}", build))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn os_version() -> Option<String> {
+    None
+}
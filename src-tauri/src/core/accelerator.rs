@@ -0,0 +1,206 @@
+//! Canonical form for hotkey strings, so "control+shift+t", "Ctrl+Shift+T"
+//! and "Shift+Ctrl+T" are treated as the same shortcut everywhere.
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ParseError {
+    #[error("accelerator is empty")]
+    Empty,
+    #[error("accelerator has an empty part: {0}")]
+    EmptyPart(String),
+    #[error("unknown key: {0}")]
+    UnknownKey(String),
+    #[error("accelerator has no key besides modifiers: {0}")]
+    MissingKey(String),
+    #[error("accelerator has more than one key: {0}")]
+    MultipleKeys(String),
+}
+
+// Canonical modifier names, in the order they are written
+const MODIFIERS: [&str; 4] = ["Ctrl", "Alt", "Shift", "Super"];
+
+// Named keys besides letters, digits, F1-F24 and Numpad0-9, as (canonical,
+// aliases); the global shortcut plugin accepts all of them
+const NAMED_KEYS: &[(&str, &[&str])] = &[
+    ("Space", &[]),
+    ("Tab", &[]),
+    ("Enter", &["return"]),
+    ("Escape", &["esc"]),
+    ("Backspace", &[]),
+    ("Delete", &["del"]),
+    ("Insert", &["ins"]),
+    ("Home", &[]),
+    ("End", &[]),
+    ("PageUp", &["pgup"]),
+    ("PageDown", &["pgdn"]),
+    ("Up", &["arrowup"]),
+    ("Down", &["arrowdown"]),
+    ("Left", &["arrowleft"]),
+    ("Right", &["arrowright"]),
+    ("Comma", &[","]),
+    ("Period", &["."]),
+    ("Minus", &["-"]),
+    ("Equal", &["="]),
+    ("Slash", &["/"]),
+    ("Semicolon", &[";"]),
+    ("Quote", &["'"]),
+    ("Backquote", &["`"]),
+    ("Backslash", &["\\"]),
+    ("BracketLeft", &["["]),
+    ("BracketRight", &["]"]),
+    ("CapsLock", &[]),
+    ("NumLock", &[]),
+    ("ScrollLock", &[]),
+    ("PrintScreen", &[]),
+    ("Pause", &["pausebreak"]),
+    ("NumpadAdd", &["numadd", "numpadplus", "numplus"]),
+    ("NumpadSubtract", &["numsubtract"]),
+    ("NumpadMultiply", &["nummultiply"]),
+    ("NumpadDivide", &["numdivide"]),
+    ("NumpadDecimal", &["numdecimal"]),
+    ("NumpadEnter", &["numenter"]),
+    ("NumpadEqual", &["numequal"]),
+    ("AudioVolumeUp", &["volumeup"]),
+    ("AudioVolumeDown", &["volumedown"]),
+    ("AudioVolumeMute", &["volumemute"]),
+    ("MediaPlay", &[]),
+    ("MediaPause", &[]),
+    ("MediaPlayPause", &[]),
+    ("MediaStop", &[]),
+    ("MediaTrackNext", &[]),
+    ("MediaTrackPrevious", &["mediatrackprev"]),
+];
+
+/// Index into `MODIFIERS` for a modifier name or alias, case-insensitive.
+fn modifier_index(part: &str) -> Option<usize> {
+    match part.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => Some(0),
+        "alt" | "option" => Some(1),
+        "shift" => Some(2),
+        "super" | "cmd" | "command" | "meta" | "win" | "windows" => Some(3),
+        // The platform's primary modifier
+        "cmdorctrl" | "commandorcontrol" | "cmdorcontrol" | "commandorctrl" => {
+            Some(if cfg!(target_os = "macos") { 3 } else { 0 })
+        }
+        _ => None,
+    }
+}
+
+fn key_name(part: &str) -> Option<String> {
+    let lower = part.to_ascii_lowercase();
+    if part.len() == 1 && part.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Some(part.to_ascii_uppercase());
+    }
+    if let Some(number) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+        return (1..=24).contains(&number).then(|| format!("F{}", number));
+    }
+    // "Numpad5" or the shorter "Num5"
+    for prefix in ["numpad", "num"] {
+        if let Some(digit) = lower.strip_prefix(prefix).filter(|rest| rest.len() == 1 && rest.chars().all(|c| c.is_ascii_digit())) {
+            return Some(format!("Numpad{}", digit));
+        }
+    }
+    // "KeyT" and "Digit5" as used by DOM key codes
+    for prefix in ["key", "digit"] {
+        if let Some(rest) = lower.strip_prefix(prefix) {
+            if rest.len() == 1 && rest.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Some(rest.to_ascii_uppercase());
+            }
+        }
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(name, aliases)| name.eq_ignore_ascii_case(part) || aliases.contains(&lower.as_str()))
+        .map(|(name, _)| name.to_string())
+}
+
+/// Parses `accelerator` into its canonical form: modifiers as Ctrl, Alt,
+/// Shift, Super in that order, then exactly one key, joined with "+".
+pub fn normalize_accelerator(accelerator: &str) -> Result<String, ParseError> {
+    let accelerator = accelerator.trim();
+    if accelerator.is_empty() {
+        return Err(ParseError::Empty);
+    }
+
+    let mut modifiers = [false; MODIFIERS.len()];
+    let mut key = None;
+    for part in accelerator.split('+').map(str::trim) {
+        if part.is_empty() {
+            return Err(ParseError::EmptyPart(accelerator.to_string()));
+        }
+        if let Some(index) = modifier_index(part) {
+            modifiers[index] = true;
+            continue;
+        }
+        let name = key_name(part).ok_or_else(|| ParseError::UnknownKey(part.to_string()))?;
+        if key.replace(name).is_some() {
+            return Err(ParseError::MultipleKeys(accelerator.to_string()));
+        }
+    }
+    let key = key.ok_or_else(|| ParseError::MissingKey(accelerator.to_string()))?;
+
+    let mut parts: Vec<&str> = MODIFIERS
+        .iter()
+        .zip(modifiers)
+        .filter_map(|(name, held)| held.then_some(*name))
+        .collect();
+    parts.push(&key);
+    Ok(parts.join("+"))
+}
+
+/// Whether two accelerators name the same shortcut; unparsable ones compare
+/// case-insensitively as written.
+pub fn same_accelerator(a: &str, b: &str) -> bool {
+    match (normalize_accelerator(a), normalize_accelerator(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.trim().eq_ignore_ascii_case(b.trim()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spellings_of_a_shortcut_share_one_form() {
+        for spelling in ["Ctrl+Shift+T", "control+shift+t", "Shift+Ctrl+T", " ctrl + SHIFT + KeyT "] {
+            assert_eq!(normalize_accelerator(spelling).unwrap(), "Ctrl+Shift+T", "{}", spelling);
+        }
+        assert_eq!(normalize_accelerator("Win+Option+Esc").unwrap(), "Alt+Super+Escape");
+        let primary = if cfg!(target_os = "macos") { "Super+T" } else { "Ctrl+T" };
+        assert_eq!(normalize_accelerator("CmdOrCtrl+T").unwrap(), primary);
+        assert!(same_accelerator("alt+f4", "Alt+F4"));
+    }
+
+    #[test]
+    fn accepts_every_key_the_plugin_does() {
+        let keys = [
+            ("Ctrl+`", "Ctrl+Backquote"),
+            ("Ctrl+BracketLeft", "Ctrl+BracketLeft"),
+            ("Ctrl+]", "Ctrl+BracketRight"),
+            ("Alt+Quote", "Alt+Quote"),
+            ("Ctrl+Backslash", "Ctrl+Backslash"),
+            ("Ctrl+Numpad7", "Ctrl+Numpad7"),
+            ("Ctrl+num0", "Ctrl+Numpad0"),
+            ("Ctrl+NumpadAdd", "Ctrl+NumpadAdd"),
+            ("PrintScreen", "PrintScreen"),
+            ("Shift+F13", "Shift+F13"),
+            ("F24", "F24"),
+            ("VolumeMute", "AudioVolumeMute"),
+        ];
+        for (spelling, canonical) in keys {
+            assert_eq!(normalize_accelerator(spelling).as_deref(), Ok(canonical), "{}", spelling);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_accelerators() {
+        assert_eq!(normalize_accelerator("  "), Err(ParseError::Empty));
+        assert!(matches!(normalize_accelerator("Ctrl++T"), Err(ParseError::EmptyPart(_))));
+        assert!(matches!(normalize_accelerator("Ctrl+Hyper"), Err(ParseError::UnknownKey(_))));
+        assert!(matches!(normalize_accelerator("F25"), Err(ParseError::UnknownKey(_))));
+        assert!(matches!(normalize_accelerator("Ctrl+Shift"), Err(ParseError::MissingKey(_))));
+        assert!(matches!(normalize_accelerator("Ctrl+A+B"), Err(ParseError::MultipleKeys(_))));
+    }
+}
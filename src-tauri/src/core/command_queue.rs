@@ -0,0 +1,542 @@
+//! Serializes touchpad state changes from every entry point (hotkeys, tray,
+//! frontend, ...) through a single worker thread so they never overlap.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crossbeam::channel::{at, never, select, Receiver, RecvTimeoutError};
+use serde::Serialize;
+use log::{info, warn};
+use crate::core::hotkey_manager::HotkeyManager;
+use crate::core::mouse_detector;
+use crate::core::state::TouchpadState;
+use crate::core::supervisor;
+
+const WORKER_NAME: &str = "action-queue";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
+pub enum TouchpadAction {
+    Enable,
+    Disable,
+    Toggle,
+    ToggleTapToClick,
+    ToggleNaturalScrolling,
+    /// Moves to the next scroll method the touchpad supports.
+    CycleScrollMethod,
+    /// Moves to the next of the `sensitivity_levels`.
+    CycleSensitivity,
+    /// Switches the trackpoint managed next to the touchpad.
+    ToggleTrackpoint,
+    /// Toggles the touchpad and sets the trackpoint to match.
+    ToggleBoth,
+    /// Switches between fully enabled and enabled with taps disabled.
+    ToggleTapsDisabled,
+    /// Enables for this many seconds, then disables again.
+    EnableTemporarily(u64),
+    /// Keeps a temporarily enabled touchpad on.
+    CancelTemporaryEnable,
+}
+
+impl TouchpadAction {
+    /// Whether the action switches the touchpad itself, as opposed to a
+    /// device property.
+    pub fn changes_state(&self) -> bool {
+        matches!(
+            self,
+            TouchpadAction::Enable
+                | TouchpadAction::Disable
+                | TouchpadAction::Toggle
+                | TouchpadAction::ToggleBoth
+                | TouchpadAction::ToggleTapsDisabled
+                | TouchpadAction::EnableTemporarily(_)
+        )
+    }
+}
+
+/// Where a state-change request came from, kept for history logging.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[allow(dead_code)]
+pub enum ActionSource {
+    Hotkey,
+    Tray,
+    Frontend,
+    Cli,
+    Schedule,
+    Auto,
+    Startup,
+    /// The `auto_reenable_after_minutes` safeguard.
+    Safeguard,
+    /// The end of an `EnableTemporarily` period.
+    TemporaryEnable,
+    /// Changed outside the app, e.g. by the hardware touchpad key.
+    External,
+    /// Announced by another machine, see `network_sync`.
+    Network,
+    /// Re-applying our state after the system woke from sleep.
+    Resume,
+    /// The corner hot zone, see `corner_toggle`.
+    Corner,
+}
+
+impl ActionSource {
+    /// Sources acting without a user request, suppressed in safe mode. The
+    /// safeguard only ever re-enables, so it stays on as a safety net.
+    pub fn is_automatic(&self) -> bool {
+        matches!(self, ActionSource::Schedule | ActionSource::Auto | ActionSource::Startup | ActionSource::Network | ActionSource::Resume)
+    }
+
+    /// Sources acting on a direct user request; these win over auto-toggles.
+    pub fn is_manual(&self) -> bool {
+        matches!(self, ActionSource::Hotkey | ActionSource::Tray | ActionSource::Frontend | ActionSource::Cli | ActionSource::Corner)
+    }
+}
+
+#[derive(Debug)]
+pub struct ActionRequest {
+    pub action: TouchpadAction,
+    pub source: ActionSource,
+}
+
+/// A timer of the action worker that ran out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Timer {
+    /// A temporary enable is over.
+    Revert,
+    /// The touchpad has been disabled for `auto_reenable_after_minutes`.
+    Safeguard,
+}
+
+/// What the tray and OSD are told about a temporary enable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TemporaryEnableNotice {
+    Started(u64),
+    Cancelled,
+}
+
+/// What the action worker does with a request it took up.
+#[derive(Debug, PartialEq)]
+enum Admission {
+    /// Wait out the rest of the cooldown, then decide again.
+    Defer(Duration),
+    /// Ignore the request, for the given reason.
+    Drop(&'static str),
+    Apply,
+}
+
+/// Timers and bookkeeping of the action worker, apart from the manager so
+/// the ordering rules can be followed, and tested, on their own.
+#[derive(Debug, Default)]
+struct WorkerState {
+    last_applied: Option<Instant>,
+    // When the safeguard re-enables a touchpad left disabled
+    reenable_at: Option<Instant>,
+    // When a temporary enable reverts to disabled
+    revert_at: Option<Instant>,
+    // When the user last changed the state by hand
+    last_manual: Option<Instant>,
+    // Requests drained from the channel but not yet applied
+    queued: VecDeque<ActionRequest>,
+}
+
+impl WorkerState {
+    fn deadline(&self) -> Option<Instant> {
+        match (self.reenable_at, self.revert_at) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Clears and returns the timer that is due at `now`, the revert first.
+    fn take_expired(&mut self, now: Instant) -> Option<Timer> {
+        if self.revert_at.is_some_and(|at| at <= now) {
+            self.revert_at = None;
+            return Some(Timer::Revert);
+        }
+        if self.reenable_at.is_some_and(|at| at <= now) {
+            self.reenable_at = None;
+            return Some(Timer::Safeguard);
+        }
+        None
+    }
+
+    /// How much of the cooldown after the last applied action is left.
+    fn cooldown_remaining(&self, now: Instant, cooldown: Duration) -> Duration {
+        self.last_applied
+            .map_or(Duration::ZERO, |last| cooldown.saturating_sub(now.saturating_duration_since(last)))
+    }
+
+    /// Manual always wins: a manual request waiting behind automatic ones
+    /// cancels them. Returns whether `request` itself is superseded.
+    fn supersede_auto(&mut self, request: &ActionRequest) -> bool {
+        if !self.queued.iter().any(|queued| queued.source.is_manual()) {
+            return false;
+        }
+        let before = self.queued.len();
+        self.queued.retain(|queued| queued.source != ActionSource::Auto);
+        if before != self.queued.len() {
+            info!("Dropped {} pending auto-toggle(s) superseded by a manual action", before - self.queued.len());
+        }
+        request.source == ActionSource::Auto
+    }
+
+    /// User intent sticks for `sticky` after a manual change.
+    fn in_manual_override(&self, request: &ActionRequest, now: Instant, sticky: Duration) -> bool {
+        request.source == ActionSource::Auto
+            && self.last_manual.is_some_and(|at| now.saturating_duration_since(at) < sticky)
+    }
+
+    /// Decides about `request` at `now`. The cooldown only ever defers, so a
+    /// manual request right after an automatic one is never lost to it.
+    fn admit(&mut self, request: &ActionRequest, now: Instant, cooldown: Duration, sticky: Duration) -> Admission {
+        let remaining = self.cooldown_remaining(now, cooldown);
+        if !remaining.is_zero() {
+            return Admission::Defer(remaining);
+        }
+        if self.supersede_auto(request) {
+            return Admission::Drop("superseded by a manual action");
+        }
+        if self.in_manual_override(request, now, sticky) {
+            return Admission::Drop("within manual override window");
+        }
+        Admission::Apply
+    }
+
+    /// Updates the timers once `request` has been applied at `now`, taking
+    /// the touchpad from `before` to `state`. `safeguard` is how long a new
+    /// disable may last, if the safeguard is on.
+    fn applied(
+        &mut self,
+        request: &ActionRequest,
+        now: Instant,
+        before: TouchpadState,
+        state: TouchpadState,
+        safeguard: Option<Duration>,
+    ) -> Option<TemporaryEnableNotice> {
+        self.last_applied = Some(now);
+        if request.source.is_manual() && request.action.changes_state() {
+            self.last_manual = Some(now);
+        }
+
+        let mut notice = None;
+        match request.action {
+            // Already on before: turning it off later would be a surprise
+            TouchpadAction::EnableTemporarily(_) if before == TouchpadState::Enabled => {
+                info!("Touchpad already enabled, nothing to revert");
+            }
+            TouchpadAction::EnableTemporarily(seconds) if state == TouchpadState::Enabled => {
+                match now.checked_add(Duration::from_secs(seconds)) {
+                    Some(revert_at) => {
+                        self.revert_at = Some(revert_at);
+                        notice = Some(TemporaryEnableNotice::Started(seconds));
+                    }
+                    None => warn!("Not scheduling a revert {}s from now: out of range", seconds),
+                }
+            }
+            TouchpadAction::CancelTemporaryEnable if self.revert_at.take().is_some() => {
+                notice = Some(TemporaryEnableNotice::Cancelled);
+            }
+            // Any other state change is a manual override of a temporary enable
+            TouchpadAction::Enable
+            | TouchpadAction::Disable
+            | TouchpadAction::Toggle
+            | TouchpadAction::ToggleBoth
+            | TouchpadAction::ToggleTapsDisabled
+                if request.source != ActionSource::TemporaryEnable && self.revert_at.take().is_some() =>
+            {
+                info!("Temporary enable cancelled by {:?}", request.source);
+            }
+            _ => {}
+        }
+
+        // Any enable clears the safeguard, any new disable restarts it
+        match request.action {
+            TouchpadAction::ToggleTapToClick
+            | TouchpadAction::ToggleNaturalScrolling
+            | TouchpadAction::CycleScrollMethod
+            | TouchpadAction::CycleSensitivity
+            | TouchpadAction::ToggleTrackpoint
+            | TouchpadAction::CancelTemporaryEnable => {}
+            _ if state == TouchpadState::Disabled => {
+                self.reenable_at = safeguard.and_then(|after| now.checked_add(after));
+                if let Some(after) = safeguard.filter(|_| self.reenable_at.is_some()) {
+                    info!("Touchpad will be re-enabled in {:?} unless a mouse is present", after);
+                }
+            }
+            _ => self.reenable_at = None,
+        }
+        notice
+    }
+}
+
+pub fn start_action_worker(manager: Arc<HotkeyManager>, rx: Receiver<ActionRequest>) {
+    supervisor::spawn(WORKER_NAME, move |token| {
+        let mut worker = WorkerState::default();
+        loop {
+            manager.set_timer_deadlines(worker.reenable_at, worker.revert_at);
+            let received = match worker.queued.pop_front() {
+                Some(request) => Ok(request),
+                None => {
+                    let timer = worker.deadline().map(at).unwrap_or_else(never);
+                    select! {
+                        recv(token.channel()) -> _ => break,
+                        recv(rx) -> request => request.map_err(|_| RecvTimeoutError::Disconnected),
+                        recv(timer) -> _ => Err(RecvTimeoutError::Timeout),
+                    }
+                }
+            };
+            let request = match received {
+                Ok(request) => request,
+                Err(RecvTimeoutError::Timeout) => match worker.take_expired(Instant::now()) {
+                    Some(Timer::Revert) => {
+                        ActionRequest { action: TouchpadAction::Disable, source: ActionSource::TemporaryEnable }
+                    }
+                    Some(Timer::Safeguard) => {
+                        if manager.tracked_state() != TouchpadState::Disabled {
+                            continue;
+                        }
+                        if mouse_detector::is_mouse_connected() {
+                            // Still have a mouse, check again after another period
+                            worker.reenable_at =
+                                safeguard_period(&manager).and_then(|after| Instant::now().checked_add(after));
+                            continue;
+                        }
+                        ActionRequest { action: TouchpadAction::Enable, source: ActionSource::Safeguard }
+                    }
+                    None => continue,
+                },
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            let settings = manager.settings();
+            let cooldown = Duration::from_millis(settings.toggle_cooldown_ms);
+            let sticky = Duration::from_millis(settings.manual_override_ms);
+            // Includes whatever arrived during the cooldown
+            worker.queued.extend(rx.try_iter());
+            let admission = loop {
+                match worker.admit(&request, Instant::now(), cooldown, sticky) {
+                    Admission::Defer(remaining) => {
+                        info!("Deferring {:?} from {:?} by {:?} for the cooldown", request.action, request.source, remaining);
+                        if !token.sleep(remaining) {
+                            return;
+                        }
+                        worker.queued.extend(rx.try_iter());
+                    }
+                    admission => break admission,
+                }
+            };
+            if let Admission::Drop(reason) = admission {
+                info!("Ignoring {:?} from {:?}: {}", request.action, request.source, reason);
+                continue;
+            }
+
+            info!("Applying {:?} requested by {:?}", request.action, request.source);
+            let before = manager.tracked_state();
+            let went_ahead = match request.action {
+                TouchpadAction::Enable => manager.handle_hotkey_set(TouchpadState::Enabled, request.source),
+                TouchpadAction::Disable => manager.handle_hotkey_set(TouchpadState::Disabled, request.source),
+                TouchpadAction::Toggle => manager.handle_hotkey_toggle(request.source),
+                TouchpadAction::ToggleBoth => manager.handle_toggle_both(request.source),
+                TouchpadAction::EnableTemporarily(_) => {
+                    manager.handle_hotkey_set(TouchpadState::Enabled, request.source)
+                }
+                TouchpadAction::ToggleTrackpoint => {
+                    manager.handle_trackpoint_toggle();
+                    true
+                }
+                TouchpadAction::ToggleTapToClick => {
+                    manager.handle_tap_to_click_toggle();
+                    true
+                }
+                TouchpadAction::ToggleNaturalScrolling => {
+                    manager.handle_natural_scrolling_toggle();
+                    true
+                }
+                TouchpadAction::CycleScrollMethod => {
+                    manager.handle_scroll_method_cycle();
+                    true
+                }
+                TouchpadAction::CycleSensitivity => {
+                    manager.handle_sensitivity_cycle();
+                    true
+                }
+                TouchpadAction::ToggleTapsDisabled => {
+                    manager.handle_taps_disabled_toggle(request.source);
+                    true
+                }
+                TouchpadAction::CancelTemporaryEnable => true,
+            };
+            // An unconfirmed first press changes nothing: no cooldown, no
+            // manual override, and a temporary enable keeps running
+            if !went_ahead {
+                continue;
+            }
+
+            let state = manager.tracked_state();
+            match worker.applied(&request, Instant::now(), before, state, safeguard_period(&manager)) {
+                Some(TemporaryEnableNotice::Started(seconds)) => manager.notify_temporary_enable(Some(seconds)),
+                Some(TemporaryEnableNotice::Cancelled) => manager.notify_temporary_enable(None),
+                None => {}
+            }
+        }
+    });
+}
+
+// How long the touchpad may stay disabled before the safeguard steps in
+fn safeguard_period(manager: &HotkeyManager) -> Option<Duration> {
+    let minutes = manager.settings().auto_reenable_after_minutes?;
+    minutes.checked_mul(60).map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(action: TouchpadAction, source: ActionSource) -> ActionRequest {
+        ActionRequest { action, source }
+    }
+
+    #[test]
+    fn cooldown_defers_until_it_has_passed() {
+        let start = Instant::now();
+        let mut worker = WorkerState::default();
+        let cooldown = Duration::from_millis(200);
+        assert_eq!(worker.cooldown_remaining(start, cooldown), Duration::ZERO);
+
+        worker.applied(&request(TouchpadAction::Toggle, ActionSource::Tray), start, TouchpadState::Enabled, TouchpadState::Disabled, None);
+        let remaining = worker.cooldown_remaining(start + Duration::from_millis(50), cooldown);
+        assert_eq!(remaining, Duration::from_millis(150));
+        assert_eq!(worker.cooldown_remaining(start + cooldown, cooldown), Duration::ZERO);
+    }
+
+    #[test]
+    fn manual_action_right_after_an_auto_toggle_is_deferred_not_dropped() {
+        let start = Instant::now();
+        let (cooldown, sticky) = (Duration::from_millis(200), Duration::from_secs(5));
+        let mut worker = WorkerState::default();
+        worker.applied(&request(TouchpadAction::Disable, ActionSource::Auto), start, TouchpadState::Enabled, TouchpadState::Disabled, None);
+
+        let toggle = request(TouchpadAction::Toggle, ActionSource::Hotkey);
+        let at = start + Duration::from_millis(50);
+        assert_eq!(worker.admit(&toggle, at, cooldown, sticky), Admission::Defer(Duration::from_millis(150)));
+        assert_eq!(worker.admit(&toggle, at + Duration::from_millis(150), cooldown, sticky), Admission::Apply);
+        worker.applied(&toggle, start + cooldown, TouchpadState::Disabled, TouchpadState::Enabled, None);
+
+        // and the user's intent then sticks against the next auto-toggle
+        let auto = request(TouchpadAction::Disable, ActionSource::Auto);
+        assert!(matches!(worker.admit(&auto, start + cooldown * 2, cooldown, sticky), Admission::Drop(_)));
+        assert_eq!(worker.admit(&auto, start + cooldown + sticky, cooldown, sticky), Admission::Apply);
+    }
+
+    #[test]
+    fn unconfirmed_disable_press_leaves_the_worker_untouched() {
+        use crate::core::press_confirm::PressConfirmation;
+
+        let start = Instant::now();
+        let (cooldown, sticky, window) = (Duration::from_millis(200), Duration::from_secs(5), Duration::from_millis(500));
+        let mut worker = WorkerState::default();
+        let temporary = request(TouchpadAction::EnableTemporarily(60), ActionSource::Auto);
+        worker.applied(&temporary, start, TouchpadState::Disabled, TouchpadState::Enabled, None);
+        let mut confirmation = PressConfirmation::default();
+
+        // First press: prompts only, like the worker skipping bookkeeping
+        let disable = request(TouchpadAction::Disable, ActionSource::Hotkey);
+        let first = start + Duration::from_secs(1);
+        assert_eq!(worker.admit(&disable, first, cooldown, sticky), Admission::Apply);
+        assert!(!confirmation.press(first, window));
+        assert!(worker.revert_at.is_some());
+        assert_eq!(worker.last_manual, None);
+        assert_eq!(worker.last_applied, Some(start));
+        // so an auto-toggle isn't held off by a manual override that never happened
+        let auto = request(TouchpadAction::Enable, ActionSource::Auto);
+        assert_eq!(worker.admit(&auto, first + Duration::from_millis(100), cooldown, sticky), Admission::Apply);
+
+        // Second press within the window: applied, with all the bookkeeping
+        let second = first + Duration::from_millis(300);
+        assert_eq!(worker.admit(&disable, second, cooldown, sticky), Admission::Apply);
+        assert!(confirmation.press(second, window));
+        worker.applied(&disable, second, TouchpadState::Enabled, TouchpadState::Disabled, None);
+        assert_eq!(worker.revert_at, None);
+        assert_eq!(worker.last_manual, Some(second));
+        assert!(matches!(worker.admit(&auto, second + cooldown, cooldown, sticky), Admission::Drop(_)));
+    }
+
+    #[test]
+    fn queued_manual_request_supersedes_auto_toggles() {
+        let mut worker = WorkerState::default();
+        worker.queued.push_back(request(TouchpadAction::Disable, ActionSource::Auto));
+        worker.queued.push_back(request(TouchpadAction::Enable, ActionSource::Hotkey));
+
+        assert!(worker.supersede_auto(&request(TouchpadAction::Enable, ActionSource::Auto)));
+        assert_eq!(worker.queued.len(), 1);
+        assert_eq!(worker.queued[0].source, ActionSource::Hotkey);
+        assert!(!worker.supersede_auto(&request(TouchpadAction::Toggle, ActionSource::Tray)));
+    }
+
+    #[test]
+    fn safeguard_fires_after_a_short_timer() {
+        let start = Instant::now();
+        let period = Duration::from_millis(30);
+        let mut worker = WorkerState::default();
+        worker.applied(&request(TouchpadAction::Disable, ActionSource::Hotkey), start, TouchpadState::Enabled, TouchpadState::Disabled, Some(period));
+        assert_eq!(worker.deadline(), Some(start + period));
+        assert_eq!(worker.take_expired(start + Duration::from_millis(10)), None);
+        assert_eq!(worker.take_expired(start + period), Some(Timer::Safeguard));
+        assert_eq!(worker.deadline(), None);
+
+        // A re-enable in the meantime cancels it
+        worker.applied(&request(TouchpadAction::Disable, ActionSource::Hotkey), start, TouchpadState::Enabled, TouchpadState::Disabled, Some(period));
+        worker.applied(&request(TouchpadAction::Enable, ActionSource::Tray), start, TouchpadState::Disabled, TouchpadState::Enabled, Some(period));
+        assert_eq!(worker.take_expired(start + period), None);
+    }
+
+    #[test]
+    fn safeguard_in_the_cooldown_is_deferred_not_dropped() {
+        let start = Instant::now();
+        let cooldown = Duration::from_millis(200);
+        let mut worker = WorkerState::default();
+        worker.applied(&request(TouchpadAction::Disable, ActionSource::Hotkey), start, TouchpadState::Enabled, TouchpadState::Disabled, None);
+
+        let safeguard = request(TouchpadAction::Enable, ActionSource::Safeguard);
+        assert!(!worker.cooldown_remaining(start, cooldown).is_zero());
+        assert!(!worker.supersede_auto(&safeguard));
+        assert!(!worker.in_manual_override(&safeguard, start, Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn overlong_safeguard_is_not_armed() {
+        let mut worker = WorkerState::default();
+        let disable = request(TouchpadAction::Disable, ActionSource::Hotkey);
+        worker.applied(&disable, Instant::now(), TouchpadState::Enabled, TouchpadState::Disabled, Some(Duration::MAX));
+        assert_eq!(worker.reenable_at, None);
+    }
+
+    #[test]
+    fn temporary_enable_reverts_unless_overridden() {
+        use TouchpadState::{Disabled, Enabled};
+        let start = Instant::now();
+        let temporary = request(TouchpadAction::EnableTemporarily(1), ActionSource::Tray);
+
+        let mut worker = WorkerState::default();
+        let notice = worker.applied(&temporary, start, Disabled, Enabled, None);
+        assert!(matches!(notice, Some(TemporaryEnableNotice::Started(1))));
+        assert_eq!(worker.take_expired(start + Duration::from_millis(500)), None);
+        assert_eq!(worker.take_expired(start + Duration::from_secs(1)), Some(Timer::Revert));
+
+        // A manual toggle in the meantime cancels the revert
+        let mut worker = WorkerState::default();
+        worker.applied(&temporary, start, Disabled, Enabled, None);
+        worker.applied(&request(TouchpadAction::Toggle, ActionSource::Hotkey), start, Enabled, Disabled, None);
+        assert_eq!(worker.take_expired(start + Duration::from_secs(1)), None);
+
+        // Already enabled: nothing to turn back off
+        let mut worker = WorkerState::default();
+        assert!(worker.applied(&temporary, start, Enabled, Enabled, None).is_none());
+        assert_eq!(worker.deadline(), None);
+
+        let endless = request(TouchpadAction::EnableTemporarily(u64::MAX), ActionSource::Frontend);
+        assert!(worker.applied(&endless, start, Disabled, Enabled, None).is_none());
+        assert_eq!(worker.deadline(), None);
+    }
+}
@@ -0,0 +1,151 @@
+//! Corner hot zone: resting the pointer in a screen corner, leaving it and
+//! coming straight back toggles the touchpad, a last resort when stranded
+//! with only a mouse.
+//!
+//! The gesture is click-free, as a click in the corner would also reach
+//! whatever sits there, e.g. a maximized window's close button. Normal corner
+//! use (hot corners, flicking to a menu) doesn't linger and then return, so
+//! both a dwell and a return are required.
+
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use rdev::EventType;
+
+// Distance from the screen edges that still counts as the corner
+const ZONE_PX: f64 = 4.0;
+// Time after leaving a dwelled-in corner to come back and complete the gesture
+const RETURN_WINDOW: Duration = Duration::from_millis(1000);
+// The display size is read again after this, as resolution and monitors change
+const SCREEN_REFRESH: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[allow(dead_code)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    fn contains(&self, x: f64, y: f64, (width, height): (f64, f64)) -> bool {
+        let left = x <= ZONE_PX;
+        let right = x >= width - 1.0 - ZONE_PX;
+        let top = y <= ZONE_PX;
+        let bottom = y >= height - 1.0 - ZONE_PX;
+        match self {
+            Corner::TopLeft => top && left,
+            Corner::TopRight => top && right,
+            Corner::BottomLeft => bottom && left,
+            Corner::BottomRight => bottom && right,
+        }
+    }
+}
+
+/// Recognizes a dwell of at least `dwell` in `corner`, followed by leaving
+/// it and returning within `RETURN_WINDOW`.
+pub struct CornerDetector {
+    corner: Corner,
+    dwell: Duration,
+    screen_size: fn() -> Option<(f64, f64)>,
+    screen: Option<(f64, f64)>,
+    screen_read_at: Option<Instant>,
+    entered_at: Option<Instant>,
+    /// Deadline to return by, set when leaving after a full dwell.
+    return_by: Option<Instant>,
+}
+
+impl CornerDetector {
+    pub fn new(corner: Corner, dwell: Duration, screen_size: fn() -> Option<(f64, f64)>) -> Self {
+        Self {
+            corner,
+            dwell,
+            screen_size,
+            screen: None,
+            screen_read_at: None,
+            entered_at: None,
+            return_by: None,
+        }
+    }
+
+    fn screen(&mut self, now: Instant) -> Option<(f64, f64)> {
+        if self.screen_read_at.is_none_or(|read_at| now.duration_since(read_at) >= SCREEN_REFRESH) {
+            self.screen_read_at = Some(now);
+            if let Some(size) = (self.screen_size)() {
+                self.screen = Some(size);
+            }
+        }
+        self.screen
+    }
+
+    /// Feeds one input event; returns true when it completes the pattern.
+    pub fn feed(&mut self, event: &EventType, now: Instant) -> bool {
+        let EventType::MouseMove { x, y } = event else {
+            return false;
+        };
+        let Some(screen) = self.screen(now) else {
+            return false;
+        };
+        if self.corner.contains(*x, *y, screen) {
+            if self.entered_at.is_some() {
+                return false;
+            }
+            self.entered_at = Some(now);
+            // One toggle per return, dwell and leave again to toggle again
+            self.return_by.take().is_some_and(|deadline| now <= deadline)
+        } else {
+            if let Some(entered) = self.entered_at.take() {
+                if now.duration_since(entered) >= self.dwell {
+                    self.return_by = Some(now + RETURN_WINDOW);
+                }
+            }
+            false
+        }
+    }
+}
+
+/// Size of the main display, for locating the corners.
+pub fn screen_size() -> Option<(f64, f64)> {
+    match rdev::display_size() {
+        Ok((width, height)) => Some((width as f64, height as f64)),
+        Err(e) => {
+            log::warn!("Failed to read display size: {:?}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_hd() -> Option<(f64, f64)> {
+        Some((1920.0, 1080.0))
+    }
+
+    fn move_to(x: f64, y: f64) -> EventType {
+        EventType::MouseMove { x, y }
+    }
+
+    #[test]
+    fn dwell_then_return_toggles_once() {
+        let mut detector = CornerDetector::new(Corner::TopRight, Duration::from_millis(800), full_hd);
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // Passing through the corner is not enough
+        assert!(!detector.feed(&move_to(1919.0, 0.0), at(0)));
+        assert!(!detector.feed(&move_to(1800.0, 200.0), at(100)));
+        assert!(!detector.feed(&move_to(1919.0, 0.0), at(200)));
+
+        // Dwelling, leaving and coming back is
+        assert!(!detector.feed(&move_to(1800.0, 200.0), at(1100)));
+        assert!(detector.feed(&move_to(1919.0, 0.0), at(1500)));
+
+        // Coming back too late is not
+        assert!(!detector.feed(&move_to(1800.0, 200.0), at(2400)));
+        assert!(!detector.feed(&move_to(1919.0, 0.0), at(3500)));
+        assert!(!detector.feed(&move_to(0.0, 0.0), at(3600)));
+    }
+}
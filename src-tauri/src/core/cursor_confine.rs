@@ -0,0 +1,170 @@
+//! Keeps the cursor from getting lost while the touchpad is disabled and the
+//! user relies on mouse keys.
+//!
+//! Every path that re-enables input (touchpad enable, a mouse connecting,
+//! exit) must call `release`; it is idempotent.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use log::{info, warn};
+
+static CONFINED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the platform can confine the cursor at all.
+pub const SUPPORTED: bool = cfg!(not(target_os = "macos"));
+
+pub fn is_confined() -> bool {
+    CONFINED.load(Ordering::SeqCst)
+}
+
+/// Confines the cursor to the active window (Windows, X11). Not available
+/// on macOS, which has no confinement API.
+#[allow(dead_code)]
+pub fn confine() {
+    if is_confined() {
+        return;
+    }
+    match platform::confine() {
+        Ok(()) => {
+            CONFINED.store(true, Ordering::SeqCst);
+            info!("Cursor confined");
+        }
+        Err(e) => warn!("Failed to confine cursor: {}", e),
+    }
+}
+
+pub fn release() {
+    if !CONFINED.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    platform::release();
+    info!("Cursor confinement released");
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Foundation::RECT;
+    use windows::Win32::UI::WindowsAndMessaging::{ClipCursor, GetForegroundWindow, GetWindowRect};
+
+    pub fn confine() -> Result<(), String> {
+        unsafe {
+            let window = GetForegroundWindow();
+            let mut rect = RECT::default();
+            GetWindowRect(window, &mut rect).map_err(|e| e.to_string())?;
+            ClipCursor(Some(&rect)).map_err(|e| e.to_string())
+        }
+    }
+
+    pub fn release() {
+        unsafe {
+            let _ = ClipCursor(None);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    // Detaching the cursor from the pointer (CGAssociateMouseAndMouseCursorPosition)
+    // freezes it rather than confining it, and there is no clip API
+    pub fn confine() -> Result<(), String> {
+        Err("not supported on macOS".to_string())
+    }
+
+    pub fn release() {}
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::ptr;
+    use std::sync::Mutex;
+    use x11::{xfixes, xlib};
+    use crate::core::state::lock_recovering;
+
+    // Barriers block the pointer at the window edges without grabbing it, so
+    // other applications keep receiving pointer events. They last as long as
+    // this connection stays open.
+    struct Barriers {
+        display: *mut xlib::Display,
+        barriers: Vec<xfixes::PointerBarrier>,
+    }
+    unsafe impl Send for Barriers {}
+
+    static BARRIERS: Mutex<Option<Barriers>> = Mutex::new(None);
+
+    pub fn confine() -> Result<(), String> {
+        unsafe {
+            let display = xlib::XOpenDisplay(ptr::null());
+            if display.is_null() {
+                return Err("no X display".to_string());
+            }
+            match create_barriers(display) {
+                Ok(barriers) => {
+                    xlib::XFlush(display);
+                    *lock_recovering(&BARRIERS) = Some(Barriers { display, barriers });
+                    Ok(())
+                }
+                Err(e) => {
+                    xlib::XCloseDisplay(display);
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    unsafe fn create_barriers(display: *mut xlib::Display) -> Result<Vec<xfixes::PointerBarrier>, String> {
+        let (mut event_base, mut error_base) = (0, 0);
+        let (mut major, minor) = (5, 0);
+        // Pointer barriers arrived in XFixes 5
+        if xfixes::XFixesQueryExtension(display, &mut event_base, &mut error_base) == 0
+            || xfixes::XFixesQueryVersion(display, &mut major, &minor) == 0
+            || major < 5
+        {
+            return Err("the X server lacks pointer barriers (XFixes 5)".to_string());
+        }
+
+        let root = xlib::XDefaultRootWindow(display);
+        let mut focus = 0;
+        let mut revert = 0;
+        xlib::XGetInputFocus(display, &mut focus, &mut revert);
+        if focus <= 1 || focus == root {
+            return Err("no focused window".to_string());
+        }
+        let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+        if xlib::XGetWindowAttributes(display, focus, &mut attributes) == 0 {
+            return Err("the focused window is gone".to_string());
+        }
+        let (mut x, mut y, mut child) = (0, 0, 0);
+        xlib::XTranslateCoordinates(display, focus, root, 0, 0, &mut x, &mut y, &mut child);
+        let (right, bottom) = (x + attributes.width - 1, y + attributes.height - 1);
+
+        // Bring the cursor inside first, or the barriers would keep it out
+        let (mut root_return, mut child_return) = (0, 0);
+        let (mut cursor_x, mut cursor_y, mut win_x, mut win_y, mut mask) = (0, 0, 0, 0, 0);
+        xlib::XQueryPointer(
+            display, root, &mut root_return, &mut child_return,
+            &mut cursor_x, &mut cursor_y, &mut win_x, &mut win_y, &mut mask,
+        );
+        if cursor_x < x || cursor_x > right || cursor_y < y || cursor_y > bottom {
+            xlib::XWarpPointer(display, 0, root, 0, 0, 0, 0, x + attributes.width / 2, y + attributes.height / 2);
+        }
+
+        let edges = [(x, y, right, y), (x, bottom, right, bottom), (x, y, x, bottom), (right, y, right, bottom)];
+        Ok(edges
+            .iter()
+            .map(|&(x1, y1, x2, y2)| {
+                // No allowed directions: blocks crossing either way, for every device
+                xfixes::XFixesCreatePointerBarrier(display, root, x1, y1, x2, y2, 0, 0, ptr::null_mut())
+            })
+            .collect())
+    }
+
+    pub fn release() {
+        if let Some(Barriers { display, barriers }) = lock_recovering(&BARRIERS).take() {
+            unsafe {
+                for barrier in barriers {
+                    xfixes::XFixesDestroyPointerBarrier(display, barrier);
+                }
+                xlib::XCloseDisplay(display);
+            }
+        }
+    }
+}
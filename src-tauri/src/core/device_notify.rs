@@ -0,0 +1,235 @@
+//! Event-driven pointing device and power notifications on Windows.
+//!
+//! A hidden message-only window receives `WM_DEVICECHANGE` for mouse device
+//! interfaces, so attach/detach is noticed without polling. A second one
+//! receives `WM_POWERBROADCAST`, so a resume from sleep is noticed as it
+//! happens.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use log::{info, warn};
+use windows::core::{w, PCWSTR};
+use windows::Win32::Devices::HumanInterfaceDevice::GUID_DEVINTERFACE_MOUSE;
+use windows::Win32::Foundation::{HANDLE, HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::Power::{RegisterSuspendResumeNotification, UnregisterSuspendResumeNotification};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetMessageW, PostMessageW,
+    PostQuitMessage, RegisterClassW, RegisterDeviceNotificationW, TranslateMessage,
+    UnregisterDeviceNotification, DBT_DEVICEARRIVAL, DBT_DEVICEREMOVECOMPLETE,
+    DBT_DEVTYP_DEVICEINTERFACE, DEVICE_NOTIFY_WINDOW_HANDLE, DEV_BROADCAST_DEVICEINTERFACE_W,
+    HWND_MESSAGE, MSG, PBT_APMRESUMEAUTOMATIC, WINDOW_EX_STYLE, WINDOW_STYLE, WM_CLOSE, WM_DESTROY,
+    WM_DEVICECHANGE, WM_POWERBROADCAST, WNDCLASSW,
+};
+
+// Message-only windows of the running listeners, 0 when stopped
+static LISTENER_WINDOW: AtomicIsize = AtomicIsize::new(0);
+static POWER_WINDOW: AtomicIsize = AtomicIsize::new(0);
+
+thread_local! {
+    // Each listener runs on its own thread, so this is that listener's callback
+    static ON_CHANGE: RefCell<Option<Box<dyn FnMut()>>> = RefCell::new(None);
+}
+
+/// What a listener window is registered for.
+#[derive(Clone, Copy)]
+enum Listener {
+    Devices,
+    Power,
+}
+
+impl Listener {
+    fn window(self) -> &'static AtomicIsize {
+        match self {
+            Listener::Devices => &LISTENER_WINDOW,
+            Listener::Power => &POWER_WINDOW,
+        }
+    }
+
+    fn class_name(self) -> PCWSTR {
+        match self {
+            Listener::Devices => w!("EnableTouchpadDeviceListener"),
+            Listener::Power => w!("EnableTouchpadPowerListener"),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Listener::Devices => "Device change",
+            Listener::Power => "Power",
+        }
+    }
+}
+
+/// Starts the listener on its own thread, calling `on_change` there whenever a
+/// mouse is attached or removed. Returns false if it couldn't be set up.
+pub fn start(on_change: impl FnMut() + Send + 'static) -> bool {
+    start_listener(Listener::Devices, on_change)
+}
+
+/// Closes the listener window and ends its message loop.
+pub fn stop() {
+    stop_listener(Listener::Devices);
+}
+
+/// Starts the power listener on its own thread, calling `on_resume` there
+/// when the system resumes from sleep. Returns false if it couldn't be set up.
+pub fn start_power(on_resume: impl FnMut() + Send + 'static) -> bool {
+    start_listener(Listener::Power, on_resume)
+}
+
+/// Closes the power listener window and ends its message loop.
+pub fn stop_power() {
+    stop_listener(Listener::Power);
+}
+
+fn start_listener(listener: Listener, on_change: impl FnMut() + Send + 'static) -> bool {
+    let (ready_tx, ready_rx) = crossbeam::channel::bounded(1);
+    std::thread::spawn(move || {
+        ON_CHANGE.with(|cell| *cell.borrow_mut() = Some(Box::new(on_change)));
+        let ok = unsafe { run_message_loop(listener, &ready_tx) };
+        if !ok {
+            let _ = ready_tx.send(false);
+        }
+    });
+    ready_rx.recv().unwrap_or(false)
+}
+
+fn stop_listener(listener: Listener) {
+    let hwnd = listener.window().swap(0, Ordering::SeqCst);
+    if hwnd == 0 {
+        return;
+    }
+    if let Err(e) = unsafe { PostMessageW(HWND(hwnd), WM_CLOSE, WPARAM(0), LPARAM(0)) } {
+        warn!("Failed to stop {} listener: {}", listener.label().to_lowercase(), e);
+    }
+}
+
+// Registers `hwnd` for the listener's notifications; returns what undoes it
+unsafe fn register(listener: Listener, hwnd: HWND) -> windows::core::Result<Box<dyn FnOnce()>> {
+    match listener {
+        Listener::Devices => {
+            // Only pointer HID interfaces, not every device in the system
+            let mut filter = DEV_BROADCAST_DEVICEINTERFACE_W {
+                dbcc_size: std::mem::size_of::<DEV_BROADCAST_DEVICEINTERFACE_W>() as u32,
+                dbcc_devicetype: DBT_DEVTYP_DEVICEINTERFACE.0,
+                dbcc_classguid: GUID_DEVINTERFACE_MOUSE,
+                ..Default::default()
+            };
+            let notification = RegisterDeviceNotificationW(
+                HANDLE(hwnd.0),
+                &mut filter as *mut _ as *const _,
+                DEVICE_NOTIFY_WINDOW_HANDLE,
+            )?;
+            Ok(Box::new(move || {
+                if let Err(e) = unsafe { UnregisterDeviceNotification(notification) } {
+                    warn!("Failed to unregister device notifications: {}", e);
+                }
+            }))
+        }
+        // Message-only windows get no broadcasts, so ask for power ones explicitly
+        Listener::Power => {
+            let notification = RegisterSuspendResumeNotification(HANDLE(hwnd.0), DEVICE_NOTIFY_WINDOW_HANDLE)?;
+            Ok(Box::new(move || {
+                if let Err(e) = unsafe { UnregisterSuspendResumeNotification(notification) } {
+                    warn!("Failed to unregister power notifications: {}", e);
+                }
+            }))
+        }
+    }
+}
+
+unsafe fn run_message_loop(listener: Listener, ready: &crossbeam::channel::Sender<bool>) -> bool {
+    let Ok(instance) = GetModuleHandleW(None) else {
+        return false;
+    };
+    let class_name = listener.class_name();
+    let class = WNDCLASSW {
+        lpfnWndProc: Some(window_proc),
+        hInstance: instance.into(),
+        lpszClassName: class_name,
+        ..Default::default()
+    };
+    if RegisterClassW(&class) == 0 {
+        warn!("Failed to register {} listener window class", listener.label().to_lowercase());
+        return false;
+    }
+
+    let hwnd = CreateWindowExW(
+        WINDOW_EX_STYLE(0),
+        class_name,
+        w!(""),
+        WINDOW_STYLE(0),
+        0,
+        0,
+        0,
+        0,
+        HWND_MESSAGE,
+        None,
+        instance,
+        None,
+    );
+    if hwnd.0 == 0 {
+        warn!("Failed to create {} listener window", listener.label().to_lowercase());
+        return false;
+    }
+
+    let unregister = match register(listener, hwnd) {
+        Ok(unregister) => unregister,
+        Err(e) => {
+            warn!("Failed to register for {} notifications: {}", listener.label().to_lowercase(), e);
+            let _ = DestroyWindow(hwnd);
+            return false;
+        }
+    };
+
+    listener.window().store(hwnd.0, Ordering::SeqCst);
+    let _ = ready.send(true);
+    info!("{} listener started", listener.label());
+
+    let mut msg = MSG::default();
+    while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+        let _ = TranslateMessage(&msg);
+        DispatchMessageW(&msg);
+    }
+
+    unregister();
+    info!("{} listener stopped", listener.label());
+    true
+}
+
+fn notify() {
+    ON_CHANGE.with(|cell| {
+        if let Some(on_change) = cell.borrow_mut().as_mut() {
+            on_change();
+        }
+    });
+}
+
+unsafe extern "system" fn window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_DEVICECHANGE => {
+            let event = wparam.0 as u32;
+            if event == DBT_DEVICEARRIVAL || event == DBT_DEVICEREMOVECOMPLETE {
+                notify();
+            }
+            LRESULT(1)
+        }
+        // Sent on every resume, whether or not a user is present
+        WM_POWERBROADCAST => {
+            if wparam.0 as u32 == PBT_APMRESUMEAUTOMATIC {
+                notify();
+            }
+            LRESULT(1)
+        }
+        WM_CLOSE => {
+            let _ = DestroyWindow(hwnd);
+            LRESULT(0)
+        }
+        WM_DESTROY => {
+            PostQuitMessage(0);
+            LRESULT(0)
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
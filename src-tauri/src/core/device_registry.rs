@@ -0,0 +1,186 @@
+//! Typed view of the pointing devices reported by the backend.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+pub enum DeviceKind {
+    Touchpad,
+    Trackpoint,
+    Mouse,
+    /// Virtual or auxiliary pointers such as XTEST or consumer control keys.
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[allow(dead_code)]
+pub struct Device {
+    pub id: String,
+    pub name: String,
+    pub kind: DeviceKind,
+}
+
+/// Classifies a pointing device by its name.
+pub fn classify(name: &str) -> DeviceKind {
+    let lower = name.to_lowercase();
+    if ["virtual", "xtest", "consumer control"].iter().any(|s| lower.contains(s)) {
+        DeviceKind::Other
+    } else if ["touchpad", "trackpad", "glidepoint"].iter().any(|s| lower.contains(s)) {
+        DeviceKind::Touchpad
+    } else if ["trackpoint", "pointing stick"].iter().any(|s| lower.contains(s)) {
+        DeviceKind::Trackpoint
+    } else {
+        DeviceKind::Mouse
+    }
+}
+
+/// Picks the device to manage and a trackpoint to manage next to it.
+///
+/// A touchpad is preferred over a trackpoint listed before it. When both are
+/// present, as on ThinkPads, the trackpoint is returned separately so it can
+/// be switched on its own; without a touchpad it becomes the primary device.
+pub fn primary_and_trackpoint(devices: &[Device]) -> (Option<Device>, Option<Device>) {
+    let touchpad = devices.iter().find(|device| device.kind == DeviceKind::Touchpad).cloned();
+    let trackpoint = devices.iter().find(|device| device.kind == DeviceKind::Trackpoint).cloned();
+    match touchpad {
+        Some(touchpad) => (Some(touchpad), trackpoint),
+        None => (trackpoint, None),
+    }
+}
+
+/// Parses `xinput --list` into the slave pointer devices, in listed order.
+///
+/// Master devices and everything under the keyboard group are skipped:
+/// ```text
+/// ⎡ Virtual core pointer                        id=2    [master pointer  (3)]
+/// ⎜   ↳ SynPS/2 Synaptics TouchPad                  id=11    [slave  pointer  (2)]
+/// ⎣ Virtual core keyboard                       id=3    [master keyboard (2)]
+/// ```
+pub fn parse_xinput_list(output: &str) -> Vec<Device> {
+    output
+        .lines()
+        .filter(|line| line.contains("[slave  pointer"))
+        .filter_map(|line| {
+            let (name, rest) = line.split_once("id=")?;
+            let id: String = rest.chars().take_while(char::is_ascii_digit).collect();
+            if id.is_empty() {
+                return None;
+            }
+            let name = name
+                .trim_matches(|c: char| c.is_whitespace() || matches!(c, '⎜' | '↳' | '⎡' | '⎣' | '∼'))
+                .to_string();
+            Some(Device { kind: classify(&name), id, name })
+        })
+        .collect()
+}
+
+/// A kernel input device as listed in `/proc/bus/input/devices`.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+pub struct InputDevice {
+    pub name: String,
+    /// Path below `/sys`, e.g. "/devices/platform/i8042/serio1/input/input12".
+    pub sysfs: String,
+    pub kind: DeviceKind,
+}
+
+/// Parses `/proc/bus/input/devices`, one blank-line separated block per device:
+/// ```text
+/// N: Name="SynPS/2 Synaptics TouchPad"
+/// S: Sysfs=/devices/platform/i8042/serio1/input/input12
+/// H: Handlers=mouse0 event11
+/// ```
+#[allow(dead_code)]
+pub fn parse_proc_input_devices(contents: &str) -> Vec<InputDevice> {
+    contents
+        .split("\n\n")
+        .filter_map(|block| {
+            let mut name = None;
+            let mut sysfs = None;
+            for line in block.lines() {
+                if let Some(value) = line.strip_prefix("N: Name=") {
+                    name = Some(value.trim().trim_matches('"').to_string());
+                } else if let Some(value) = line.strip_prefix("S: Sysfs=") {
+                    sysfs = Some(value.trim().to_string());
+                }
+            }
+            let name = name?;
+            Some(InputDevice { kind: classify(&name), name, sysfs: sysfs? })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAPTOP_WITH_DOCK: &str = "\
+⎡ Virtual core pointer                    \tid=2\t[master pointer  (3)]
+⎜   ↳ Virtual core XTEST pointer              \tid=4\t[slave  pointer  (2)]
+⎜   ↳ ELAN0676:00 04F3:3195 Touchpad          \tid=12\t[slave  pointer  (2)]
+⎜   ↳ ELAN0676:00 04F3:3195 Mouse             \tid=11\t[slave  pointer  (2)]
+⎜   ↳ Souris sans fil – Éditions Ω            \tid=17\t[slave  pointer  (2)]
+⎜   ↳ Logitech USB Receiver Consumer Control  \tid=15\t[slave  pointer  (2)]
+⎣ Virtual core keyboard                   \tid=3\t[master keyboard (2)]
+    ↳ Virtual core XTEST keyboard             \tid=5\t[slave  keyboard (3)]
+    ↳ Logitech USB Receiver                   \tid=14\t[slave  keyboard (3)]
+∼ Wacom One Pen stylus                      \tid=20\t[floating slave]";
+
+    #[test]
+    fn parses_slave_pointers_in_listed_order() {
+        let devices = parse_xinput_list(LAPTOP_WITH_DOCK);
+        let ids: Vec<&str> = devices.iter().map(|device| device.id.as_str()).collect();
+        assert_eq!(ids, ["4", "12", "11", "17", "15"]);
+        assert_eq!(devices[1].name, "ELAN0676:00 04F3:3195 Touchpad");
+        assert_eq!(devices[1].kind, DeviceKind::Touchpad);
+    }
+
+    #[test]
+    fn classifies_virtual_and_unicode_devices() {
+        let devices = parse_xinput_list(LAPTOP_WITH_DOCK);
+        let kind_of = |id: &str| devices.iter().find(|device| device.id == id).map(|device| device.kind);
+        assert_eq!(kind_of("4"), Some(DeviceKind::Other));
+        assert_eq!(kind_of("15"), Some(DeviceKind::Other));
+        assert_eq!(kind_of("11"), Some(DeviceKind::Mouse));
+        let unicode = devices.iter().find(|device| device.id == "17").unwrap();
+        assert_eq!(unicode.name, "Souris sans fil – Éditions Ω");
+        assert_eq!(unicode.kind, DeviceKind::Mouse);
+    }
+
+    const THINKPAD: &str = "\
+⎡ Virtual core pointer                    \tid=2\t[master pointer  (3)]
+⎜   ↳ Virtual core XTEST pointer              \tid=4\t[slave  pointer  (2)]
+⎜   ↳ TPPS/2 Elan TrackPoint                  \tid=13\t[slave  pointer  (2)]
+⎜   ↳ Synaptics TM3276-022                    \tid=11\t[slave  pointer  (2)]
+⎜   ↳ SynPS/2 Synaptics TouchPad              \tid=12\t[slave  pointer  (2)]
+⎣ Virtual core keyboard                   \tid=3\t[master keyboard (2)]
+    ↳ ThinkPad Extra Buttons                  \tid=15\t[slave  keyboard (3)]";
+
+    #[test]
+    fn thinkpad_touchpad_is_primary_with_the_trackpoint_apart() {
+        let devices = parse_xinput_list(THINKPAD);
+        let (primary, trackpoint) = primary_and_trackpoint(&devices);
+        // Listed first, but the touchpad still wins
+        assert_eq!(primary.map(|device| device.id), Some("12".to_string()));
+        assert_eq!(trackpoint.map(|device| device.name), Some("TPPS/2 Elan TrackPoint".to_string()));
+
+        // Without the touchpad the trackpoint is the device to manage
+        let without_touchpad: Vec<Device> = devices.into_iter().filter(|device| device.id != "12").collect();
+        let (primary, trackpoint) = primary_and_trackpoint(&without_touchpad);
+        assert_eq!(primary.map(|device| device.id), Some("13".to_string()));
+        assert_eq!(trackpoint, None);
+
+        assert_eq!(primary_and_trackpoint(&parse_xinput_list(LAPTOP_WITH_DOCK)).1, None);
+    }
+
+    #[test]
+    fn skips_masters_keyboards_and_floating_devices() {
+        let devices = parse_xinput_list(LAPTOP_WITH_DOCK);
+        for id in ["2", "3", "5", "14", "20"] {
+            assert!(devices.iter().all(|device| device.id != id), "id {} should be skipped", id);
+        }
+        assert!(parse_xinput_list("").is_empty());
+        assert!(parse_xinput_list("⎜   ↳ Broken line without an id\t[slave  pointer  (2)]").is_empty());
+    }
+}
@@ -0,0 +1,26 @@
+//! Packs the reports maintainers ask for into one zip file.
+
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Writes each `(name, contents)` entry to a zip at `path`, adding a ".zip"
+/// extension if it has none. Returns the path written. An existing file is
+/// never overwritten.
+pub fn write_bundle(path: &Path, entries: &[(&str, String)]) -> Result<PathBuf, String> {
+    let path = if path.extension().is_some() { path.to_path_buf() } else { path.with_extension("zip") };
+    let file = OpenOptions::new().write(true).create_new(true).open(&path).map_err(|e| match e.kind() {
+        ErrorKind::AlreadyExists => format!("{} already exists", path.display()),
+        _ => format!("Failed to create {}: {}", path.display(), e),
+    })?;
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, contents) in entries {
+        zip.start_file(*name, options).map_err(|e| e.to_string())?;
+        zip.write_all(contents.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(path)
+}
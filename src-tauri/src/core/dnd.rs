@@ -0,0 +1,45 @@
+//! Best-effort detection of the OS "Do Not Disturb" / Focus state.
+//!
+//! Every probe returns `None` when the state can't be determined, in which
+//! case callers should behave as if DND is off.
+
+#[cfg(target_os = "macos")]
+pub fn is_dnd_active() -> Option<bool> {
+    // Focus modes record active assertions in this file (macOS 12+)
+    let home = std::env::var_os("HOME")?;
+    let path = std::path::Path::new(&home).join("Library/DoNotDisturb/DB/Assertions.json");
+    let contents = std::fs::read_to_string(path).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    let records = json.get("data")?.get(0)?.get("storeAssertionRecords");
+    Some(records.and_then(|r| r.as_array()).map_or(false, |r| !r.is_empty()))
+}
+
+#[cfg(target_os = "windows")]
+pub fn is_dnd_active() -> Option<bool> {
+    use windows::Win32::UI::Shell::{
+        SHQueryUserNotificationState, QUNS_ACCEPTS_NOTIFICATIONS, QUNS_APP, QUNS_NOT_PRESENT,
+    };
+
+    let state = unsafe { SHQueryUserNotificationState() }.ok()?;
+    Some(state != QUNS_ACCEPTS_NOTIFICATIONS && state != QUNS_NOT_PRESENT && state != QUNS_APP)
+}
+
+#[cfg(target_os = "linux")]
+pub fn is_dnd_active() -> Option<bool> {
+    use std::process::{Command, Stdio};
+
+    // GNOME-based desktops expose notification inhibition as "show-banners"
+    let output = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "false" => Some(true),
+        "true" => Some(false),
+        _ => None,
+    }
+}
@@ -0,0 +1,87 @@
+//! Double-tap of a lone modifier (e.g. Ctrl, Ctrl) as a hotkey alternative.
+
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use rdev::{EventType, Key};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+pub enum DoubleTapModifier {
+    Ctrl,
+    Alt,
+    Shift,
+    Super,
+}
+
+impl DoubleTapModifier {
+    fn matches(&self, key: &Key) -> bool {
+        match self {
+            DoubleTapModifier::Ctrl => matches!(key, Key::ControlLeft | Key::ControlRight),
+            DoubleTapModifier::Alt => matches!(key, Key::Alt | Key::AltGr),
+            DoubleTapModifier::Shift => matches!(key, Key::ShiftLeft | Key::ShiftRight),
+            DoubleTapModifier::Super => matches!(key, Key::MetaLeft | Key::MetaRight),
+        }
+    }
+}
+
+/// Recognizes two isolated taps of a modifier within `window`.
+///
+/// A tap is a press and release of the modifier alone, shorter than `window`.
+/// Any other key in between (e.g. Ctrl+C) resets the pattern, so normal
+/// modifier use never fires.
+pub struct DoubleTapDetector {
+    modifier: DoubleTapModifier,
+    window: Duration,
+    pressed_at: Option<Instant>,
+    last_tap: Option<Instant>,
+}
+
+impl DoubleTapDetector {
+    pub fn new(modifier: DoubleTapModifier, window: Duration) -> Self {
+        Self {
+            modifier,
+            window,
+            pressed_at: None,
+            last_tap: None,
+        }
+    }
+
+    /// Feeds one key event; returns true when it completes a double-tap.
+    pub fn feed(&mut self, event: &EventType, now: Instant) -> bool {
+        match event {
+            EventType::KeyPress(key) if self.modifier.matches(key) => {
+                // Auto-repeat while held keeps the original press time
+                if self.pressed_at.is_none() {
+                    self.pressed_at = Some(now);
+                }
+                false
+            }
+            EventType::KeyRelease(key) if self.modifier.matches(key) => {
+                let Some(pressed_at) = self.pressed_at.take() else {
+                    return false;
+                };
+                if now.duration_since(pressed_at) > self.window {
+                    self.last_tap = None;
+                    return false;
+                }
+                match self.last_tap {
+                    Some(last) if now.duration_since(last) <= self.window => {
+                        self.last_tap = None;
+                        true
+                    }
+                    _ => {
+                        self.last_tap = Some(now);
+                        false
+                    }
+                }
+            }
+            EventType::KeyPress(_) | EventType::KeyRelease(_) => {
+                self.pressed_at = None;
+                self.last_tap = None;
+                false
+            }
+            _ => false,
+        }
+    }
+}
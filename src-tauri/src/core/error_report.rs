@@ -0,0 +1,95 @@
+//! Local-only error report.
+//!
+//! Controller and permission failures are appended as compact JSON lines to a
+//! file in the app data directory that users can inspect and share if they
+//! choose to. Nothing here is ever sent anywhere: this is not telemetry. Records
+//! only carry a timestamp, the platform, the backend and the error kind, never
+//! device names, paths or other user data.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use log::warn;
+use crate::core::input_controller::{ControllerError, BACKEND};
+
+const REPORT_FILE: &str = "error-report.jsonl";
+
+static REPORT_PATH: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Serialize)]
+struct ErrorRecord<'a> {
+    timestamp: u64,
+    platform: &'a str,
+    backend: &'a str,
+    kind: &'a str,
+}
+
+/// Sets the directory the report file lives in; records are dropped until then.
+pub fn init(dir: PathBuf) {
+    match REPORT_PATH.lock() {
+        Ok(mut path) => *path = Some(dir.join(REPORT_FILE)),
+        Err(e) => warn!("Failed to acquire report path lock: {}", e),
+    }
+}
+
+pub fn report_path() -> Option<PathBuf> {
+    match REPORT_PATH.lock() {
+        Ok(path) => path.clone(),
+        Err(e) => {
+            warn!("Failed to acquire report path lock: {}", e);
+            None
+        }
+    }
+}
+
+pub fn record(kind: &str) {
+    let Some(path) = report_path() else {
+        return;
+    };
+
+    let record = ErrorRecord {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        platform: std::env::consts::OS,
+        backend: BACKEND,
+        kind,
+    };
+
+    let line = match serde_json::to_string(&record) {
+        Ok(line) => line,
+        Err(e) => {
+            warn!("Failed to serialize error record: {}", e);
+            return;
+        }
+    };
+
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = result {
+        warn!("Failed to write error report: {}", e);
+    }
+}
+
+pub fn record_controller_error(error: &ControllerError) {
+    // Debug output of the variant is the error kind, e.g. "Timeout"
+    record(&format!("{:?}", error));
+}
+
+pub fn clear() -> std::io::Result<()> {
+    match report_path() {
+        Some(path) if path.exists() => fs::remove_file(path),
+        _ => Ok(()),
+    }
+}
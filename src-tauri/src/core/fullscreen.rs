@@ -0,0 +1,329 @@
+//! Disables the touchpad while the focused window is fullscreen, e.g. a game
+//! or a video, and puts it back afterwards.
+//!
+//! On several monitors "fullscreen" means filling the monitor the window is
+//! on, not the primary one. Monitors come from the same enumeration the
+//! settings window uses to stay on screen, re-read on every poll, so a
+//! monitor being plugged in or removed is noticed: the touchpad is restored
+//! then, and the rule applies again if the window still fills its monitor.
+
+use std::sync::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+use log::{info, warn};
+use crate::core::command_queue::{ActionSource, TouchpadAction};
+use crate::core::hotkey_manager::HotkeyManager;
+use crate::core::settings::Settings;
+use crate::core::state::{lock_recovering, TouchpadState};
+use crate::core::supervisor;
+
+const WORKER_NAME: &str = "fullscreen-watch";
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Whether the focused window's bounds can be read on this platform.
+pub const SUPPORTED: bool = cfg!(not(target_os = "macos"));
+
+static RUNNING: Mutex<bool> = Mutex::new(false);
+
+/// A rectangle in physical desktop coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    fn right(&self) -> i64 {
+        self.x as i64 + self.width as i64
+    }
+
+    fn bottom(&self) -> i64 {
+        self.y as i64 + self.height as i64
+    }
+
+    fn overlap_area(&self, other: &Rect) -> i64 {
+        let width = self.right().min(other.right()) - (self.x.max(other.x) as i64);
+        let height = self.bottom().min(other.bottom()) - (self.y.max(other.y) as i64);
+        if width <= 0 || height <= 0 {
+            0
+        } else {
+            width * height
+        }
+    }
+
+    fn covers(&self, other: &Rect) -> bool {
+        self.x <= other.x && self.y <= other.y && self.right() >= other.right() && self.bottom() >= other.bottom()
+    }
+}
+
+/// The monitor a window is on: the one it overlaps most, as the OS decides
+/// for a window spanning monitors.
+pub fn monitor_for<'a>(window: &Rect, monitors: &'a [Rect]) -> Option<&'a Rect> {
+    monitors
+        .iter()
+        .map(|monitor| (monitor, window.overlap_area(monitor)))
+        .filter(|(_, area)| *area > 0)
+        .max_by_key(|(_, area)| *area)
+        .map(|(monitor, _)| monitor)
+}
+
+/// Whether `window` fills the monitor it is on, which need not be the primary.
+pub fn is_fullscreen(window: &Rect, monitors: &[Rect]) -> bool {
+    monitor_for(window, monitors).is_some_and(|monitor| window.covers(monitor))
+}
+
+/// What the rule does to the touchpad after a poll.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuleChange {
+    Disable,
+    Restore,
+}
+
+/// Tracks whether the rule currently holds the touchpad disabled and the
+/// monitors it decided that on.
+#[derive(Debug, Default)]
+pub struct FullscreenRule {
+    active: bool,
+    monitors: Vec<Rect>,
+}
+
+impl FullscreenRule {
+    /// Feeds one poll: the focused window's bounds, the monitors and whether
+    /// the touchpad is enabled. Only a touchpad the rule disabled itself is
+    /// restored, and a monitor change while active always restores it.
+    pub fn update(&mut self, window: Option<Rect>, monitors: Vec<Rect>, touchpad_enabled: bool) -> Option<RuleChange> {
+        let monitors_changed = monitors != self.monitors;
+        self.monitors = monitors;
+        if self.active && monitors_changed {
+            self.active = false;
+            return Some(RuleChange::Restore);
+        }
+        let fullscreen = window.is_some_and(|window| is_fullscreen(&window, &self.monitors));
+        match (self.active, fullscreen) {
+            (false, true) if touchpad_enabled => {
+                self.active = true;
+                Some(RuleChange::Disable)
+            }
+            (true, false) => {
+                self.active = false;
+                Some(RuleChange::Restore)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+fn monitors(app: &AppHandle) -> Vec<Rect> {
+    app.available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|monitor| {
+            let (position, size) = (monitor.position(), monitor.size());
+            Rect { x: position.x, y: position.y, width: size.width, height: size.height }
+        })
+        .collect()
+}
+
+/// Starts or stops the watcher to match `settings`. Stopping it while it
+/// holds the touchpad disabled restores it.
+pub fn configure(app: &AppHandle, manager: &Arc<HotkeyManager>, settings: &Settings) {
+    let wanted = settings.disable_in_fullscreen && SUPPORTED;
+    let mut running = lock_recovering(&RUNNING);
+    if *running == wanted {
+        return;
+    }
+    supervisor::stop(WORKER_NAME);
+    *running = false;
+    if !wanted {
+        return;
+    }
+
+    let reader = match platform::FocusReader::open() {
+        Ok(reader) => reader,
+        Err(e) => {
+            warn!("Fullscreen rule unavailable: {}", e);
+            return;
+        }
+    };
+    info!("Watching for fullscreen windows");
+    *running = true;
+
+    let app = app.clone();
+    let manager = manager.clone();
+    supervisor::spawn(WORKER_NAME, move |token| {
+        let mut rule = FullscreenRule::default();
+        while token.sleep(POLL_INTERVAL) {
+            let enabled = manager.tracked_state() == TouchpadState::Enabled;
+            match rule.update(reader.focused_window(), monitors(&app), enabled) {
+                Some(RuleChange::Disable) => {
+                    info!("Focused window is fullscreen, disabling the touchpad");
+                    manager.request(TouchpadAction::Disable, ActionSource::Auto);
+                }
+                Some(RuleChange::Restore) => {
+                    info!("Fullscreen ended or the monitors changed, restoring the touchpad");
+                    manager.request(TouchpadAction::Enable, ActionSource::Auto);
+                }
+                None => {}
+            }
+        }
+        if rule.is_active() {
+            manager.request(TouchpadAction::Enable, ActionSource::Auto);
+        }
+    });
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Foundation::RECT;
+    use windows::Win32::UI::WindowsAndMessaging::{GetDesktopWindow, GetForegroundWindow, GetShellWindow, GetWindowRect};
+    use super::Rect;
+
+    pub struct FocusReader;
+
+    impl FocusReader {
+        pub fn open() -> Result<Self, String> {
+            Ok(Self)
+        }
+
+        pub fn focused_window(&self) -> Option<Rect> {
+            unsafe {
+                let window = GetForegroundWindow();
+                // The desktop always fills its monitor but isn't fullscreen
+                if window.0 == 0 || window == GetShellWindow() || window == GetDesktopWindow() {
+                    return None;
+                }
+                let mut rect = RECT::default();
+                GetWindowRect(window, &mut rect).ok()?;
+                Some(Rect {
+                    x: rect.left,
+                    y: rect.top,
+                    width: (rect.right - rect.left).max(0) as u32,
+                    height: (rect.bottom - rect.top).max(0) as u32,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::ptr;
+    use x11::xlib;
+    use super::Rect;
+
+    /// Keeps one X connection for the watcher's lifetime.
+    pub struct FocusReader {
+        display: *mut xlib::Display,
+    }
+
+    // Only used from the watcher thread it is moved to
+    unsafe impl Send for FocusReader {}
+
+    impl FocusReader {
+        pub fn open() -> Result<Self, String> {
+            let display = unsafe { xlib::XOpenDisplay(ptr::null()) };
+            if display.is_null() {
+                return Err("no X display".to_string());
+            }
+            Ok(Self { display })
+        }
+
+        pub fn focused_window(&self) -> Option<Rect> {
+            unsafe {
+                let root = xlib::XDefaultRootWindow(self.display);
+                let (mut focus, mut revert) = (0, 0);
+                xlib::XGetInputFocus(self.display, &mut focus, &mut revert);
+                if focus <= 1 || focus == root {
+                    return None;
+                }
+                let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+                if xlib::XGetWindowAttributes(self.display, focus, &mut attributes) == 0 {
+                    return None;
+                }
+                let (mut x, mut y, mut child) = (0, 0, 0);
+                xlib::XTranslateCoordinates(self.display, focus, root, 0, 0, &mut x, &mut y, &mut child);
+                Some(Rect { x, y, width: attributes.width.max(0) as u32, height: attributes.height.max(0) as u32 })
+            }
+        }
+    }
+
+    impl Drop for FocusReader {
+        fn drop(&mut self) {
+            unsafe {
+                xlib::XCloseDisplay(self.display);
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+mod platform {
+    use super::Rect;
+
+    // No API for another app's window bounds without the screen recording
+    // permission
+    pub struct FocusReader;
+
+    impl FocusReader {
+        pub fn open() -> Result<Self, String> {
+            Err("not supported on this platform".to_string())
+        }
+
+        pub fn focused_window(&self) -> Option<Rect> {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: Rect = Rect { x: 0, y: 0, width: 1920, height: 1080 };
+    const RIGHT: Rect = Rect { x: 1920, y: 0, width: 2560, height: 1440 };
+
+    #[test]
+    fn fullscreen_is_judged_against_the_window_s_own_monitor() {
+        let monitors = [LEFT, RIGHT];
+        assert!(is_fullscreen(&RIGHT, &monitors));
+        // Fills the primary's size, but on the larger secondary monitor
+        let primary_sized = Rect { x: 1920, y: 0, width: 1920, height: 1080 };
+        assert!(!is_fullscreen(&primary_sized, &monitors));
+
+        // Spanning both: on the one it overlaps most, which it doesn't fill
+        let spanning = Rect { x: 1000, y: 0, width: 2000, height: 1080 };
+        assert_eq!(monitor_for(&spanning, &monitors), Some(&RIGHT));
+        assert!(!is_fullscreen(&spanning, &monitors));
+        // Stretched over every monitor counts as fullscreen
+        let everything = Rect { x: 0, y: 0, width: 4480, height: 1440 };
+        assert!(is_fullscreen(&everything, &monitors));
+        assert!(!is_fullscreen(&Rect { x: -5000, y: 0, width: 10, height: 10 }, &monitors));
+    }
+
+    #[test]
+    fn rule_restores_on_exit_and_on_monitor_change() {
+        let mut rule = FullscreenRule::default();
+        let two = vec![LEFT, RIGHT];
+        assert_eq!(rule.update(Some(LEFT), two.clone(), true), Some(RuleChange::Disable));
+        assert_eq!(rule.update(Some(LEFT), two.clone(), false), None);
+        assert_eq!(rule.update(None, two.clone(), false), Some(RuleChange::Restore));
+
+        // Already disabled by someone else: not ours to restore later
+        assert_eq!(rule.update(Some(LEFT), two.clone(), false), None);
+        assert_eq!(rule.update(None, two.clone(), false), None);
+
+        // The secondary is unplugged while a game fills it
+        assert_eq!(rule.update(Some(RIGHT), two.clone(), true), Some(RuleChange::Disable));
+        assert_eq!(rule.update(Some(RIGHT), vec![LEFT], false), Some(RuleChange::Restore));
+        assert!(!rule.is_active());
+        // and applies again once the window fills the remaining monitor
+        assert_eq!(rule.update(Some(LEFT), vec![LEFT], true), Some(RuleChange::Disable));
+    }
+}
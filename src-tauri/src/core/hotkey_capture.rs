@@ -0,0 +1,109 @@
+//! "Press the keys you want" capture for hotkey settings.
+
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use crossbeam::channel::{bounded, Sender};
+use once_cell::sync::Lazy;
+use rdev::{EventType, Key};
+use crate::core::accelerator::normalize_accelerator;
+use crate::core::key_listener;
+use crate::core::state::lock_recovering;
+
+enum CaptureMessage {
+    Captured(String),
+    Cancelled,
+}
+
+// Sender for the capture in progress, if any
+static ACTIVE: Lazy<Mutex<Option<Sender<CaptureMessage>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Modifier name for `key`, in accelerator order (lower sorts first).
+fn modifier(key: Key) -> Option<(u8, &'static str)> {
+    match key {
+        Key::ControlLeft | Key::ControlRight => Some((0, "Ctrl")),
+        Key::Alt | Key::AltGr => Some((1, "Alt")),
+        Key::ShiftLeft | Key::ShiftRight => Some((2, "Shift")),
+        Key::MetaLeft | Key::MetaRight => Some((3, "Super")),
+        _ => None,
+    }
+}
+
+fn key_name(key: Key) -> Option<String> {
+    let name = match key {
+        Key::KeyA => "A", Key::KeyB => "B", Key::KeyC => "C", Key::KeyD => "D",
+        Key::KeyE => "E", Key::KeyF => "F", Key::KeyG => "G", Key::KeyH => "H",
+        Key::KeyI => "I", Key::KeyJ => "J", Key::KeyK => "K", Key::KeyL => "L",
+        Key::KeyM => "M", Key::KeyN => "N", Key::KeyO => "O", Key::KeyP => "P",
+        Key::KeyQ => "Q", Key::KeyR => "R", Key::KeyS => "S", Key::KeyT => "T",
+        Key::KeyU => "U", Key::KeyV => "V", Key::KeyW => "W", Key::KeyX => "X",
+        Key::KeyY => "Y", Key::KeyZ => "Z",
+        Key::Num0 => "0", Key::Num1 => "1", Key::Num2 => "2", Key::Num3 => "3",
+        Key::Num4 => "4", Key::Num5 => "5", Key::Num6 => "6", Key::Num7 => "7",
+        Key::Num8 => "8", Key::Num9 => "9",
+        Key::F1 => "F1", Key::F2 => "F2", Key::F3 => "F3", Key::F4 => "F4",
+        Key::F5 => "F5", Key::F6 => "F6", Key::F7 => "F7", Key::F8 => "F8",
+        Key::F9 => "F9", Key::F10 => "F10", Key::F11 => "F11", Key::F12 => "F12",
+        Key::Space => "Space", Key::Tab => "Tab", Key::Return => "Enter",
+        Key::Backspace => "Backspace", Key::Delete => "Delete", Key::Insert => "Insert",
+        Key::Home => "Home", Key::End => "End", Key::PageUp => "PageUp", Key::PageDown => "PageDown",
+        Key::UpArrow => "Up", Key::DownArrow => "Down", Key::LeftArrow => "Left", Key::RightArrow => "Right",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// Waits for the next key combination and returns it as an accelerator such
+/// as "Ctrl+Shift+T". Modifier-only presses are ignored until a regular key
+/// joins them; Escape or `cancel` aborts.
+pub fn capture(timeout: Duration) -> Result<String, String> {
+    let (tx, rx) = bounded(1);
+    {
+        let mut active = lock_recovering(&ACTIVE);
+        if active.is_some() {
+            return Err("A hotkey capture is already in progress".to_string());
+        }
+        *active = Some(tx.clone());
+    }
+
+    let held: Mutex<BTreeSet<(u8, &'static str)>> = Mutex::new(BTreeSet::new());
+    let subscription = key_listener::subscribe(move |event| match *event {
+        EventType::KeyPress(Key::Escape) => {
+            let _ = tx.try_send(CaptureMessage::Cancelled);
+        }
+        EventType::KeyPress(key) => {
+            if let Some(m) = modifier(key) {
+                lock_recovering(&held).insert(m);
+            } else if let Some(name) = key_name(key) {
+                let held = lock_recovering(&held);
+                let mut parts: Vec<&str> = held.iter().map(|(_, name)| *name).collect();
+                parts.push(&name);
+                let accelerator = parts.join("+");
+                let accelerator = normalize_accelerator(&accelerator).unwrap_or(accelerator);
+                let _ = tx.try_send(CaptureMessage::Captured(accelerator));
+            }
+        }
+        EventType::KeyRelease(key) => {
+            if let Some(m) = modifier(key) {
+                lock_recovering(&held).remove(&m);
+            }
+        }
+        _ => {}
+    });
+
+    let result = match rx.recv_timeout(timeout) {
+        Ok(CaptureMessage::Captured(accelerator)) => Ok(accelerator),
+        Ok(CaptureMessage::Cancelled) => Err("Hotkey capture cancelled".to_string()),
+        Err(_) => Err(format!("No key pressed within {} seconds", timeout.as_secs())),
+    };
+
+    key_listener::unsubscribe(subscription);
+    *lock_recovering(&ACTIVE) = None;
+    result
+}
+
+pub fn cancel() {
+    if let Some(tx) = lock_recovering(&ACTIVE).as_ref() {
+        let _ = tx.try_send(CaptureMessage::Cancelled);
+    }
+}
@@ -1,16 +1,203 @@
-use std::sync::Arc;
-use crate::core::state::SharedState;
-use crate::core::input_controller::{TouchpadController, PlatformTouchpadController};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::core::state::{lock_recovering, safe_mode, PointerMode, SharedState, TouchpadState};
+use crate::core::input_controller::{clamp_sensitivity, find_in_path, next_sensitivity_level, ControllerError, DeviceInfo, ScrollMethod, TouchpadController, PlatformTouchpadController};
 use crate::core::mouse_emulator::MouseEmulator;
+use crate::core::error_report;
+use crate::core::accelerator::{normalize_accelerator, same_accelerator};
+use crate::core::command_queue::{ActionRequest, ActionSource, TouchpadAction};
+use crate::core::cursor_confine;
+use crate::core::corner_toggle::{self, CornerDetector};
+use crate::core::double_tap::DoubleTapDetector;
+use crate::core::press_confirm::PressConfirmation;
+use crate::core::key_listener;
+use crate::core::mouse_detector;
+use crate::core::settings::{DeviceLostAction, ExternalChangePolicy, Settings, StartupState, UnknownToggleTarget};
+use crate::core::resume;
+use crate::core::supervisor;
+use crate::core::webhook;
+use crate::window::toggle_settings_window;
 use crossbeam::channel::Sender;
-use log::{info, error};
+use serde::Serialize;
+use log::{debug, info, warn, error};
+use tauri::{Manager, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcut, GlobalShortcutExt, ShortcutState};
+
+// Larger movements since disable mean a working mouse moved the cursor
+const CURSOR_DRIFT_TOLERANCE: i32 = 48;
+// Upper bound for `benchmark_toggle`, each iteration switches the device twice
+pub const MAX_BENCHMARK_ITERATIONS: u32 = 50;
+// How often registered hotkeys are re-verified; doubled per consecutive
+// failed re-registration, up to 2^MAX_HOTKEY_CHECK_BACKOFF times
+const HOTKEY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const MAX_HOTKEY_CHECK_BACKOFF: u32 = 5;
+const DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+// A lost device's fallback re-detection skips 2^failures - 1 device checks
+// after each failure, up to 2^MAX_REDETECT_BACKOFF - 1
+const MAX_REDETECT_BACKOFF: u32 = 6;
+const STATE_RECONCILE_INTERVAL: Duration = Duration::from_secs(10);
 
 #[derive(Debug)]
 #[allow(dead_code)]
 pub enum HotkeyEvent {
-    TouchpadEnabled,
-    TouchpadDisabled,
+    /// `silent` changes (e.g. the startup state with `suppress_startup_osd`)
+    /// update the tray but skip the OSD and sound.
+    TouchpadEnabled { silent: bool },
+    TouchpadDisabled { silent: bool },
     PermissionNeeded,
+    HotkeysUnavailable(String),
+    ToggleFailed(String),
+    TapToClickChanged(bool),
+    /// Entered (`true`) or left the enabled-but-taps-disabled mode.
+    TapsDisabledChanged(bool),
+    /// Enabled for this many seconds; `None` when the revert was cancelled.
+    TemporarilyEnabled(Option<u64>),
+    NaturalScrollingChanged(bool),
+    ScrollMethodChanged(ScrollMethod),
+    /// Pointer speed set from `sensitivity_levels`: level index, level count
+    /// and the applied value.
+    SensitivityChanged { level: usize, levels: usize, value: f64 },
+    /// A hotkey disable needs a second press within the window to apply.
+    ConfirmDisable,
+    /// The separately managed trackpoint was switched on (`true`) or off.
+    TrackpointChanged(bool),
+    HotkeyConflict(HotkeyConflict),
+    /// The managed device was re-detected and may differ from before.
+    DeviceChanged(TouchpadState),
+    /// The managed device, by name, is no longer attached.
+    DeviceLost(String),
+}
+
+#[allow(dead_code)]
+impl HotkeyEvent {
+    /// The event for a state change applied on behalf of `source`.
+    fn state_changed(state: TouchpadState, source: ActionSource, settings: &Settings) -> Self {
+        let silent = source == ActionSource::Startup && settings.suppress_startup_osd;
+        if state == TouchpadState::Enabled {
+            HotkeyEvent::TouchpadEnabled { silent }
+        } else {
+            HotkeyEvent::TouchpadDisabled { silent }
+        }
+    }
+}
+
+/// A configured hotkey that could not be registered or is reserved by the OS.
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyConflict {
+    pub setting: String,
+    pub accelerator: String,
+    pub reason: String,
+}
+
+/// Latency of one controller operation over a benchmark run, in milliseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyStats {
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub avg_ms: f64,
+}
+
+impl LatencyStats {
+    fn from_samples(samples: &[Duration]) -> Self {
+        let ms: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        if ms.is_empty() {
+            return Self { min_ms: 0.0, max_ms: 0.0, avg_ms: 0.0 };
+        }
+        Self {
+            min_ms: ms.iter().copied().fold(f64::INFINITY, f64::min),
+            max_ms: ms.iter().copied().fold(0.0, f64::max),
+            avg_ms: ms.iter().sum::<f64>() / ms.len() as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkResult {
+    pub backend: String,
+    pub iterations: u32,
+    pub enable: LatencyStats,
+    pub disable: LatencyStats,
+}
+
+#[cfg(target_os = "windows")]
+const RESERVED_SHORTCUTS: &[(&str, &str)] = &[
+    ("Alt+Tab", "window switcher"),
+    ("Alt+F4", "close window"),
+    ("Ctrl+Alt+Delete", "security screen"),
+    ("Ctrl+Shift+Escape", "Task Manager"),
+    ("Super+L", "lock screen"),
+    ("Super+D", "show desktop"),
+    ("Super+E", "File Explorer"),
+    ("Super+R", "Run dialog"),
+    ("Super+Tab", "Task View"),
+];
+#[cfg(target_os = "macos")]
+const RESERVED_SHORTCUTS: &[(&str, &str)] = &[
+    ("Super+Space", "Spotlight"),
+    ("Super+Tab", "app switcher"),
+    ("Super+Q", "quit app"),
+    ("Super+H", "hide app"),
+    ("Super+M", "minimize window"),
+    ("Ctrl+Super+Q", "lock screen"),
+    ("Super+Shift+3", "screenshot"),
+    ("Super+Shift+4", "screenshot"),
+];
+#[cfg(target_os = "linux")]
+const RESERVED_SHORTCUTS: &[(&str, &str)] = &[
+    ("Alt+Tab", "window switcher"),
+    ("Alt+F4", "close window"),
+    ("Ctrl+Alt+T", "terminal"),
+    ("Ctrl+Alt+Delete", "log out"),
+    ("Super+L", "lock screen"),
+    ("Super+D", "show desktop"),
+    ("Super+A", "app grid"),
+];
+
+/// Why `accelerator` is known to be reserved on this platform, if it is.
+pub(crate) fn reserved_reason(accelerator: &str) -> Option<&'static str> {
+    RESERVED_SHORTCUTS
+        .iter()
+        .find(|(reserved, _)| same_accelerator(reserved, accelerator))
+        .map(|(_, reason)| *reason)
+}
+
+/// Each configured accelerator and whether it is currently registered.
+#[derive(Debug, Default)]
+struct HotkeyStatus(Vec<(String, bool)>);
+
+impl HotkeyStatus {
+    /// Replaces the status with the outcome of a registration pass.
+    fn registered(&mut self, outcome: Vec<(String, bool)>) {
+        self.0 = outcome;
+    }
+
+    /// Marks every hotkey inactive after the plugin released them all.
+    fn unregistered(&mut self) {
+        for (_, active) in self.0.iter_mut() {
+            *active = false;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    fn entries(&self) -> Vec<(String, bool)> {
+        self.0.clone()
+    }
+
+    /// Whether every hotkey that did register still is. Ones that failed
+    /// (conflicts) are left out, or the check could never pass.
+    #[allow(dead_code)]
+    fn intact(&self, is_registered: impl Fn(&str) -> bool) -> bool {
+        self.0.iter().filter(|(_, active)| *active).all(|(accelerator, _)| is_registered(accelerator))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum HotkeyAction {
+    Touchpad(TouchpadAction),
+    ToggleSettingsWindow,
 }
 
 #[allow(dead_code)]
@@ -19,6 +206,16 @@ pub struct HotkeyManager {
     touchpad_controller: Arc<PlatformTouchpadController>,
     mouse_emulator: Arc<MouseEmulator>,
     event_sender: Sender<HotkeyEvent>,
+    action_sender: Sender<ActionRequest>,
+    // Held while the device is being switched so diagnostics can't interleave with the action worker
+    action_lock: Mutex<()>,
+    // Key listener subscription for the double-tap binding, if enabled
+    double_tap_subscription: Mutex<Option<u64>>,
+    corner_subscription: Mutex<Option<u64>>,
+    // Each configured accelerator and whether it is currently registered
+    hotkey_status: Mutex<HotkeyStatus>,
+    // First press of a hotkey disable awaiting confirmation, see `double_press_to_disable`
+    disable_confirmation: Mutex<PressConfirmation>,
 }
 
 impl HotkeyManager {
@@ -26,61 +223,1256 @@ impl HotkeyManager {
         state: SharedState,
         touchpad_controller: Arc<PlatformTouchpadController>,
         mouse_emulator: Arc<MouseEmulator>,
-        event_sender: Sender<HotkeyEvent>
-    ) -> Self {
-        Self {
+        event_sender: Sender<HotkeyEvent>,
+        action_sender: Sender<ActionRequest>
+    ) -> Arc<Self> {
+        Arc::new(Self {
             state,
             touchpad_controller,
             mouse_emulator,
             event_sender,
+            action_sender,
+            action_lock: Mutex::new(()),
+            double_tap_subscription: Mutex::new(None),
+            corner_subscription: Mutex::new(None),
+            hotkey_status: Mutex::new(HotkeyStatus::default()),
+            disable_confirmation: Mutex::new(PressConfirmation::default()),
+        })
+    }
+
+    pub fn settings(&self) -> Settings {
+        self.state.get_settings()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.is_paused()
+    }
+
+    /// The last state applied or verified, without querying the backend.
+    pub fn tracked_state(&self) -> TouchpadState {
+        self.state.get_touchpad_state()
+    }
+
+    /// Raw backend query output; changes no state.
+    pub fn backend_diagnostics(&self) -> String {
+        let info = self.touchpad_controller.describe();
+        format!(
+            "Backend: {}\nDevice: {}\n\n{}",
+            info.backend,
+            info.name,
+            self.touchpad_controller.raw_diagnostics()
+        )
+    }
+
+    /// Re-detects the managed device and reports its current state.
+    pub fn redetect_device(&self) -> Result<DeviceInfo, ControllerError> {
+        self.try_redetect_device().inspect_err(|e| self.record_error(e))
+    }
+
+    // Not recorded as the last error, for the device watch's repeated attempts
+    fn try_redetect_device(&self) -> Result<DeviceInfo, ControllerError> {
+        let _guard = self.action_lock.lock();
+        let info = self.touchpad_controller.redetect_device()?;
+        self.state.set_device_name(info.name.clone());
+        let state = self.touchpad_controller.get_state().unwrap_or(self.state.get_touchpad_state());
+        self.state.set_touchpad_state(state);
+        if let Err(e) = self.event_sender.send(HotkeyEvent::DeviceChanged(state)) {
+            error!("Failed to send hotkey event: {}", e);
+        }
+        Ok(info)
+    }
+
+    pub fn can_disable_device(&self) -> bool {
+        self.touchpad_controller.can_disable_device()
+    }
+
+    /// The controller, for the async read-only calls made by commands.
+    pub fn controller(&self) -> Arc<PlatformTouchpadController> {
+        Arc::clone(&self.touchpad_controller)
+    }
+
+    /// Clamps `value` into the normalized range, applies it and returns what was set.
+    pub fn set_sensitivity(&self, value: f64) -> Result<f64, ControllerError> {
+        let _guard = self.action_lock.lock();
+        let value = clamp_sensitivity(value)?;
+        match self.touchpad_controller.set_sensitivity(value) {
+            Ok(()) => {
+                self.state.clear_last_error();
+                Ok(value)
+            }
+            Err(e) => {
+                self.record_error(&e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Mirrors the action worker's timers into the shared state for the tray.
+    pub fn set_timer_deadlines(&self, reenable_at: Option<Instant>, revert_at: Option<Instant>) {
+        self.state.set_timer_deadlines(reenable_at, revert_at);
+    }
+
+    /// Queues a state change; it is applied in order by the action worker.
+    pub fn request(&self, action: TouchpadAction, source: ActionSource) {
+        if source.is_automatic() && safe_mode() {
+            info!("Safe mode: ignoring {:?} from {:?}", action, source);
+            return;
+        }
+        if let Err(e) = self.action_sender.send(ActionRequest { action, source }) {
+            error!("Failed to queue touchpad action: {}", e);
         }
     }
-    
-    pub fn start(&self) {
-        // This is a placeholder implementation
-        // In a real implementation, we would register global hotkeys here
+
+    pub fn start(self: &Arc<Self>) {
+        self.register_hotkeys();
+        self.start_hotkey_check();
+        self.start_device_watch();
+        self.start_state_reconcile();
+        self.start_resume_watch();
         info!("Hotkey manager started");
     }
-    
-    pub fn handle_hotkey_toggle(&self) {
-        // Get current state and toggle
-        match self.touchpad_controller.get_state() {
-            Ok(current_state) => {
-                let result = if current_state == crate::core::state::TouchpadState::Enabled {
-                    self.touchpad_controller.disable()
-                } else {
-                    self.touchpad_controller.enable()
-                };
-                
-                match result {
-                    Ok(()) => {
-                        // Send event to OSD
-                        let new_state = self.touchpad_controller.get_state().unwrap_or(current_state);
-                        let event = if new_state == crate::core::state::TouchpadState::Enabled {
-                            HotkeyEvent::TouchpadEnabled
-                        } else {
-                            HotkeyEvent::TouchpadDisabled
-                        };
-                        
-                        if let Err(e) = self.event_sender.send(event) {
-                            error!("Failed to send hotkey event: {}", e);
-                        }
+
+    /// Periodically checks that the managed device is still attached and
+    /// applies `on_device_lost` when it disappears.
+    fn start_device_watch(self: &Arc<Self>) {
+        let manager = Arc::clone(self);
+        supervisor::spawn("device-watch", move |token| {
+            let mut lost = false;
+            let (mut redetect_failures, mut skipped_checks) = (0u32, 0u32);
+            while token.sleep(DEVICE_CHECK_INTERVAL) {
+                let present = manager.touchpad_controller.device_present();
+                let action = manager.settings().on_device_lost;
+                if present {
+                    (redetect_failures, skipped_checks) = (0, 0);
+                    if lost {
+                        lost = false;
+                        info!("{} is back", manager.state.get_device_name());
+                        manager.recover_device(action);
                     }
+                    continue;
+                }
+                if !lost {
+                    lost = true;
+                    let name = manager.state.get_device_name();
+                    warn!("{} is no longer attached, on_device_lost is {:?}", name, action);
+                    if let Err(e) = manager.event_sender.send(HotkeyEvent::DeviceLost(name)) {
+                        error!("Failed to send hotkey event: {}", e);
+                    }
+                }
+                // Fallback keeps trying until another device shows up, backing off
+                if action != DeviceLostAction::Fallback {
+                    continue;
+                }
+                if skipped_checks < 2u32.pow(redetect_failures.min(MAX_REDETECT_BACKOFF)) - 1 {
+                    skipped_checks += 1;
+                    continue;
+                }
+                skipped_checks = 0;
+                match manager.try_redetect_device() {
+                    Ok(_) => lost = false,
                     Err(e) => {
-                        error!("Failed to toggle touchpad: {:?}", e);
-                        // Send permission needed event
-                        if let Err(e) = self.event_sender.send(HotkeyEvent::PermissionNeeded) {
-                            error!("Failed to send permission event: {}", e);
+                        if redetect_failures == 0 {
+                            info!("No fallback device yet, retrying with backoff: {}", e);
                         }
+                        redetect_failures += 1;
                     }
                 }
             }
-            Err(e) => {
+        });
+    }
+
+    // The lost device is attached again
+    fn recover_device(&self, action: DeviceLostAction) {
+        if action != DeviceLostAction::Notify {
+            match self.redetect_device() {
+                Ok(_) => return,
+                Err(e) => warn!("Re-detection failed: {}", e),
+            }
+        }
+        let state = self.touchpad_controller.get_state().unwrap_or(self.state.get_touchpad_state());
+        self.state.set_touchpad_state(state);
+        if let Err(e) = self.event_sender.send(HotkeyEvent::DeviceChanged(state)) {
+            error!("Failed to send hotkey event: {}", e);
+        }
+    }
+
+    /// Periodically re-registers hotkeys that were dropped, e.g. when the
+    /// desktop environment grabbed a shortcut after a settings change.
+    fn start_hotkey_check(self: &Arc<Self>) {
+        let manager = Arc::clone(self);
+        supervisor::spawn("hotkey-check", move |token| {
+            let mut failures = 0;
+            while token.sleep(HOTKEY_CHECK_INTERVAL * 2u32.pow(failures.min(MAX_HOTKEY_CHECK_BACKOFF))) {
+                if manager.hotkeys_intact() {
+                    failures = 0;
+                    continue;
+                }
+                info!("Some hotkeys are no longer registered, re-registering");
+                manager.update_hotkeys();
+                if manager.hotkeys_intact() {
+                    info!("Hotkeys re-registered");
+                    failures = 0;
+                } else {
+                    failures += 1;
+                    warn!("Hotkey re-registration failed {} time(s) in a row", failures);
+                }
+            }
+        });
+    }
+
+    // Whether every registered accelerator is still held by the plugin
+    fn hotkeys_intact(&self) -> bool {
+        let Some(shortcuts) = self.state.app_handle.try_state::<GlobalShortcut<Wry>>() else {
+            return true;
+        };
+        lock_recovering(&self.hotkey_status).intact(|accelerator| shortcuts.is_registered(accelerator))
+    }
+
+    /// Re-registers all hotkeys from the current settings.
+    pub fn update_hotkeys(self: &Arc<Self>) {
+        if let Some(shortcuts) = self.state.app_handle.try_state::<GlobalShortcut<Wry>>() {
+            match shortcuts.unregister_all() {
+                Ok(()) => lock_recovering(&self.hotkey_status).unregistered(),
+                Err(e) => warn!("Failed to unregister hotkeys: {}", e),
+            }
+        }
+        self.register_hotkeys();
+    }
+
+    /// Releases every hotkey, global shortcuts and low-level listeners alike, on exit.
+    pub fn unregister_hotkeys(&self) {
+        if let Some(shortcuts) = self.state.app_handle.try_state::<GlobalShortcut<Wry>>() {
+            if let Err(e) = shortcuts.unregister_all() {
+                warn!("Failed to unregister hotkeys: {}", e);
+            }
+        }
+        for subscription in [&self.double_tap_subscription, &self.corner_subscription] {
+            if let Some(id) = lock_recovering(subscription).take() {
+                key_listener::unsubscribe(id);
+            }
+        }
+        // Also keeps the periodic check from re-registering them
+        lock_recovering(&self.hotkey_status).clear();
+    }
+
+    /// Each configured hotkey and whether it is currently active.
+    pub fn hotkey_status(&self) -> Vec<(String, bool)> {
+        lock_recovering(&self.hotkey_status).entries()
+    }
+
+    fn register_double_tap(self: &Arc<Self>, settings: &Settings) {
+        let mut subscription = lock_recovering(&self.double_tap_subscription);
+        if let Some(id) = subscription.take() {
+            key_listener::unsubscribe(id);
+        }
+        let Some(modifier) = settings.double_tap_modifier else {
+            return;
+        };
+
+        let detector = Mutex::new(DoubleTapDetector::new(
+            modifier,
+            Duration::from_millis(settings.double_tap_window_ms),
+        ));
+        let manager = Arc::clone(self);
+        *subscription = Some(key_listener::subscribe(move |event| {
+            if lock_recovering(&detector).feed(event, Instant::now()) {
+                manager.request(TouchpadAction::Toggle, ActionSource::Hotkey);
+            }
+        }));
+        info!("Double-tap {:?} bound to toggle", modifier);
+    }
+
+    fn register_corner_toggle(self: &Arc<Self>, settings: &Settings) {
+        let mut subscription = lock_recovering(&self.corner_subscription);
+        if let Some(id) = subscription.take() {
+            key_listener::unsubscribe(id);
+        }
+        let Some(corner) = settings.corner_toggle else {
+            return;
+        };
+        let detector = Mutex::new(CornerDetector::new(
+            corner,
+            Duration::from_millis(settings.corner_toggle_dwell_ms),
+            corner_toggle::screen_size,
+        ));
+        let manager = Arc::clone(self);
+        *subscription = Some(key_listener::subscribe(move |event| {
+            if lock_recovering(&detector).feed(event, Instant::now()) {
+                manager.request(TouchpadAction::Toggle, ActionSource::Corner);
+            }
+        }));
+        info!("Corner {:?} bound to toggle", corner);
+    }
+
+    fn register_hotkeys(self: &Arc<Self>) {
+        let app_handle = &self.state.app_handle;
+        // Independent of the global shortcut plugin
+        let settings = self.state.get_settings();
+        self.register_double_tap(&settings);
+        self.register_corner_toggle(&settings);
+
+        // The plugin is registered at runtime in setup; if that failed there is
+        // nothing managed to register shortcuts against.
+        if app_handle.try_state::<GlobalShortcut<Wry>>().is_none() {
+            lock_recovering(&self.hotkey_status).clear();
+            self.report_hotkeys_unavailable("global shortcut plugin is not initialized".to_string());
+            return;
+        }
+
+        let bindings = [
+            ("enable_hotkey", settings.enable_hotkey, HotkeyAction::Touchpad(TouchpadAction::Enable)),
+            ("disable_hotkey", settings.disable_hotkey, HotkeyAction::Touchpad(TouchpadAction::Disable)),
+            ("settings_hotkey", settings.settings_hotkey, HotkeyAction::ToggleSettingsWindow),
+            ("tap_to_click_hotkey", settings.tap_to_click_hotkey, HotkeyAction::Touchpad(TouchpadAction::ToggleTapToClick)),
+            ("natural_scrolling_hotkey", settings.natural_scrolling_hotkey, HotkeyAction::Touchpad(TouchpadAction::ToggleNaturalScrolling)),
+            ("taps_disabled_hotkey", settings.taps_disabled_hotkey, HotkeyAction::Touchpad(TouchpadAction::ToggleTapsDisabled)),
+            ("scroll_method_hotkey", settings.scroll_method_hotkey, HotkeyAction::Touchpad(TouchpadAction::CycleScrollMethod)),
+            ("sensitivity_hotkey", settings.sensitivity_hotkey, HotkeyAction::Touchpad(TouchpadAction::CycleSensitivity)),
+            ("trackpoint_hotkey", settings.trackpoint_hotkey, HotkeyAction::Touchpad(TouchpadAction::ToggleTrackpoint)),
+            ("toggle_both_hotkey", settings.toggle_both_hotkey, HotkeyAction::Touchpad(TouchpadAction::ToggleBoth)),
+        ];
+
+        let mut conflicts = Vec::new();
+        let mut status = Vec::new();
+        let mut registered = 0;
+        let mut configured = 0;
+        for (setting, accelerator, action) in bindings {
+            if accelerator.is_empty() {
+                continue;
+            }
+            configured += 1;
+            // Settings are normalized on save, but a hand-edited file may not be
+            let accelerator = normalize_accelerator(&accelerator).unwrap_or(accelerator);
+
+            // Registration can succeed for OS-reserved combos that then never
+            // fire, so warn about known ones up front
+            if let Some(reason) = reserved_reason(&accelerator) {
+                conflicts.push(HotkeyConflict {
+                    setting: setting.to_string(),
+                    accelerator: accelerator.clone(),
+                    reason: format!("this shortcut is taken by the system ({})", reason),
+                });
+            }
+
+            let manager = Arc::clone(self);
+            let result = app_handle.global_shortcut().on_shortcut(
+                accelerator.as_str(),
+                move |app, _shortcut, event| {
+                    if event.state() != ShortcutState::Pressed {
+                        return;
+                    }
+                    match action {
+                        HotkeyAction::Touchpad(action) => manager.request(action, ActionSource::Hotkey),
+                        HotkeyAction::ToggleSettingsWindow => toggle_settings_window(app),
+                    }
+                },
+            );
+
+            status.push((accelerator.clone(), result.is_ok()));
+            match result {
+                Ok(()) => registered += 1,
+                Err(e) => {
+                    warn!("Failed to register hotkey {}: {}", accelerator, e);
+                    conflicts.push(HotkeyConflict {
+                        setting: setting.to_string(),
+                        accelerator: accelerator.clone(),
+                        reason: format!("this shortcut is taken by the system or another app ({})", e),
+                    });
+                }
+            }
+        }
+
+        // Re-registration repeats known conflicts; only report new ones
+        let known = self.state.hotkey_conflicts();
+        let is_new = |conflict: &&HotkeyConflict| {
+            !known.iter().any(|k| k.setting == conflict.setting && k.accelerator == conflict.accelerator)
+        };
+        for conflict in conflicts.iter().filter(is_new) {
+            if let Err(e) = self.event_sender.send(HotkeyEvent::HotkeyConflict(conflict.clone())) {
+                error!("Failed to send hotkey event: {}", e);
+            }
+        }
+        self.state.set_hotkey_conflicts(conflicts);
+        lock_recovering(&self.hotkey_status).registered(status);
+
+        if configured > 0 && registered == 0 {
+            self.report_hotkeys_unavailable("no hotkey could be registered".to_string());
+        } else {
+            self.state.set_hotkeys_available(true);
+        }
+    }
+
+    fn report_hotkeys_unavailable(&self, reason: String) {
+        warn!("Global hotkeys unavailable: {}; use the tray menu to toggle the touchpad", reason);
+        self.state.set_hotkeys_available(false);
+        if let Err(e) = self.event_sender.send(HotkeyEvent::HotkeysUnavailable(reason)) {
+            error!("Failed to send hotkey event: {}", e);
+        }
+    }
+
+    // Errors go to the local report and stay queryable until the next success
+    fn record_error(&self, error: &ControllerError) {
+        error_report::record_controller_error(error);
+        self.state.set_last_error(error.clone());
+    }
+
+    // The handle_* methods are run by the action worker only; every other
+    // entry point queues through `request`. Those that may disable return
+    // false while the disable waits for its confirming press, see
+    // `confirm_disable`, so the worker doesn't count it as applied.
+    pub(crate) fn handle_hotkey_toggle(&self, source: ActionSource) -> bool {
+        // Get current state and toggle
+        match self.touchpad_controller.get_state() {
+            Ok(current_state) => {
+                let target = toggle_target(Some(current_state), UnknownToggleTarget::Enable);
+                if target == TouchpadState::Disabled && !self.confirm_disable(source) {
+                    return false;
+                }
+                self.apply_state(target, current_state, source);
+            }
+            Err(e @ ControllerError::MacOsPermissionRequired) => {
                 error!("Failed to get touchpad state: {:?}", e);
+                self.record_error(&e);
+                if let Err(e) = self.event_sender.send(HotkeyEvent::PermissionNeeded) {
+                    error!("Failed to send permission event: {}", e);
+                }
+            }
+            Err(e) => {
+                // The state is unknown, fall back to the configured target.
+                // Whatever the backend then reports is trusted, since a
+                // verification read would most likely fail the same way.
+                let target = toggle_target(None, self.state.get_settings().unknown_toggle_target);
+                warn!("Failed to get touchpad state ({}), toggling to {:?}", e, target);
+                if target == TouchpadState::Disabled && !self.confirm_disable(source) {
+                    return false;
+                }
+                self.apply_state(target, target, source);
+            }
+        }
+        true
+    }
+
+    pub(crate) fn handle_tap_to_click_toggle(&self) {
+        let current = self.touchpad_controller
+            .get_tap_to_click()
+            .unwrap_or_else(|_| self.state.tap_to_click());
+
+        if self.set_tap_to_click(!current) {
+            if let Err(e) = self.event_sender.send(HotkeyEvent::TapToClickChanged(!current)) {
+                error!("Failed to send hotkey event: {}", e);
+            }
+        }
+    }
+
+    pub fn notify_temporary_enable(&self, seconds: Option<u64>) {
+        if let Err(e) = self.event_sender.send(HotkeyEvent::TemporarilyEnabled(seconds)) {
+            error!("Failed to send hotkey event: {}", e);
+        }
+    }
+
+    /// Leaves the taps-disabled mode if active, otherwise enters it: the
+    /// touchpad is enabled if needed and tap-to-click is turned off.
+    pub(crate) fn handle_taps_disabled_toggle(&self, source: ActionSource) {
+        let entering = self.state.pointer_mode().toggled_taps() == PointerMode::TapsDisabled;
+        if entering {
+            let current = self.tracked_state();
+            if current == TouchpadState::Disabled {
+                self.apply_state(TouchpadState::Enabled, current, source);
+                if self.tracked_state() == TouchpadState::Disabled {
+                    return;
+                }
+            }
+        }
+
+        if self.set_tap_to_click(!entering) {
+            if let Err(e) = self.event_sender.send(HotkeyEvent::TapsDisabledChanged(entering)) {
+                error!("Failed to send hotkey event: {}", e);
+            }
+        }
+    }
+
+    // Returns whether the change was applied; failures are reported
+    fn set_tap_to_click(&self, enabled: bool) -> bool {
+        match self.touchpad_controller.set_tap_to_click(enabled) {
+            Ok(()) => {
+                self.state.clear_last_error();
+                self.state.set_tap_to_click(enabled);
+                true
+            }
+            Err(e) => {
+                error!("Failed to set tap-to-click: {:?}", e);
+                self.record_error(&e);
+                let message = format!("Tap-to-click unavailable: {}", e);
+                if let Err(e) = self.event_sender.send(HotkeyEvent::ToggleFailed(message)) {
+                    error!("Failed to send hotkey event: {}", e);
+                }
+                false
+            }
+        }
+    }
+
+    pub(crate) fn handle_natural_scrolling_toggle(&self) {
+        let current = match self.touchpad_controller.get_natural_scrolling() {
+            Ok(current) => current,
+            Err(e) => {
+                // Without the current value a toggle could just as well
+                // re-apply it, so report the failure instead of guessing
+                error!("Failed to read natural scrolling: {:?}", e);
+                self.record_error(&e);
+                let message = format!("Natural scrolling unavailable: {}", e);
+                if let Err(e) = self.event_sender.send(HotkeyEvent::ToggleFailed(message)) {
+                    error!("Failed to send hotkey event: {}", e);
+                }
+                return;
+            }
+        };
+        self.set_natural_scrolling(!current);
+    }
+
+    pub fn set_natural_scrolling(&self, enabled: bool) {
+        match self.touchpad_controller.set_natural_scrolling(enabled) {
+            Ok(()) => {
+                self.state.clear_last_error();
+                if let Err(e) = self.event_sender.send(HotkeyEvent::NaturalScrollingChanged(enabled)) {
+                    error!("Failed to send hotkey event: {}", e);
+                }
+            }
+            Err(e) => {
+                error!("Failed to set natural scrolling: {:?}", e);
+                self.record_error(&e);
+                let message = format!("Natural scrolling unavailable: {}", e);
+                if let Err(e) = self.event_sender.send(HotkeyEvent::ToggleFailed(message)) {
+                    error!("Failed to send hotkey event: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Switches to the scroll method after the current one, wrapping around.
+    pub(crate) fn handle_scroll_method_cycle(&self) {
+        let methods = match self.touchpad_controller.scroll_methods() {
+            Ok(methods) => methods,
+            Err(e) => {
+                error!("Failed to read scroll methods: {:?}", e);
+                Vec::new()
+            }
+        };
+        if methods.len() < 2 {
+            let message = "Only one scroll method is supported".to_string();
+            if let Err(e) = self.event_sender.send(HotkeyEvent::ToggleFailed(message)) {
+                error!("Failed to send hotkey event: {}", e);
+            }
+            return;
+        }
+        let current = self.touchpad_controller.get_scroll_method().ok();
+        let index = current.and_then(|current| methods.iter().position(|m| *m == current));
+        let next = methods[index.map_or(0, |i| (i + 1) % methods.len())];
+        // Failures are reported by set_scroll_method
+        let _ = self.set_scroll_method(next);
+    }
+
+    pub(crate) fn handle_sensitivity_cycle(&self) {
+        let levels = self.settings().sensitivity_levels;
+        let current = self.touchpad_controller.get_sensitivity().ok();
+        let Some((level, value)) = next_sensitivity_level(&levels, current) else {
+            return;
+        };
+        match self.set_sensitivity(value) {
+            Ok(value) => {
+                let event = HotkeyEvent::SensitivityChanged { level, levels: levels.len(), value };
+                if let Err(e) = self.event_sender.send(event) {
+                    error!("Failed to send hotkey event: {}", e);
+                }
+            }
+            Err(e) => {
+                error!("Failed to set sensitivity {}: {:?}", value, e);
+                let message = format!("Pointer speed unavailable: {}", e);
+                if let Err(e) = self.event_sender.send(HotkeyEvent::ToggleFailed(message)) {
+                    error!("Failed to send hotkey event: {}", e);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn handle_trackpoint_toggle(&self) {
+        let target = match self.touchpad_controller.get_trackpoint_state() {
+            Ok(TouchpadState::Enabled) => TouchpadState::Disabled,
+            Ok(TouchpadState::Disabled) => TouchpadState::Enabled,
+            Err(e) => {
+                self.report_trackpoint_error(&e);
+                return;
+            }
+        };
+        self.set_trackpoint_state(target);
+    }
+
+    /// Toggles the touchpad and switches the trackpoint to the same state.
+    /// Both are enabled unless both are currently on.
+    pub(crate) fn handle_toggle_both(&self, source: ActionSource) -> bool {
+        let touchpad = self.touchpad_controller.get_state().ok();
+        let trackpoint = self.touchpad_controller.get_trackpoint_state().ok();
+        let target = if touchpad == Some(TouchpadState::Enabled) && trackpoint != Some(TouchpadState::Disabled) {
+            TouchpadState::Disabled
+        } else {
+            TouchpadState::Enabled
+        };
+        if !self.handle_hotkey_set(target, source) {
+            return false;
+        }
+        // Leave the trackpoint on if the touchpad refused to turn off
+        if self.touchpad_controller.trackpoint_name().is_some() && self.tracked_state() == target {
+            self.set_trackpoint_state(target);
+        }
+        true
+    }
+
+    fn set_trackpoint_state(&self, target: TouchpadState) {
+        match self.touchpad_controller.set_trackpoint_state(target) {
+            Ok(()) => {
+                info!("Trackpoint {:?}", target);
+                let event = HotkeyEvent::TrackpointChanged(target == TouchpadState::Enabled);
+                if let Err(e) = self.event_sender.send(event) {
+                    error!("Failed to send hotkey event: {}", e);
+                }
+            }
+            Err(e) => self.report_trackpoint_error(&e),
+        }
+    }
+
+    fn report_trackpoint_error(&self, e: &ControllerError) {
+        error!("Failed to switch trackpoint: {:?}", e);
+        self.record_error(e);
+        let message = format!("Trackpoint unavailable: {}", e);
+        if let Err(e) = self.event_sender.send(HotkeyEvent::ToggleFailed(message)) {
+            error!("Failed to send hotkey event: {}", e);
+        }
+    }
+
+    pub fn set_scroll_method(&self, method: ScrollMethod) -> Result<(), ControllerError> {
+        match self.touchpad_controller.set_scroll_method(method) {
+            Ok(()) => {
+                self.state.clear_last_error();
+                if let Err(e) = self.event_sender.send(HotkeyEvent::ScrollMethodChanged(method)) {
+                    error!("Failed to send hotkey event: {}", e);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to set scroll method {:?}: {:?}", method, e);
+                self.record_error(&e);
+                let message = format!("Scroll method unavailable: {}", e);
+                if let Err(e) = self.event_sender.send(HotkeyEvent::ToggleFailed(message)) {
+                    error!("Failed to send hotkey event: {}", e);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Queues the state configured by `startup_state`, see `StartupState` for precedence.
+    pub fn apply_startup_state(&self) {
+        let settings = self.state.get_settings();
+        let target = match settings.startup_state {
+            StartupState::Enabled => Some(TouchpadState::Enabled),
+            StartupState::Disabled => Some(TouchpadState::Disabled),
+            StartupState::Last if settings.persist_state => self.state.load_persisted_state(),
+            StartupState::Last => None,
+        };
+        match target {
+            Some(TouchpadState::Enabled) => self.request(TouchpadAction::Enable, ActionSource::Startup),
+            Some(TouchpadState::Disabled) => self.request(TouchpadAction::Disable, ActionSource::Startup),
+            None => {}
+        }
+    }
+
+    /// Re-enables the touchpad on exit if `restore_on_exit` is set. The persisted
+    /// state is left untouched so `StartupState::Last` still sees the user's choice.
+    pub fn handle_exit(&self) {
+        if !self.state.get_settings().restore_on_exit {
+            return;
+        }
+        let _guard = self.action_lock.lock();
+        if let Err(e) = self.touchpad_controller.enable() {
+            error!("Failed to re-enable touchpad on exit: {:?}", e);
+        }
+    }
+
+    /// Applies the device options from settings that aren't plain enable/disable.
+    pub fn apply_device_settings(&self, settings: &Settings) {
+        if let Some(enabled) = settings.natural_scrolling {
+            let current = self.touchpad_controller.get_natural_scrolling().ok();
+            if current != Some(enabled) {
+                self.set_natural_scrolling(enabled);
+            }
+        }
+    }
+
+    pub(crate) fn handle_hotkey_set(&self, target: TouchpadState, source: ActionSource) -> bool {
+        if target == TouchpadState::Enabled {
+            // A press before the enable must not count towards the next disable
+            lock_recovering(&self.disable_confirmation).reset();
+        } else if !self.confirm_disable(source) {
+            return false;
+        }
+        let current_state = self.touchpad_controller.get_state().unwrap_or(target);
+        self.apply_state(target, current_state, source);
+        true
+    }
+
+    /// Whether a disable from `source` may go ahead. With
+    /// `double_press_to_disable`, a hotkey disable only applies on the second
+    /// press within the window; the first one prompts for it on the OSD.
+    fn confirm_disable(&self, source: ActionSource) -> bool {
+        let settings = self.state.get_settings();
+        if !settings.double_press_to_disable || source != ActionSource::Hotkey {
+            return true;
+        }
+        let window = Duration::from_millis(settings.double_press_window_ms);
+        if lock_recovering(&self.disable_confirmation).press(Instant::now(), window) {
+            return true;
+        }
+        info!("Disable needs a second press within {:?}", window);
+        if let Err(e) = self.event_sender.send(HotkeyEvent::ConfirmDisable) {
+            error!("Failed to send hotkey event: {}", e);
+        }
+        false
+    }
+
+    /// Waits the configured verification delay and re-reads the real device state.
+    fn read_verified_state(&self, delay_ms: u64) -> Option<TouchpadState> {
+        if delay_ms > 0 {
+            std::thread::sleep(Duration::from_millis(delay_ms));
+        }
+        self.touchpad_controller.get_state().ok()
+    }
+
+    /// Times `iterations` disable/enable cycles (capped at
+    /// `MAX_BENCHMARK_ITERATIONS`). The touchpad is always left enabled.
+    pub fn benchmark_toggle(&self, iterations: u32) -> Result<BenchmarkResult, ControllerError> {
+        let _guard = self.action_lock.lock();
+        let iterations = iterations.clamp(1, MAX_BENCHMARK_ITERATIONS);
+        let mut enable = Vec::new();
+        let mut disable = Vec::new();
+
+        let mut result = Ok(());
+        for _ in 0..iterations {
+            let start = Instant::now();
+            result = self.touchpad_controller.disable();
+            disable.push(start.elapsed());
+            if result.is_err() {
+                break;
+            }
+            let start = Instant::now();
+            result = self.touchpad_controller.enable();
+            enable.push(start.elapsed());
+            if result.is_err() {
+                break;
+            }
+        }
+
+        if let Err(e) = self.touchpad_controller.enable() {
+            error!("Failed to re-enable touchpad after benchmark: {:?}", e);
+        }
+        self.state.set_touchpad_state(TouchpadState::Enabled);
+        result?;
+
+        info!("Benchmarked {} toggle cycles", iterations);
+        Ok(BenchmarkResult {
+            backend: self.touchpad_controller.describe().backend,
+            iterations,
+            enable: LatencyStats::from_samples(&enable),
+            disable: LatencyStats::from_samples(&disable),
+        })
+    }
+
+    /// The backend's tools and the current state, without touching the device.
+    pub fn diagnostic_report(&self) -> Vec<String> {
+        let mut log = Vec::new();
+        for tool in self.touchpad_controller.required_tools() {
+            match find_in_path(tool) {
+                Some(path) => log.push(format!("Tool {}: {}", tool, path.display())),
+                None => log.push(ControllerError::BackendToolMissing(tool.to_string()).to_string()),
+            }
+        }
+        match self.touchpad_controller.get_state() {
+            Ok(state) => log.push(format!("Current state: {:?}", state)),
+            Err(e) => log.push(format!("Reading the state failed: {}", e)),
+        }
+        log
+    }
+
+    /// Disables then re-enables the touchpad, verifying each step, and returns
+    /// a step-by-step log. The touchpad is always left enabled.
+    pub fn diagnostic_cycle(&self) -> Vec<String> {
+        let _guard = self.action_lock.lock();
+        let delay = self.state.get_settings().verify_delay_ms;
+        let mut log = self.diagnostic_report();
+
+        let steps = [
+            (TouchpadState::Disabled, "Disable"),
+            (TouchpadState::Enabled, "Enable"),
+        ];
+        for (i, (target, name)) in steps.into_iter().enumerate() {
+            if i > 0 {
+                std::thread::sleep(Duration::from_secs(1));
+            }
+            let result = if target == TouchpadState::Enabled {
+                self.touchpad_controller.enable()
+            } else {
+                self.touchpad_controller.disable()
+            };
+            match result {
+                Ok(()) => log.push(format!("{}: command succeeded", name)),
+                Err(e) => {
+                    log.push(format!("{}: command failed: {}", name, e));
+                    continue;
+                }
+            }
+            match self.read_verified_state(delay) {
+                Some(state) if state == target => log.push(format!("{}: verified, state is {:?}", name, state)),
+                Some(state) => log.push(format!("{}: did not take effect, state is {:?}", name, state)),
+                None => log.push(format!("{}: could not read state to verify", name)),
+            }
+        }
+
+        // Never leave the user without a touchpad
+        if self.touchpad_controller.get_state().ok() != Some(TouchpadState::Enabled) {
+            match self.touchpad_controller.enable() {
+                Ok(()) => log.push("Re-enabled touchpad after diagnostics".to_string()),
+                Err(e) => log.push(format!("Failed to re-enable touchpad: {}", e)),
+            }
+        }
+        let final_state = self.touchpad_controller.get_state().unwrap_or(TouchpadState::Enabled);
+        self.state.set_touchpad_state(final_state);
+        log.push(format!("Final state: {:?}", final_state));
+        log
+    }
+
+    /// Picks up a state change made outside the app, e.g. by the hardware
+    /// key or the system settings. Our own writes already match the tracked
+    /// state by the time the action lock is released, so they are ignored.
+    pub fn sync_external_state(&self) {
+        let _guard = self.action_lock.lock();
+        let Ok(new_state) = self.touchpad_controller.get_state() else {
+            return;
+        };
+        let intended = self.state.get_touchpad_state();
+        if new_state == intended {
+            return;
+        }
+
+        let settings = self.state.get_settings();
+        let response = external_change(new_state, &settings, mouse_detector::is_mouse_connected(), resume::resume_pending());
+        if response == ExternalChange::Ignore {
+            debug!("Touchpad is {:?} right after a resume, leaving it to the resume check", new_state);
+            return;
+        }
+        self.state.set_touchpad_state(new_state);
+        if settings.persist_state {
+            self.state.persist_touchpad_state(new_state);
+        }
+        self.state.history.record(new_state, ActionSource::External);
+        info!(
+            device = self.state.get_device_name().as_str(),
+            state:? = new_state,
+            source:? = ActionSource::External;
+            "Touchpad state changed"
+        );
+        if let Some(url) = &settings.webhook_url {
+            webhook::notify_state_change(url, new_state, ActionSource::External);
+        }
+        let event = if new_state == TouchpadState::Enabled {
+            HotkeyEvent::TouchpadEnabled { silent: false }
+        } else {
+            HotkeyEvent::TouchpadDisabled { silent: false }
+        };
+        if let Err(e) = self.event_sender.send(event) {
+            error!("Failed to send hotkey event: {}", e);
+        }
+
+        match response {
+            ExternalChange::Reclaim(source) => {
+                info!("Reclaiming the touchpad state {:?} after an external change", intended);
+                let action = if intended == TouchpadState::Enabled { TouchpadAction::Enable } else { TouchpadAction::Disable };
+                self.request(action, source);
+            }
+            // Adopted, e.g. the hardware key: a resume must not undo it
+            ExternalChange::Adopt => self.state.set_intended_state(new_state),
+            ExternalChange::Ignore => {}
+        }
+    }
+
+    fn start_resume_watch(self: &Arc<Self>) {
+        let manager = Arc::clone(self);
+        resume::start_watcher(move |slept| {
+            info!("Resumed after about {} s asleep", slept.as_secs());
+            manager.handle_resume();
+        });
+    }
+
+    /// Puts back the state we last applied if it changed while asleep, as
+    /// some firmware re-enables the touchpad on every resume.
+    pub fn handle_resume(&self) {
+        if !self.settings().reapply_state_on_resume {
+            return;
+        }
+        let Some(intended) = self.state.intended_state() else {
+            return;
+        };
+        match self.touchpad_controller.get_state() {
+            Ok(actual) => {
+                if let Some(action) = resume_action(intended, actual) {
+                    info!("Touchpad is {:?} after resume, re-applying {:?}", actual, intended);
+                    self.request(action, ActionSource::Resume);
+                }
+            }
+            Err(e) => warn!("Failed to read the touchpad state after resume: {}", e),
+        }
+    }
+
+    /// Polls the real state so changes by other apps are noticed on every
+    /// platform, not only where an event listener exists.
+    fn start_state_reconcile(self: &Arc<Self>) {
+        let manager = Arc::clone(self);
+        supervisor::spawn("state-reconcile", move |token| {
+            while token.sleep(STATE_RECONCILE_INTERVAL) {
+                manager.sync_external_state();
+            }
+        });
+    }
+
+    fn apply_state(&self, target: TouchpadState, current_state: TouchpadState, source: ActionSource) {
+        let _guard = self.action_lock.lock();
+        let settings = self.state.get_settings();
+        if settings.restore_cursor_position && target == TouchpadState::Disabled {
+            self.state.save_cursor_position(self.mouse_emulator.cursor_position());
+        }
+
+        let result = if target == TouchpadState::Enabled {
+            self.touchpad_controller.enable()
+        } else {
+            self.touchpad_controller.disable()
+        };
+
+        match result {
+            Ok(()) => {
+                // Backends may report success without changing anything, so
+                // re-read the real state before telling the OSD
+                let new_state = self.read_verified_state(settings.verify_delay_ms).unwrap_or(current_state);
+                self.state.set_touchpad_state(new_state);
+                if settings.persist_state {
+                    self.state.persist_touchpad_state(new_state);
+                }
+
+                if new_state != target {
+                    warn!("Touchpad state is {:?} after requesting {:?}", new_state, target);
+                    error_report::record("StateMismatch");
+                    let message = format!("Failed to switch touchpad to {:?}", target);
+                    if let Err(e) = self.event_sender.send(HotkeyEvent::ToggleFailed(message)) {
+                        error!("Failed to send hotkey event: {}", e);
+                    }
+                    return;
+                }
+
+                self.state.clear_last_error();
+                self.state.set_intended_state(new_state);
+                self.state.history.record(new_state, source);
+                info!(
+                    device = self.state.get_device_name().as_str(),
+                    state:? = new_state,
+                    source:? = source;
+                    "Touchpad state changed"
+                );
+                if let Some(url) = &settings.webhook_url {
+                    webhook::notify_state_change(url, new_state, source);
+                }
+                if new_state == TouchpadState::Enabled {
+                    cursor_confine::release();
+                } else if settings.mouse_keys && settings.confine_cursor && source.is_manual() {
+                    // Only a deliberate disable may take the cursor; a mouse
+                    // connecting must not trap it
+                    cursor_confine::confine();
+                }
+                if settings.restore_cursor_position && new_state == TouchpadState::Enabled {
+                    self.restore_cursor_position();
+                }
+
+                // Send event to OSD
+                let event = HotkeyEvent::state_changed(new_state, source, &settings);
+
+                if let Err(e) = self.event_sender.send(event) {
+                    error!("Failed to send hotkey event: {}", e);
+                }
+            }
+            Err(ControllerError::WouldStrandUser) => {
+                warn!("Not disabling the touchpad: no other pointing device is connected");
+                let message = "Touchpad kept on: no mouse connected".to_string();
+                if let Err(e) = self.event_sender.send(HotkeyEvent::ToggleFailed(message)) {
+                    error!("Failed to send hotkey event: {}", e);
+                }
+            }
+            Err(e) => {
+                error!(
+                    device = self.state.get_device_name().as_str(),
+                    requested:? = target,
+                    source:? = source,
+                    error = e.to_string().as_str();
+                    "Failed to toggle touchpad: {:?}", e
+                );
+                self.record_error(&e);
+                // Send permission needed event
                 if let Err(e) = self.event_sender.send(HotkeyEvent::PermissionNeeded) {
                     error!("Failed to send permission event: {}", e);
                 }
             }
         }
     }
-}
\ No newline at end of file
+
+    fn restore_cursor_position(&self) {
+        let Some((saved_x, saved_y)) = self.state.take_cursor_position() else {
+            return;
+        };
+        let Some((x, y)) = self.mouse_emulator.cursor_position() else {
+            return;
+        };
+
+        if (x - saved_x).abs() > CURSOR_DRIFT_TOLERANCE || (y - saved_y).abs() > CURSOR_DRIFT_TOLERANCE {
+            info!("Cursor moved while touchpad was disabled, not restoring position");
+            return;
+        }
+        self.mouse_emulator.simulate_mouse_move(saved_x, saved_y);
+    }
+}
+
+/// What puts back `intended` when the touchpad woke up `actual`, if anything.
+fn resume_action(intended: TouchpadState, actual: TouchpadState) -> Option<TouchpadAction> {
+    match (intended, actual) {
+        (TouchpadState::Enabled, TouchpadState::Disabled) => Some(TouchpadAction::Enable),
+        (TouchpadState::Disabled, TouchpadState::Enabled) => Some(TouchpadAction::Disable),
+        _ => None,
+    }
+}
+
+/// The state a toggle switches to from `current`, or per `unknown` when the
+/// state couldn't be read.
+fn toggle_target(current: Option<TouchpadState>, unknown: UnknownToggleTarget) -> TouchpadState {
+    match (current, unknown) {
+        (Some(TouchpadState::Enabled), _) => TouchpadState::Disabled,
+        (Some(TouchpadState::Disabled), _) => TouchpadState::Enabled,
+        (None, UnknownToggleTarget::Enable) => TouchpadState::Enabled,
+        (None, UnknownToggleTarget::Disable) => TouchpadState::Disabled,
+    }
+}
+
+/// What the reconcile poll does about the device changing outside the app.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExternalChange {
+    /// Changed across a sleep, so left to `handle_resume`
+    Ignore,
+    /// Our state is put back by this source
+    Reclaim(ActionSource),
+    /// Taken as the new intended state
+    Adopt,
+}
+
+/// How to respond to the device changing to `new_state` outside the app.
+/// Right after a resume the change is most likely the firmware's, and
+/// adopting it would leave the resume check nothing to put back.
+fn external_change(new_state: TouchpadState, settings: &Settings, mouse_connected: bool, resume_pending: bool) -> ExternalChange {
+    if resume_pending {
+        return ExternalChange::Ignore;
+    }
+    match reclaim_source(new_state, settings, mouse_connected) {
+        Some(source) => ExternalChange::Reclaim(source),
+        None => ExternalChange::Adopt,
+    }
+}
+
+/// Who puts our state back after the device changed to `new_state` outside
+/// the app, or `None` to go along with the change. Never leaving the user
+/// without a pointer is a safety net and bypasses safe mode like the
+/// safeguard; the `Reclaim` policy is an ordinary auto-toggle.
+fn reclaim_source(new_state: TouchpadState, settings: &Settings, mouse_connected: bool) -> Option<ActionSource> {
+    let stranded = new_state == TouchpadState::Disabled && settings.enable_on_mouse_disconnect && !mouse_connected;
+    if stranded {
+        Some(ActionSource::Safeguard)
+    } else if settings.external_change_policy == ExternalChangePolicy::Reclaim {
+        Some(ActionSource::Auto)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use crate::core::state::StateHistory;
+
+    /// Stands in for the global shortcut plugin: accelerators in `taken`
+    /// belong to another app and fail to register.
+    #[derive(Default)]
+    struct MockRegistrar {
+        taken: HashSet<String>,
+        registered: HashSet<String>,
+    }
+
+    impl MockRegistrar {
+        fn register_all(&mut self, accelerators: &[&str]) -> Vec<(String, bool)> {
+            accelerators
+                .iter()
+                .map(|accelerator| {
+                    let ok = !self.taken.contains(*accelerator);
+                    if ok {
+                        self.registered.insert(accelerator.to_string());
+                    }
+                    (accelerator.to_string(), ok)
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn status_follows_registration() {
+        let mut registrar = MockRegistrar { taken: HashSet::from(["Super+L".to_string()]), ..Default::default() };
+        let mut status = HotkeyStatus::default();
+
+        status.registered(registrar.register_all(&["Ctrl+Alt+F9", "Super+L"]));
+        assert_eq!(
+            status.entries(),
+            vec![("Ctrl+Alt+F9".to_string(), true), ("Super+L".to_string(), false)]
+        );
+
+        registrar.registered.clear();
+        status.unregistered();
+        assert!(status.entries().iter().all(|(_, active)| !active));
+
+        // Re-registering with a changed binding replaces the list
+        status.registered(registrar.register_all(&["Ctrl+Alt+F10"]));
+        assert_eq!(status.entries(), vec![("Ctrl+Alt+F10".to_string(), true)]);
+
+        status.clear();
+        assert!(status.entries().is_empty());
+    }
+
+    #[test]
+    fn health_check_ignores_hotkeys_that_never_registered() {
+        let mut registrar = MockRegistrar { taken: HashSet::from(["Super+L".to_string()]), ..Default::default() };
+        let mut status = HotkeyStatus::default();
+        status.registered(registrar.register_all(&["Ctrl+Alt+F9", "Super+L"]));
+        assert!(status.intact(|accelerator| registrar.registered.contains(accelerator)));
+
+        // The desktop grabbed ours at runtime
+        registrar.registered.remove("Ctrl+Alt+F9");
+        assert!(!status.intact(|accelerator| registrar.registered.contains(accelerator)));
+    }
+
+    #[test]
+    fn startup_state_change_is_silent() {
+        let settings = Settings::default();
+        assert!(settings.suppress_startup_osd);
+        assert!(matches!(
+            HotkeyEvent::state_changed(TouchpadState::Disabled, ActionSource::Startup, &settings),
+            HotkeyEvent::TouchpadDisabled { silent: true }
+        ));
+        assert!(matches!(
+            HotkeyEvent::state_changed(TouchpadState::Enabled, ActionSource::Hotkey, &settings),
+            HotkeyEvent::TouchpadEnabled { silent: false }
+        ));
+
+        let loud = Settings { suppress_startup_osd: false, ..Settings::default() };
+        assert!(matches!(
+            HotkeyEvent::state_changed(TouchpadState::Enabled, ActionSource::Startup, &loud),
+            HotkeyEvent::TouchpadEnabled { silent: false }
+        ));
+    }
+
+    #[test]
+    fn toggle_from_unknown_state_follows_the_setting() {
+        assert_eq!(toggle_target(None, UnknownToggleTarget::Enable), TouchpadState::Enabled);
+        assert_eq!(toggle_target(None, UnknownToggleTarget::Disable), TouchpadState::Disabled);
+        // A readable state always flips, whatever the setting
+        for unknown in [UnknownToggleTarget::Enable, UnknownToggleTarget::Disable] {
+            assert_eq!(toggle_target(Some(TouchpadState::Enabled), unknown), TouchpadState::Disabled);
+            assert_eq!(toggle_target(Some(TouchpadState::Disabled), unknown), TouchpadState::Enabled);
+        }
+        // Enabling is the default so an unreadable device can't strand the user
+        assert_eq!(Settings::default().unknown_toggle_target, UnknownToggleTarget::Enable);
+    }
+
+    #[test]
+    fn resume_keeps_a_hardware_key_change_that_was_adopted() {
+        // Disabled by us, then enabled with the Fn key under the default policy
+        let settings = Settings::default();
+        assert_eq!(settings.external_change_policy, ExternalChangePolicy::Defer);
+        let mut intended = TouchpadState::Disabled;
+        if reclaim_source(TouchpadState::Enabled, &settings, true).is_none() {
+            intended = TouchpadState::Enabled;
+        }
+
+        // An hour asleep is noticed as a resume
+        assert!(resume::slept_during(Duration::from_secs(2), Duration::from_secs(3600)));
+        assert!(!resume::slept_during(Duration::from_secs(2), Duration::from_secs(3)));
+        assert_eq!(resume_action(intended, TouchpadState::Enabled), None);
+
+        // Firmware that turns the touchpad off on wake is still corrected
+        assert_eq!(resume_action(intended, TouchpadState::Disabled), Some(TouchpadAction::Enable));
+        assert_eq!(resume_action(TouchpadState::Disabled, TouchpadState::Enabled), Some(TouchpadAction::Disable));
+    }
+
+    #[test]
+    fn reconcile_poll_running_before_the_resume_check_leaves_the_intent_alone() {
+        use std::time::SystemTime;
+
+        // Disabled by us, then the firmware re-enabled it during an hour asleep
+        let settings = Settings::default();
+        let last_tick = SystemTime::now() - Duration::from_secs(3600);
+        let woke = SystemTime::now();
+        let mut intended = TouchpadState::Disabled;
+
+        // The reconcile poll runs before the resume watcher has ticked
+        let pending = resume::resume_pending_at(woke, Some(last_tick), None);
+        match external_change(TouchpadState::Enabled, &settings, true, pending) {
+            ExternalChange::Adopt => intended = TouchpadState::Enabled,
+            response => assert_eq!(response, ExternalChange::Ignore),
+        }
+        // so the resume check still finds something to put back
+        assert_eq!(resume_action(intended, TouchpadState::Enabled), Some(TouchpadAction::Disable));
+
+        // Also while that correction is still queued, right after the report
+        let reported = woke + Duration::from_secs(1);
+        assert!(resume::resume_pending_at(reported, Some(woke), Some(woke)));
+
+        // Later changes are adopted as usual
+        let later = woke + Duration::from_secs(20);
+        let pending = resume::resume_pending_at(later, Some(later - Duration::from_secs(2)), Some(woke));
+        assert!(!pending);
+        assert_eq!(external_change(TouchpadState::Enabled, &settings, true, pending), ExternalChange::Adopt);
+    }
+
+    #[test]
+    fn external_flip_is_recorded_and_handled_per_policy() {
+        let history = StateHistory::default();
+        // We enabled it, then another tool disabled it behind our back
+        history.record(TouchpadState::Enabled, ActionSource::Hotkey);
+        history.record(TouchpadState::Disabled, ActionSource::External);
+        let sources: Vec<_> = history.entries().iter().map(|change| (change.state, change.source)).collect();
+        assert_eq!(
+            sources,
+            vec![(TouchpadState::Enabled, ActionSource::Hotkey), (TouchpadState::Disabled, ActionSource::External)]
+        );
+
+        let defer = Settings { external_change_policy: ExternalChangePolicy::Defer, enable_on_mouse_disconnect: false, ..Settings::default() };
+        assert_eq!(reclaim_source(TouchpadState::Disabled, &defer, false), None);
+
+        let reclaim = Settings { external_change_policy: ExternalChangePolicy::Reclaim, ..defer.clone() };
+        assert_eq!(reclaim_source(TouchpadState::Disabled, &reclaim, true), Some(ActionSource::Auto));
+        assert_eq!(reclaim_source(TouchpadState::Enabled, &reclaim, true), Some(ActionSource::Auto));
+
+        // Without a mouse, a disable is always undone, even when deferring
+        let stranded = Settings { enable_on_mouse_disconnect: true, ..defer };
+        assert_eq!(reclaim_source(TouchpadState::Disabled, &stranded, false), Some(ActionSource::Safeguard));
+        assert_eq!(reclaim_source(TouchpadState::Disabled, &stranded, true), None);
+        assert_eq!(reclaim_source(TouchpadState::Enabled, &stranded, false), None);
+    }
+}
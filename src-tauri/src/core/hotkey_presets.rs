@@ -0,0 +1,68 @@
+//! Built-in hotkey sets for users who don't want to pick their own.
+
+use serde::Serialize;
+use crate::core::hotkey_manager::reserved_reason;
+use crate::core::settings::Settings;
+
+#[derive(Debug, Clone, Serialize)]
+#[allow(dead_code)]
+pub struct HotkeyPreset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub enable_hotkey: &'static str,
+    pub disable_hotkey: &'static str,
+    pub settings_hotkey: &'static str,
+}
+
+pub const PRESETS: &[HotkeyPreset] = &[
+    HotkeyPreset {
+        name: "Ctrl+Shift letters",
+        description: "The defaults: T to enable, Y to disable, O for settings",
+        enable_hotkey: "Ctrl+Shift+T",
+        disable_hotkey: "Ctrl+Shift+Y",
+        settings_hotkey: "Ctrl+Shift+O",
+    },
+    HotkeyPreset {
+        name: "Function-row style",
+        description: "Ctrl with F5 to enable, F6 to disable, F7 for settings",
+        enable_hotkey: "Ctrl+F5",
+        disable_hotkey: "Ctrl+F6",
+        settings_hotkey: "Ctrl+F7",
+    },
+    HotkeyPreset {
+        name: "Ctrl+Alt letters",
+        description: "E to enable, K to disable, comma for settings",
+        enable_hotkey: "Ctrl+Alt+E",
+        disable_hotkey: "Ctrl+Alt+K",
+        settings_hotkey: "Ctrl+Alt+Comma",
+    },
+];
+
+/// Looks a preset up by name, ignoring case.
+pub fn find(name: &str) -> Option<&'static HotkeyPreset> {
+    PRESETS.iter().find(|preset| preset.name.eq_ignore_ascii_case(name))
+}
+
+impl HotkeyPreset {
+    /// Copies the preset's hotkeys into `settings`, leaving the others as-is.
+    pub fn apply_to(&self, settings: &mut Settings) {
+        settings.enable_hotkey = self.enable_hotkey.to_string();
+        settings.disable_hotkey = self.disable_hotkey.to_string();
+        settings.settings_hotkey = self.settings_hotkey.to_string();
+    }
+
+    /// Hotkeys in the preset known to be taken by the system on this platform.
+    pub fn warnings(&self) -> Vec<String> {
+        [
+            ("enable_hotkey", self.enable_hotkey),
+            ("disable_hotkey", self.disable_hotkey),
+            ("settings_hotkey", self.settings_hotkey),
+        ]
+        .iter()
+        .filter_map(|(setting, accelerator)| {
+            reserved_reason(accelerator)
+                .map(|reason| format!("{} {} may not fire, it is taken by the system ({})", setting, accelerator, reason))
+        })
+        .collect()
+    }
+}
@@ -1,10 +1,294 @@
 // src-tauri/src/core/input_controller.rs
-use crate::core::state::TouchpadState;
+use crate::core::device_registry::{Device, DeviceKind};
+use crate::core::mouse_detector;
+use crate::core::state::{lock_recovering, TouchpadState};
 use log::error;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+/// Upper bound for external backend commands (xinput etc.), in milliseconds.
+static COMMAND_TIMEOUT_MS: AtomicU64 = AtomicU64::new(2000);
+
+/// Mirrors the `allow_disable_without_mouse` setting for `disable()`.
+static ALLOW_DISABLE_WITHOUT_MOUSE: AtomicBool = AtomicBool::new(true);
+
+#[allow(dead_code)]
+pub fn set_command_timeout(timeout: Duration) {
+    COMMAND_TIMEOUT_MS.store(timeout.as_millis() as u64, Ordering::SeqCst);
+}
+
+#[allow(dead_code)]
+pub fn command_timeout() -> Duration {
+    Duration::from_millis(COMMAND_TIMEOUT_MS.load(Ordering::SeqCst))
+}
+
+#[allow(dead_code)]
+pub fn set_allow_disable_without_mouse(allow: bool) {
+    ALLOW_DISABLE_WITHOUT_MOUSE.store(allow, Ordering::SeqCst);
+}
+
+fn allow_disable_without_mouse() -> bool {
+    ALLOW_DISABLE_WITHOUT_MOUSE.load(Ordering::SeqCst)
+}
+
+/// Mirrors the `evdev_backend` setting, read when the controller is created
+/// or the device re-detected.
+static EVDEV_BACKEND: AtomicBool = AtomicBool::new(true);
+
+/// Returns whether the value changed, i.e. the device should be re-detected.
+#[allow(dead_code)]
+pub fn set_evdev_backend(enabled: bool) -> bool {
+    EVDEV_BACKEND.swap(enabled, Ordering::SeqCst) != enabled
+}
+
+#[allow(dead_code)]
+fn evdev_backend() -> bool {
+    EVDEV_BACKEND.load(Ordering::SeqCst)
+}
+
+/// Where the macOS controller's `get_state` takes the state from. Reading the
+/// trackpad preference is accurate on some macOS versions but fails or lags
+/// on others, while the last applied state is always available but misses
+/// changes made outside the app.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+pub enum StateSource {
+    /// Trust the state we last applied; never misreports our own changes.
+    Cache,
+    /// Always read the preference; errors are passed on to the caller.
+    Read,
+    /// Read, falling back to the last applied state if the read fails.
+    Auto,
+}
+
+/// Mirrors the `macos_state_source` setting.
+static MACOS_STATE_SOURCE: Mutex<StateSource> = Mutex::new(StateSource::Auto);
+
+#[allow(dead_code)]
+pub fn set_macos_state_source(source: StateSource) {
+    *lock_recovering(&MACOS_STATE_SOURCE) = source;
+}
+
+#[allow(dead_code)]
+fn macos_state_source() -> StateSource {
+    *lock_recovering(&MACOS_STATE_SOURCE)
+}
+
+/// Resolves the state per `source` from a real `read` and the last applied
+/// `intent`. `read` is only called when the mode needs it.
+#[allow(dead_code)]
+fn resolve_state(
+    source: StateSource,
+    read: impl FnOnce() -> Result<TouchpadState, ControllerError>,
+    intent: TouchpadState,
+) -> Result<TouchpadState, ControllerError> {
+    match source {
+        StateSource::Cache => Ok(intent),
+        StateSource::Read => read(),
+        StateSource::Auto => read().or_else(|e| {
+            log::debug!("Reading the state failed ({}), using last intent {:?}", e, intent);
+            Ok(intent)
+        }),
+    }
+}
+
+/// The built-in trackpad's state on macOS: the "ignore when a mouse is
+/// present" preference only takes effect while a mouse is connected.
+#[cfg(any(target_os = "macos", test))]
+fn trackpad_state_from(ignore_with_mouse: bool, mouse_present: bool) -> TouchpadState {
+    if ignore_with_mouse && mouse_present {
+        TouchpadState::Disabled
+    } else {
+        TouchpadState::Enabled
+    }
+}
+
+/// Mirrors the `applescript_retries` setting: attempts after the first when
+/// an AppleScript fails transiently.
+static APPLESCRIPT_RETRIES: AtomicU64 = AtomicU64::new(2);
+
+const APPLESCRIPT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[allow(dead_code)]
+pub fn set_applescript_retries(retries: u32) {
+    APPLESCRIPT_RETRIES.store(retries as u64, Ordering::SeqCst);
+}
+
+#[allow(dead_code)]
+fn applescript_retries() -> u32 {
+    APPLESCRIPT_RETRIES.load(Ordering::SeqCst) as u32
+}
+
+/// The error dictionary filled in by `NSAppleScript executeAndReturnError:`.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct ScriptError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl ScriptError {
+    /// Not authorized to send Apple events (-1743), assistive access not
+    /// allowed (-1719 / -25211). Retrying can't fix these.
+    fn is_permission_denied(&self) -> bool {
+        matches!(self.code, -1743 | -1719 | -25211)
+    }
+}
+
+/// AppleScript setting "Ignore built-in trackpad when mouse or wireless
+/// trackpad is present" to `ignore`. It only clicks the checkbox when its
+/// value differs, so running it again after an ambiguous failure can't flip
+/// it back.
+#[cfg(any(target_os = "macos", test))]
+fn ignore_trackpad_script(ignore: bool) -> String {
+    format!(
+        "tell application \"System Preferences\"\nset current pane to pane \"com.apple.preference.trackpad\"\nend tell\n\
+         tell application \"System Events\" to tell process \"System Preferences\"\n\
+         set ignoreBox to checkbox \"Ignore built-in trackpad when mouse or wireless trackpad is present\" of tab group 1 of window \"Trackpad\"\n\
+         if value of ignoreBox is not {} then click ignoreBox\nend tell",
+        u8::from(ignore)
+    )
+}
+
+/// Runs `execute` until it succeeds, retrying up to `retries` times with
+/// `delay` between attempts. Permission errors fail immediately with
+/// `MacOsPermissionRequired`; anything else, e.g. System Events not ready
+/// yet or a dialog still on screen, is treated as transient. Only pass
+/// scripts that set a state, never ones that flip it, since a failed
+/// attempt may still have taken effect.
+#[allow(dead_code)]
+fn run_script_with_retry(
+    retries: u32,
+    delay: Duration,
+    mut execute: impl FnMut() -> Result<(), ScriptError>,
+) -> Result<(), ControllerError> {
+    let mut attempt = 0;
+    loop {
+        let e = match execute() {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        if e.is_permission_denied() {
+            return Err(ControllerError::MacOsPermissionRequired);
+        }
+        if attempt >= retries {
+            return Err(ControllerError::MacOsScriptFailed(format!("{} ({})", e.message, e.code)));
+        }
+        attempt += 1;
+        log::warn!("AppleScript failed ({}: {}), retrying {}/{}", e.code, e.message, attempt, retries);
+        std::thread::sleep(delay);
+    }
+}
+
+/// Backend chosen by `select_backend` at startup; `None` keeps the built-in
+/// order.
+static PREFERRED_BACKEND: Mutex<Option<String>> = Mutex::new(None);
+
+#[allow(dead_code)]
+pub fn set_preferred_backend(backend: Option<String>) {
+    *lock_recovering(&PREFERRED_BACKEND) = backend;
+}
+
+#[allow(dead_code)]
+pub fn preferred_backend() -> Option<String> {
+    lock_recovering(&PREFERRED_BACKEND).clone()
+}
+
+/// Outcome of a read-only probe of one backend.
+#[derive(Debug, Clone, Serialize)]
+#[allow(dead_code)]
+pub struct BackendProbe {
+    pub backend: String,
+    /// Whether the backend could read the device state and is allowed to
+    /// switch it.
+    pub usable: bool,
+    /// What was read, or why the backend was skipped.
+    pub detail: String,
+}
+
+/// The backend in use after a probe, and why the others were skipped.
+#[derive(Debug, Clone, Serialize)]
+#[allow(dead_code)]
+pub struct BackendProbeReport {
+    pub selected: Option<String>,
+    pub probes: Vec<BackendProbe>,
+}
+
+/// Probes every backend of this platform, in preference order.
+#[allow(dead_code)]
+pub fn probe_backends() -> Vec<BackendProbe> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::PROBE_ORDER.iter().map(|backend| linux::probe_backend(backend)).collect()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        vec![BackendProbe {
+            backend: BACKEND.to_string(),
+            usable: true,
+            detail: "only backend on this platform".to_string(),
+        }]
+    }
+}
+
+/// Picks the first usable backend in preference order, so a better one that
+/// became usable, e.g. evdev after a udev rule was added, wins over an earlier
+/// choice. Unless `full_probe`, the backends after it aren't probed. Returns
+/// the choice and the probes run.
+#[allow(dead_code)]
+pub fn select_backend(full_probe: bool) -> (Option<String>, Vec<BackendProbe>) {
+    #[cfg(target_os = "linux")]
+    if !full_probe {
+        let mut probes = Vec::new();
+        for backend in linux::PROBE_ORDER {
+            let probe = linux::probe_backend(backend);
+            let usable = probe.usable;
+            probes.push(probe);
+            if usable {
+                return (Some(backend.to_string()), probes);
+            }
+        }
+        return (None, probes);
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = full_probe;
+
+    let probes = probe_backends();
+    let selected = probes.iter().find(|probe| probe.usable).map(|probe| probe.backend.clone());
+    (selected, probes)
+}
+
+/// Runs a read-only query and formats its raw output for diagnostics.
+#[allow(dead_code)]
+fn capture_output(program: &str, args: &[&str]) -> String {
+    let header = format!("$ {} {}\n", program, args.join(" "));
+    match std::process::Command::new(program).args(args).output() {
+        Ok(output) => format!(
+            "{}{}{}(exit: {})\n",
+            header,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+            output.status
+        ),
+        Err(e) => format!("{}failed to run: {}\n", header, e),
+    }
+}
+
+/// Whether anything besides the managed device (`managed` by id) could still
+/// move the pointer.
+pub fn other_pointer_present(devices: &[Device], managed: &str) -> bool {
+    devices
+        .iter()
+        .any(|device| device.id != managed && device.kind != DeviceKind::Other)
+}
+
+#[derive(Error, Debug, Clone)]
 #[allow(dead_code)]
 pub enum ControllerError {
     #[error("Linux: Failed to find touchpad device")]
@@ -13,8 +297,228 @@ pub enum ControllerError {
     WindowsApiError,
     #[error("macOS: Accessibility permission required")]
     MacOsPermissionRequired,
+    #[error("macOS: AppleScript failed: {0}")]
+    MacOsScriptFailed(String),
     #[error("Unsupported platform")]
     UnsupportedPlatform,
+    #[error("Backend command timed out")]
+    Timeout,
+    #[error("Backend command failed: {0}")]
+    CommandFailed(String),
+    #[error("Device property not supported: {0}")]
+    PropertyUnsupported(&'static str),
+    #[error("No display available (DISPLAY and WAYLAND_DISPLAY are unset)")]
+    NoDisplay,
+    #[error("No display available and the display-free evdev backend is unusable: {0}")]
+    HeadlessUnavailable(String),
+    #[error("Disabling would leave no usable pointing device")]
+    WouldStrandUser,
+    #[error("Background task failed: {0}")]
+    TaskFailed(String),
+    #[error("{0} was not found in PATH; install the {0} package and restart")]
+    BackendToolMissing(String),
+}
+
+/// Full path of the executable `tool` in `PATH`, if any.
+#[allow(dead_code)]
+pub fn find_in_path(tool: &str) -> Option<std::path::PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .flat_map(|dir| {
+            let exe = dir.join(tool).with_extension(std::env::consts::EXE_EXTENSION);
+            [dir.join(tool), exe]
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+/// Fails with `BackendToolMissing` naming `tool` if it isn't installed.
+#[allow(dead_code)]
+pub fn require_tool(tool: &str) -> Result<(), ControllerError> {
+    match find_in_path(tool) {
+        Some(_) => Ok(()),
+        None => Err(ControllerError::BackendToolMissing(tool.to_string())),
+    }
+}
+
+/// Runs `command` and returns its status and stdout, killing it once
+/// `timeout` elapses so a hung X server can't block the caller forever.
+pub fn run_with_timeout(
+    command: &mut Command,
+    timeout: Duration,
+) -> Result<(ExitStatus, String), ControllerError> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                ControllerError::BackendToolMissing(command.get_program().to_string_lossy().into_owned())
+            }
+            _ => ControllerError::LinuxDeviceNotFound,
+        })?;
+
+    // Drain stdout on a separate thread so a full pipe can't stall the child
+    let mut stdout = child.stdout.take();
+    let reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(stdout) = stdout.as_mut() {
+            let _ = stdout.read_to_string(&mut output);
+        }
+        output
+    });
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                let output = reader.join().unwrap_or_default();
+                return Ok((status, output));
+            }
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                error!("Command {:?} timed out after {:?}", command, timeout);
+                return Err(ControllerError::Timeout);
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+            Err(_) => return Err(ControllerError::LinuxDeviceNotFound),
+        }
+    }
+}
+
+/// Like `run_with_timeout`, but a non-zero exit status is an error.
+pub fn run_checked(command: &mut Command, timeout: Duration) -> Result<String, ControllerError> {
+    let (status, output) = run_with_timeout(command, timeout)?;
+    if !status.success() {
+        return Err(ControllerError::CommandFailed(format!("{:?} exited with {}", command, status)));
+    }
+    Ok(output)
+}
+
+/// Whether a graphical session is reachable. Always true outside Linux.
+#[allow(dead_code)]
+pub fn display_available() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        ["DISPLAY", "WAYLAND_DISPLAY"]
+            .iter()
+            .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        true
+    }
+}
+
+/// Human-readable description of the device a controller manages.
+#[derive(Debug, Clone, Serialize)]
+#[allow(dead_code)]
+pub struct DeviceInfo {
+    pub name: String,
+    pub backend: String,
+    pub natural_scrolling: Option<bool>,
+    /// Pointer speed normalized to `SENSITIVITY_MIN..=SENSITIVITY_MAX`.
+    pub sensitivity: Option<f64>,
+    pub scroll_method: Option<ScrollMethod>,
+    pub state: Option<TouchpadState>,
+    /// A trackpoint switched independently of the managed device, if any.
+    pub trackpoint: Option<String>,
+    pub trackpoint_state: Option<TouchpadState>,
+}
+
+/// How the touchpad turns finger movement into scrolling.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[allow(dead_code)]
+pub enum ScrollMethod {
+    TwoFinger,
+    /// Sliding along the right or bottom edge.
+    Edge,
+}
+
+/// Bounds of the normalized pointer speed, libinput's "Accel Speed" range;
+/// 0.0 is the platform default.
+pub const SENSITIVITY_MIN: f64 = -1.0;
+pub const SENSITIVITY_MAX: f64 = 1.0;
+
+/// Rejects non-numbers and clamps into the normalized range.
+pub fn clamp_sensitivity(value: f64) -> Result<f64, ControllerError> {
+    if !value.is_finite() {
+        return Err(ControllerError::PropertyUnsupported("sensitivity"));
+    }
+    Ok(value.clamp(SENSITIVITY_MIN, SENSITIVITY_MAX))
+}
+
+/// The level after the one closest to `current`, wrapping around, with its
+/// index. `None` for an empty list.
+pub fn next_sensitivity_level(levels: &[f64], current: Option<f64>) -> Option<(usize, f64)> {
+    if levels.is_empty() {
+        return None;
+    }
+    let closest = current.and_then(|current| {
+        (0..levels.len()).min_by(|&a, &b| (levels[a] - current).abs().total_cmp(&(levels[b] - current).abs()))
+    });
+    let index = closest.map_or(0, |i| (i + 1) % levels.len());
+    Some((index, levels[index]))
+}
+
+/// Maps a normalized sensitivity onto a platform scale `min..=max` whose
+/// default sits at `default`, and back.
+fn sensitivity_to_platform(value: f64, min: f64, default: f64, max: f64) -> f64 {
+    if value < 0.0 {
+        default + value * (default - min)
+    } else {
+        default + value * (max - default)
+    }
+}
+
+fn sensitivity_from_platform(raw: f64, min: f64, default: f64, max: f64) -> f64 {
+    let raw = raw.clamp(min, max);
+    if raw < default {
+        (raw - default) / (default - min)
+    } else {
+        (raw - default) / (max - default)
+    }
+}
+
+/// Value `name` in `reg query` output:
+/// ```text
+///     TapsEnabled    REG_DWORD    0xffffffff
+/// ```
+#[cfg(any(target_os = "windows", test))]
+pub(crate) fn parse_reg_dword(output: &str, name: &str) -> Option<u32> {
+    output.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        if fields.next()? != name || fields.next()? != "REG_DWORD" {
+            return None;
+        }
+        u32::from_str_radix(fields.next()?.trim_start_matches("0x"), 16).ok()
+    })
+}
+
+/// A float printed by `defaults read`, e.g. "0.6875".
+#[cfg(any(target_os = "macos", test))]
+fn parse_defaults_float(output: &str) -> Option<f64> {
+    output.trim().parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// Why the evdev backend can't be used when the setting turns it off.
+#[cfg(any(target_os = "linux", test))]
+const EVDEV_TURNED_OFF: &str = "turned off by the evdev_backend setting";
+
+/// What stops the Linux controller from working, if there is no display:
+/// only the evdev backend runs headless. `evdev_problem` says what stops
+/// that backend while it is on, and is only asked then.
+#[cfg(any(target_os = "linux", test))]
+fn headless_failure(display_available: bool, evdev_enabled: bool, evdev_problem: impl FnOnce() -> String) -> Option<String> {
+    if display_available {
+        None
+    } else if !evdev_enabled {
+        Some(EVDEV_TURNED_OFF.to_string())
+    } else {
+        Some(evdev_problem())
+    }
 }
 
 #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
@@ -23,6 +527,77 @@ pub trait TouchpadController: Send + Sync {
     fn enable(&self) -> Result<(), ControllerError>;
     fn disable(&self) -> Result<(), ControllerError>;
     fn get_state(&self) -> Result<TouchpadState, ControllerError>;
+    fn describe(&self) -> DeviceInfo;
+    /// Mechanism switching the device, without querying the backend.
+    fn backend_name(&self) -> String {
+        BACKEND.to_string()
+    }
+    /// Name of the managed device, without querying the backend.
+    fn device_name(&self) -> String;
+    /// Whether the whole device can be disabled, as opposed to only the
+    /// per-user driver setting.
+    fn can_disable_device(&self) -> bool;
+    /// Re-runs platform detection, e.g. after a new trackpad or driver change,
+    /// and returns the device now being managed.
+    fn redetect_device(&self) -> Result<DeviceInfo, ControllerError>;
+    /// Raw output of the backend's read-only queries, for support reports.
+    fn raw_diagnostics(&self) -> String;
+    /// External programs the current backend runs, checked by the self-test.
+    fn required_tools(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+    /// All pointing devices the backend can see, including the managed one.
+    fn devices(&self) -> Result<Vec<Device>, ControllerError>;
+    /// Id of the managed device within `devices()`.
+    fn device_id(&self) -> String;
+
+    /// Whether the managed device is still attached. A failed listing counts
+    /// as present so a flaky backend isn't mistaken for a removal.
+    fn device_present(&self) -> bool {
+        let device_id = self.device_id();
+        self.devices().map_or(true, |devices| devices.iter().any(|device| device.id == device_id))
+    }
+
+    /// Whether disabling the managed device still leaves a usable pointer,
+    /// another device in the registry or a detected external mouse.
+    fn can_disable_safely(&self) -> bool {
+        let devices = self.devices().unwrap_or_default();
+        other_pointer_present(&devices, &self.device_id()) || mouse_detector::is_mouse_connected()
+    }
+
+    /// Refuses to disable when that would strand the user, unless allowed.
+    fn check_disable_allowed(&self) -> Result<(), ControllerError> {
+        if allow_disable_without_mouse() || self.can_disable_safely() {
+            return Ok(());
+        }
+        Err(ControllerError::WouldStrandUser)
+    }
+
+    /// Name of a trackpoint managed next to the touchpad, e.g. on ThinkPads.
+    fn trackpoint_name(&self) -> Option<String> {
+        None
+    }
+    fn get_trackpoint_state(&self) -> Result<TouchpadState, ControllerError> {
+        Err(ControllerError::PropertyUnsupported("trackpoint"))
+    }
+    fn set_trackpoint_state(&self, _state: TouchpadState) -> Result<(), ControllerError> {
+        Err(ControllerError::PropertyUnsupported("trackpoint"))
+    }
+
+    fn set_tap_to_click(&self, enabled: bool) -> Result<(), ControllerError>;
+    fn get_tap_to_click(&self) -> Result<bool, ControllerError>;
+    fn set_natural_scrolling(&self, enabled: bool) -> Result<(), ControllerError>;
+    fn get_natural_scrolling(&self) -> Result<bool, ControllerError>;
+    /// Normalized pointer speed, see `SENSITIVITY_MIN`/`SENSITIVITY_MAX`.
+    fn get_sensitivity(&self) -> Result<f64, ControllerError>;
+    /// Sets the pointer speed; callers pass a value from `clamp_sensitivity`.
+    fn set_sensitivity(&self, value: f64) -> Result<(), ControllerError>;
+    /// Scroll methods the hardware supports, in cycling order.
+    fn scroll_methods(&self) -> Result<Vec<ScrollMethod>, ControllerError>;
+    fn get_scroll_method(&self) -> Result<ScrollMethod, ControllerError>;
+    /// Switches scrolling; fails with `PropertyUnsupported` for a method
+    /// missing from `scroll_methods`.
+    fn set_scroll_method(&self, method: ScrollMethod) -> Result<(), ControllerError>;
 }
 
 // Platform implementations
@@ -35,24 +610,62 @@ mod windows {
     use windows::Win32::Foundation::HWND;
     use std::sync::Mutex;
     use once_cell::sync::Lazy;
-    use log::error;
 
     static STATE: Lazy<Mutex<TouchpadState>> = Lazy::new(|| Mutex::new(TouchpadState::Disabled));
 
-    pub struct WindowsTouchpadController;
+    // Per-user Precision Touchpad settings
+    const PTP_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\PrecisionTouchPad";
+
+    fn read_ptp_dword(name: &'static str) -> Result<u32, ControllerError> {
+        let output = std::process::Command::new("reg")
+            .args(["query", PTP_KEY, "/v", name])
+            .output()
+            .map_err(|_| ControllerError::WindowsApiError)?;
+        parse_reg_dword(&String::from_utf8_lossy(&output.stdout), name)
+            .ok_or(ControllerError::PropertyUnsupported(name))
+    }
+
+    fn write_ptp_dword(name: &'static str, value: u32) -> Result<(), ControllerError> {
+        let status = std::process::Command::new("reg")
+            .args(["add", PTP_KEY, "/v", name, "/t", "REG_DWORD", "/d", &value.to_string(), "/f"])
+            .status()
+            .map_err(|_| ControllerError::WindowsApiError)?;
+        if !status.success() {
+            return Err(ControllerError::PropertyUnsupported(name));
+        }
+        Ok(())
+    }
+
+    pub struct WindowsTouchpadController {
+        // Precision touchpads are toggled through the per-user setting, which needs no admin
+        precision: AtomicBool,
+        elevated: bool,
+    }
 
     impl WindowsTouchpadController {
         pub fn create() -> Result<Arc<Self>, ControllerError> {
-            // Check if application has admin privileges
-            if !is_elevated() {
+            let precision = read_ptp_dword("Enabled").is_ok();
+            let elevated = is_elevated();
+            // Only the device-disable fallback requires admin privileges
+            if !precision && !elevated {
                 return Err(ControllerError::WindowsApiError);
             }
-            Ok(Arc::new(Self))
+            Ok(Arc::new(Self { precision: AtomicBool::new(precision), elevated }))
+        }
+
+        fn precision(&self) -> bool {
+            self.precision.load(Ordering::SeqCst)
         }
     }
 
     impl TouchpadController for WindowsTouchpadController {
         fn enable(&self) -> Result<(), ControllerError> {
+            if self.precision() {
+                write_ptp_dword("Enabled", u32::MAX)?;
+                *lock_recovering(&STATE) = TouchpadState::Enabled;
+                return Ok(());
+            }
+
             unsafe {
                 let mut input = INPUT {
                     r#type: INPUT_KEYBOARD,
@@ -68,32 +681,127 @@ mod windows {
                 SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
             }
             
-            match STATE.lock() {
-                Ok(mut state) => *state = TouchpadState::Enabled,
-                Err(e) => error!("Failed to acquire STATE lock: {}", e)
-            }
+            *lock_recovering(&STATE) = TouchpadState::Enabled;
             
             Ok(())
         }
 
         fn disable(&self) -> Result<(), ControllerError> {
-            // Similar to enable but with different key code
-            match STATE.lock() {
-                Ok(mut state) => *state = TouchpadState::Disabled,
-                Err(e) => error!("Failed to acquire STATE lock: {}", e)
+            self.check_disable_allowed()?;
+            if self.precision() {
+                write_ptp_dword("Enabled", 0)?;
+                *lock_recovering(&STATE) = TouchpadState::Disabled;
+                return Ok(());
             }
+
+            // Similar to enable but with different key code
+            *lock_recovering(&STATE) = TouchpadState::Disabled;
             
             Ok(())
         }
 
         fn get_state(&self) -> Result<TouchpadState, ControllerError> {
-            match STATE.lock() {
-                Ok(state) => Ok(*state),
-                Err(e) => {
-                    error!("Failed to acquire STATE lock: {}", e);
-                    // Return a default state in case of error
-                    Ok(TouchpadState::Disabled)
-                }
+            if self.precision() {
+                return Ok(if read_ptp_dword("Enabled")? != 0 {
+                    TouchpadState::Enabled
+                } else {
+                    TouchpadState::Disabled
+                });
+            }
+            Ok(*lock_recovering(&STATE))
+        }
+
+        fn describe(&self) -> DeviceInfo {
+            DeviceInfo {
+                name: self.device_name(),
+                backend: BACKEND.to_string(),
+                natural_scrolling: self.get_natural_scrolling().ok(),
+                sensitivity: self.get_sensitivity().ok(),
+                scroll_method: self.get_scroll_method().ok(),
+                state: self.get_state().ok(),
+                trackpoint: self.trackpoint_name(),
+                trackpoint_state: self.get_trackpoint_state().ok(),
+            }
+        }
+
+        fn device_name(&self) -> String {
+            "Built-in touchpad".to_string()
+        }
+
+        fn can_disable_device(&self) -> bool {
+            self.elevated
+        }
+
+        fn raw_diagnostics(&self) -> String {
+            capture_output("reg", &["query", PTP_KEY, "/s"])
+        }
+
+        fn redetect_device(&self) -> Result<DeviceInfo, ControllerError> {
+            let precision = read_ptp_dword("Enabled").is_ok();
+            if !precision && !self.elevated {
+                return Err(ControllerError::WindowsApiError);
+            }
+            self.precision.store(precision, Ordering::SeqCst);
+            Ok(self.describe())
+        }
+
+        fn devices(&self) -> Result<Vec<Device>, ControllerError> {
+            Ok(vec![Device {
+                id: "0".to_string(),
+                name: self.device_name(),
+                kind: DeviceKind::Touchpad,
+            }])
+        }
+
+        fn device_id(&self) -> String {
+            "0".to_string()
+        }
+
+        fn set_tap_to_click(&self, enabled: bool) -> Result<(), ControllerError> {
+            write_ptp_dword("TapsEnabled", if enabled { u32::MAX } else { 0 })
+        }
+
+        fn get_tap_to_click(&self) -> Result<bool, ControllerError> {
+            Ok(read_ptp_dword("TapsEnabled")? != 0)
+        }
+
+        // Non-zero means "down motion scrolls up", i.e. natural scrolling
+        fn set_natural_scrolling(&self, enabled: bool) -> Result<(), ControllerError> {
+            write_ptp_dword("ScrollDirection", if enabled { u32::MAX } else { 0 })
+        }
+
+        fn get_natural_scrolling(&self) -> Result<bool, ControllerError> {
+            Ok(read_ptp_dword("ScrollDirection")? != 0)
+        }
+
+        // Settings app slider: 0..=20, default 10
+        fn get_sensitivity(&self) -> Result<f64, ControllerError> {
+            let raw = read_ptp_dword("CursorSpeed")?;
+            Ok(sensitivity_from_platform(raw as f64, 0.0, 10.0, 20.0))
+        }
+
+        fn set_sensitivity(&self, value: f64) -> Result<(), ControllerError> {
+            let raw = sensitivity_to_platform(clamp_sensitivity(value)?, 0.0, 10.0, 20.0);
+            write_ptp_dword("CursorSpeed", raw.round() as u32)
+        }
+
+        // Precision touchpads only offer two-finger panning, on or off
+        fn scroll_methods(&self) -> Result<Vec<ScrollMethod>, ControllerError> {
+            read_ptp_dword("PanEnabled")?;
+            Ok(vec![ScrollMethod::TwoFinger])
+        }
+
+        fn get_scroll_method(&self) -> Result<ScrollMethod, ControllerError> {
+            match read_ptp_dword("PanEnabled")? {
+                0 => Err(ControllerError::PropertyUnsupported("PanEnabled")),
+                _ => Ok(ScrollMethod::TwoFinger),
+            }
+        }
+
+        fn set_scroll_method(&self, method: ScrollMethod) -> Result<(), ControllerError> {
+            match method {
+                ScrollMethod::TwoFinger => write_ptp_dword("PanEnabled", u32::MAX),
+                ScrollMethod::Edge => Err(ControllerError::PropertyUnsupported("edge scrolling")),
             }
         }
     }
@@ -135,9 +843,112 @@ mod macos {
     use objc_foundation::{INSString, NSString};
     use std::sync::Mutex;
     use once_cell::sync::Lazy;
-    use log::error;
 
-    static STATE: Lazy<Mutex<TouchpadState>> = Lazy::new(|| Mutex::new(TouchpadState::Disabled));
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> u8;
+    }
+
+    /// Whether the app may control System Events, which the AppleScript
+    /// switching the trackpad needs (Privacy & Security > Accessibility).
+    pub fn accessibility_trusted() -> bool {
+        unsafe { AXIsProcessTrusted() != 0 }
+    }
+
+    // The state we last asked for. Reading the real state is unreliable on
+    // macOS, so `get_state` may use this intent instead, see `StateSource`.
+    static LAST_INTENT: Lazy<Mutex<TouchpadState>> = Lazy::new(|| Mutex::new(TouchpadState::Enabled));
+
+    // Built-in and Bluetooth trackpads keep separate preference domains
+    const TRACKPAD_DOMAINS: [&str; 2] = [
+        "com.apple.AppleMultitouchTrackpad",
+        "com.apple.driver.AppleBluetoothMultitouch.trackpad",
+    ];
+
+    fn read_bool_default(domain: &str, key: &'static str) -> Result<bool, ControllerError> {
+        let output = std::process::Command::new("defaults")
+            .args(["read", domain, key])
+            .output()
+            .map_err(|_| ControllerError::MacOsPermissionRequired)?;
+        match String::from_utf8_lossy(&output.stdout).trim() {
+            "1" => Ok(true),
+            "0" => Ok(false),
+            _ => Err(ControllerError::PropertyUnsupported(key)),
+        }
+    }
+
+    fn write_bool_default(domain: &str, key: &'static str, value: bool) -> Result<(), ControllerError> {
+        let status = std::process::Command::new("defaults")
+            .args(["write", domain, key, "-bool", if value { "true" } else { "false" }])
+            .status()
+            .map_err(|_| ControllerError::MacOsPermissionRequired)?;
+        if !status.success() {
+            return Err(ControllerError::PropertyUnsupported(key));
+        }
+        Ok(())
+    }
+
+    fn read_float_default(domain: &str, key: &'static str) -> Result<f64, ControllerError> {
+        let output = std::process::Command::new("defaults")
+            .args(["read", domain, key])
+            .output()
+            .map_err(|_| ControllerError::MacOsPermissionRequired)?;
+        parse_defaults_float(&String::from_utf8_lossy(&output.stdout))
+            .ok_or(ControllerError::PropertyUnsupported(key))
+    }
+
+    fn write_float_default(domain: &str, key: &'static str, value: f64) -> Result<(), ControllerError> {
+        let status = std::process::Command::new("defaults")
+            .args(["write", domain, key, "-float", &value.to_string()])
+            .status()
+            .map_err(|_| ControllerError::MacOsPermissionRequired)?;
+        if !status.success() {
+            return Err(ControllerError::PropertyUnsupported(key));
+        }
+        Ok(())
+    }
+
+    /// Runs `source` once, turning the error dictionary into a `ScriptError`.
+    fn execute_script(source: &str) -> Result<(), ScriptError> {
+        unsafe {
+            let cls = class!(NSAppleScript);
+            let script: *mut Object = msg_send![cls, alloc];
+            let source = NSString::from_str(source);
+            let script: *mut Object = msg_send![script, initWithSource: source];
+            let mut error: *mut Object = std::ptr::null_mut();
+            let result: *mut Object = msg_send![script, executeAndReturnError: &mut error];
+            let _: () = msg_send![script, release];
+            if !result.is_null() {
+                return Ok(());
+            }
+            if error.is_null() {
+                return Err(ScriptError { code: 0, message: "unknown error".to_string() });
+            }
+            let number_key = NSString::from_str("NSAppleScriptErrorNumber");
+            let number: *mut Object = msg_send![error, objectForKey: &*number_key];
+            let code: i64 = if number.is_null() { 0 } else { msg_send![number, integerValue] };
+            let message_key = NSString::from_str("NSAppleScriptErrorMessage");
+            let message: *mut Object = msg_send![error, objectForKey: &*message_key];
+            let message = if message.is_null() {
+                String::new()
+            } else {
+                (*(message as *const NSString)).as_str().to_string()
+            };
+            Err(ScriptError { code, message })
+        }
+    }
+
+    /// Reads whether macOS currently ignores the built-in trackpad. There is
+    /// no API for that, so it is derived from "Ignore built-in trackpad when
+    /// mouse or wireless trackpad is present", the preference the AppleScript
+    /// toggles, and whether such a mouse is present.
+    fn read_real_state() -> Result<TouchpadState, ControllerError> {
+        let ignore_with_mouse = read_bool_default(TRACKPAD_DOMAINS[0], "USBMouseStopsTrackpad")?;
+        let mice = crate::core::mouse_detector::detect_mice()
+            .ok_or_else(|| ControllerError::CommandFailed("ioreg could not list pointing devices".to_string()))?;
+        let mouse_present = !mice.is_empty();
+        Ok(trackpad_state_from(ignore_with_mouse, mouse_present))
+    }
 
     pub struct MacosTouchpadController;
 
@@ -149,127 +960,691 @@ mod macos {
 
     impl TouchpadController for MacosTouchpadController {
         fn enable(&self) -> Result<(), ControllerError> {
-            unsafe {
-                let cls = class!(NSAppleScript);
-                let script: *mut Object = msg_send![cls, alloc];
-                let source = NSString::from_str("tell application \"System Preferences\"\nset current pane to pane \"com.apple.preference.trackpad\"\nend tell\ntell application \"System Events\" to tell process \"System Preferences\"\nclick checkbox \"Ignore built-in trackpad when mouse or wireless trackpad is present\" of tab group 1 of window \"Trackpad\"\nend tell");
-                let script: *mut Object = msg_send![script, initWithSource: source];
-                let _: () = msg_send![script, executeAndReturnError: 0 as *mut _];
-            }
+            let script = ignore_trackpad_script(false);
+            run_script_with_retry(applescript_retries(), APPLESCRIPT_RETRY_DELAY, || execute_script(&script))?;
             
-            match STATE.lock() {
-                Ok(mut state) => *state = TouchpadState::Enabled,
-                Err(e) => error!("Failed to acquire STATE lock: {}", e)
-            }
+            *lock_recovering(&LAST_INTENT) = TouchpadState::Enabled;
             
             Ok(())
         }
 
         fn disable(&self) -> Result<(), ControllerError> {
-            // Similar to enable with opposite setting
-            match STATE.lock() {
-                Ok(mut state) => *state = TouchpadState::Disabled,
-                Err(e) => error!("Failed to acquire STATE lock: {}", e)
-            }
+            self.check_disable_allowed()?;
+            let script = ignore_trackpad_script(true);
+            run_script_with_retry(applescript_retries(), APPLESCRIPT_RETRY_DELAY, || execute_script(&script))?;
+            
+            *lock_recovering(&LAST_INTENT) = TouchpadState::Disabled;
             
             Ok(())
         }
 
         fn get_state(&self) -> Result<TouchpadState, ControllerError> {
-            // macOS doesn't provide API to get current state
-            match STATE.lock() {
-                Ok(state) => Ok(*state),
-                Err(e) => {
-                    error!("Failed to acquire STATE lock: {}", e);
-                    // Return a default state in case of error
-                    Ok(TouchpadState::Disabled)
-                }
+            resolve_state(macos_state_source(), read_real_state, *lock_recovering(&LAST_INTENT))
+        }
+
+        fn describe(&self) -> DeviceInfo {
+            DeviceInfo {
+                name: self.device_name(),
+                backend: BACKEND.to_string(),
+                natural_scrolling: self.get_natural_scrolling().ok(),
+                sensitivity: self.get_sensitivity().ok(),
+                scroll_method: self.get_scroll_method().ok(),
+                state: self.get_state().ok(),
+                trackpoint: self.trackpoint_name(),
+                trackpoint_state: self.get_trackpoint_state().ok(),
             }
         }
-    }
-}
-#[cfg(target_os = "linux")]
-mod linux {
-    use super::*;
-    use std::process::{Command, Stdio};
-    use std::sync::Mutex;
-    use once_cell::sync::Lazy;
-    use log::error;
 
-    static STATE: Lazy<Mutex<TouchpadState>> = Lazy::new(|| Mutex::new(TouchpadState::Disabled));
+        fn device_name(&self) -> String {
+            "Built-in trackpad".to_string()
+        }
 
-    pub struct LinuxTouchpadController {
-        device_id: String,
+        fn can_disable_device(&self) -> bool {
+            true
+        }
+
+        // The built-in trackpad is the only device managed here
+        fn redetect_device(&self) -> Result<DeviceInfo, ControllerError> {
+            Ok(self.describe())
+        }
+
+        fn raw_diagnostics(&self) -> String {
+            let mut report = String::new();
+            for domain in TRACKPAD_DOMAINS {
+                report.push_str(&capture_output("defaults", &["read", domain]));
+            }
+            report.push_str(&capture_output("defaults", &["read", "NSGlobalDomain", "com.apple.swipescrolldirection"]));
+            report
+        }
+
+        fn devices(&self) -> Result<Vec<Device>, ControllerError> {
+            Ok(vec![Device {
+                id: "0".to_string(),
+                name: self.device_name(),
+                kind: DeviceKind::Touchpad,
+            }])
+        }
+
+        fn device_id(&self) -> String {
+            "0".to_string()
+        }
+
+        fn set_tap_to_click(&self, enabled: bool) -> Result<(), ControllerError> {
+            for domain in TRACKPAD_DOMAINS {
+                write_bool_default(domain, "Clicking", enabled)?;
+            }
+            Ok(())
+        }
+
+        fn get_tap_to_click(&self) -> Result<bool, ControllerError> {
+            read_bool_default(TRACKPAD_DOMAINS[0], "Clicking")
+        }
+
+        fn set_natural_scrolling(&self, enabled: bool) -> Result<(), ControllerError> {
+            write_bool_default("NSGlobalDomain", "com.apple.swipescrolldirection", enabled)
+        }
+
+        fn get_natural_scrolling(&self) -> Result<bool, ControllerError> {
+            read_bool_default("NSGlobalDomain", "com.apple.swipescrolldirection")
+        }
+
+        // Tracking speed slider: 0.0..=3.0, default 1.0. Applies after the
+        // next login, like the other trackpad defaults.
+        fn get_sensitivity(&self) -> Result<f64, ControllerError> {
+            let raw = read_float_default("NSGlobalDomain", "com.apple.trackpad.scaling")?;
+            Ok(sensitivity_from_platform(raw, 0.0, 1.0, 3.0))
+        }
+
+        fn set_sensitivity(&self, value: f64) -> Result<(), ControllerError> {
+            let raw = sensitivity_to_platform(clamp_sensitivity(value)?, 0.0, 1.0, 3.0);
+            write_float_default("NSGlobalDomain", "com.apple.trackpad.scaling", raw)
+        }
+
+        // Trackpads have no edge scrolling, two-finger scrolling is always on
+        fn scroll_methods(&self) -> Result<Vec<ScrollMethod>, ControllerError> {
+            Ok(vec![ScrollMethod::TwoFinger])
+        }
+
+        fn get_scroll_method(&self) -> Result<ScrollMethod, ControllerError> {
+            Ok(ScrollMethod::TwoFinger)
+        }
+
+        fn set_scroll_method(&self, method: ScrollMethod) -> Result<(), ControllerError> {
+            match method {
+                ScrollMethod::TwoFinger => Ok(()),
+                ScrollMethod::Edge => Err(ControllerError::PropertyUnsupported("edge scrolling")),
+            }
+        }
+    }
+}
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::*;
+    use crate::core::device_registry::{parse_proc_input_devices, parse_xinput_list, primary_and_trackpoint};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+    use once_cell::sync::Lazy;
+
+    static STATE: Lazy<Mutex<TouchpadState>> = Lazy::new(|| Mutex::new(TouchpadState::Disabled));
+
+    const PROC_INPUT_DEVICES: &str = "/proc/bus/input/devices";
+    const SYS_ROOT: &str = "/sys";
+    const EVDEV_BACKEND_NAME: &str = "evdev";
+    const GSETTINGS_BACKEND_NAME: &str = "gsettings";
+    const SWAY_BACKEND_NAME: &str = "sway";
+    const LIBINPUT_BACKEND_NAME: &str = "libinput";
+    const TOUCHPAD_SCHEMA: &str = "org.gnome.desktop.peripherals.touchpad";
+
+    /// How the device is switched when it isn't through xinput, which then
+    /// only serves device properties like tap-to-click.
+    #[derive(Debug, Clone, PartialEq)]
+    enum DirectSwitch {
+        /// Kernel inhibit switch (`inhibited`, Linux 5.11+) of the device.
+        Inhibit(PathBuf),
+        /// GNOME's touchpad `send-events` setting, applied by the session.
+        Gsettings,
+        /// sway's `events` input setting, applied to every touchpad.
+        Sway,
+    }
+
+    impl DirectSwitch {
+        fn backend(&self) -> &'static str {
+            match self {
+                DirectSwitch::Inhibit(_) => EVDEV_BACKEND_NAME,
+                DirectSwitch::Gsettings => GSETTINGS_BACKEND_NAME,
+                DirectSwitch::Sway => SWAY_BACKEND_NAME,
+            }
+        }
+
+        fn tool(&self) -> Option<&'static str> {
+            match self {
+                DirectSwitch::Inhibit(_) => None,
+                DirectSwitch::Gsettings => Some("gsettings"),
+                DirectSwitch::Sway => Some("swaymsg"),
+            }
+        }
+
+        fn read(&self) -> Result<TouchpadState, ControllerError> {
+            match self {
+                DirectSwitch::Inhibit(switch) => {
+                    let inhibited = fs::read_to_string(switch)
+                        .map_err(|_| ControllerError::PropertyUnsupported("inhibited"))?;
+                    Ok(if inhibited.trim() == "1" { TouchpadState::Disabled } else { TouchpadState::Enabled })
+                }
+                DirectSwitch::Gsettings => parse_send_events(&read_send_events()?)
+                    .ok_or(ControllerError::PropertyUnsupported("send-events")),
+                DirectSwitch::Sway => parse_sway_touchpad(&sway_inputs()?)
+                    .map(|(_, state)| state)
+                    .ok_or(ControllerError::LinuxDeviceNotFound),
+            }
+        }
+
+        fn write(&self, state: TouchpadState) -> Result<(), ControllerError> {
+            let enabled = state == TouchpadState::Enabled;
+            match self {
+                DirectSwitch::Inhibit(switch) => write_inhibit_switch(switch, !enabled),
+                DirectSwitch::Gsettings => {
+                    let value = if enabled { "enabled" } else { "disabled" };
+                    run_checked(
+                        Command::new("gsettings").args(["set", TOUCHPAD_SCHEMA, "send-events", value]),
+                        command_timeout(),
+                    )
+                    .map(|_| ())
+                }
+                DirectSwitch::Sway => {
+                    let value = if enabled { "enabled" } else { "disabled" };
+                    run_checked(
+                        Command::new("swaymsg").args(["input", "type:touchpad", "events", value]),
+                        command_timeout(),
+                    )
+                    .map(|_| ())
+                }
+            }
+        }
+
+        /// Raw output of the read this switch is based on.
+        fn diagnostics(&self) -> String {
+            let (header, result) = match self {
+                DirectSwitch::Inhibit(switch) => (
+                    format!("$ cat {}\n", switch.display()),
+                    fs::read_to_string(switch).map_err(|e| e.to_string()),
+                ),
+                DirectSwitch::Gsettings => (
+                    format!("$ gsettings get {} send-events\n", TOUCHPAD_SCHEMA),
+                    read_send_events().map_err(|e| e.to_string()),
+                ),
+                DirectSwitch::Sway => (
+                    "$ swaymsg -r -t get_inputs\n".to_string(),
+                    sway_inputs().map_err(|e| e.to_string()),
+                ),
+            };
+            match result {
+                Ok(output) => format!("{}{}\n", header, output.trim_end()),
+                Err(e) => format!("{}failed: {}\n", header, e),
+            }
+        }
+    }
+
+    fn read_send_events() -> Result<String, ControllerError> {
+        run_checked(
+            Command::new("gsettings").args(["get", TOUCHPAD_SCHEMA, "send-events"]),
+            command_timeout(),
+        )
+    }
+
+    fn sway_inputs() -> Result<String, ControllerError> {
+        run_checked(Command::new("swaymsg").args(["-r", "-t", "get_inputs"]), command_timeout())
+    }
+
+    /// Parses GNOME's `send-events` value, e.g. `'disabled-on-external-mouse'`,
+    /// which counts as enabled as the touchpad works without a mouse.
+    fn parse_send_events(value: &str) -> Option<TouchpadState> {
+        match value.trim().trim_matches('\'') {
+            "enabled" | "disabled-on-external-mouse" => Some(TouchpadState::Enabled),
+            "disabled" => Some(TouchpadState::Disabled),
+            _ => None,
+        }
+    }
+
+    /// Name and state of the first touchpad in `swaymsg -r -t get_inputs` output.
+    fn parse_sway_touchpad(json: &str) -> Option<(String, TouchpadState)> {
+        let inputs: Vec<serde_json::Value> = serde_json::from_str(json).ok()?;
+        let touchpad = inputs.iter().find(|input| input["type"] == "touchpad")?;
+        let state = match touchpad["libinput"]["send_events"].as_str()? {
+            "enabled" | "disabled_on_external_mouse" => TouchpadState::Enabled,
+            "disabled" => TouchpadState::Disabled,
+            _ => return None,
+        };
+        Some((touchpad["name"].as_str()?.to_string(), state))
+    }
+
+    // GNOME keeps the setting in other sessions too, where nothing applies it
+    fn gnome_session() -> bool {
+        std::env::var("XDG_CURRENT_DESKTOP")
+            .is_ok_and(|desktop| desktop.split(':').any(|name| name.eq_ignore_ascii_case("gnome")))
+    }
+
+    /// Name of the touchpad, or else the trackpoint, the kernel lists.
+    fn kernel_device_name() -> Option<String> {
+        let contents = fs::read_to_string(PROC_INPUT_DEVICES).ok()?;
+        find_inhibit_switch(&contents, Path::new(SYS_ROOT)).map(|(name, _)| name)
+    }
+
+    /// The switch of the preferred backend, or the kernel inhibit switch when
+    /// none is preferred, with the name of the device it switches.
+    fn direct_switch() -> Option<(String, DirectSwitch)> {
+        match preferred_backend().as_deref() {
+            Some(GSETTINGS_BACKEND_NAME) => probe_gsettings().ok().map(|_| {
+                (kernel_device_name().unwrap_or_else(|| "Touchpad".to_string()), DirectSwitch::Gsettings)
+            }),
+            Some(SWAY_BACKEND_NAME) => sway_inputs()
+                .ok()
+                .and_then(|output| parse_sway_touchpad(&output))
+                .map(|(name, _)| (name, DirectSwitch::Sway)),
+            _ => writable_inhibit_switch().map(|(name, switch)| (name, DirectSwitch::Inhibit(switch))),
+        }
+    }
+
+    /// Resolves the kernel inhibit switch (`inhibited`, Linux 5.11+) of the
+    /// touchpad, or else the trackpoint, listed in `proc_devices`, below `sys_root`.
+    fn find_inhibit_switch(proc_devices: &str, sys_root: &Path) -> Option<(String, PathBuf)> {
+        let devices = parse_proc_input_devices(proc_devices);
+        let device = devices.iter()
+            .find(|device| device.kind == DeviceKind::Touchpad)
+            .or_else(|| devices.iter().find(|device| device.kind == DeviceKind::Trackpoint))?;
+        let switch = sys_root.join(device.sysfs.trim_start_matches('/')).join("inhibited");
+        Some((device.name.clone(), switch))
+    }
+
+    /// Whether the `Groups:` line of `/proc/self/status` contains the gid of
+    /// the `input` group in `/etc/group`.
+    fn in_input_group(etc_group: &str, proc_status: &str) -> bool {
+        let Some(gid) = etc_group.lines().find_map(|line| {
+            let mut fields = line.split(':');
+            (fields.next()? == "input").then(|| fields.nth(1))?
+        }) else {
+            return false;
+        };
+        proc_status
+            .lines()
+            .find_map(|line| line.strip_prefix("Groups:"))
+            .is_some_and(|groups| groups.split_whitespace().any(|group| group == gid))
+    }
+
+    /// What to change so this process may write the inhibit switch.
+    fn permission_hint() -> &'static str {
+        let etc_group = fs::read_to_string("/etc/group").unwrap_or_default();
+        let proc_status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+        if in_input_group(&etc_group, &proc_status) {
+            "you are in the input group; a udev rule granting it write access enables the evdev backend"
+        } else {
+            "add your user to the input group and a udev rule granting it write access to enable the evdev backend"
+        }
+    }
+
+    // Only used if this process may write it, which needs root or a udev
+    // rule granting e.g. the input group access
+    fn writable_inhibit_switch() -> Option<(String, PathBuf)> {
+        if !evdev_backend() || preferred_backend().is_some_and(|backend| backend != EVDEV_BACKEND_NAME) {
+            return None;
+        }
+        let contents = fs::read_to_string(PROC_INPUT_DEVICES).ok()?;
+        let (name, switch) = find_inhibit_switch(&contents, Path::new(SYS_ROOT))?;
+        if !switch.exists() {
+            log::info!("Kernel has no input inhibit switch, using xinput");
+            return None;
+        }
+        if fs::OpenOptions::new().write(true).open(&switch).is_err() {
+            log::info!("{} is not writable, using xinput; {}", switch.display(), permission_hint());
+            return None;
+        }
+        Some((name, switch))
+    }
+
+    /// Backends `select_backend` tries, best first: the kernel switch works
+    /// in any session, the session's own settings don't fight with it, and
+    /// libinput's tool is only probed to say why it can't be used.
+    pub const PROBE_ORDER: [&str; 5] = [
+        EVDEV_BACKEND_NAME,
+        GSETTINGS_BACKEND_NAME,
+        SWAY_BACKEND_NAME,
+        BACKEND,
+        LIBINPUT_BACKEND_NAME,
+    ];
+
+    /// Read-only check that `backend` can read the device state here.
+    pub fn probe_backend(backend: &str) -> BackendProbe {
+        let result = match backend {
+            EVDEV_BACKEND_NAME => probe_evdev(),
+            GSETTINGS_BACKEND_NAME => probe_gsettings(),
+            SWAY_BACKEND_NAME => probe_sway(),
+            BACKEND => probe_xinput(),
+            LIBINPUT_BACKEND_NAME => probe_libinput(),
+            other => Err(format!("unknown backend {}", other)),
+        };
+        log::debug!("Backend probe {}: {:?}", backend, result);
+        let (usable, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(detail) => (false, detail),
+        };
+        BackendProbe { backend: backend.to_string(), usable, detail }
+    }
+
+    fn probe_evdev() -> Result<String, String> {
+        if !evdev_backend() {
+            return Err(EVDEV_TURNED_OFF.to_string());
+        }
+        let contents = fs::read_to_string(PROC_INPUT_DEVICES)
+            .map_err(|e| format!("{}: {}", PROC_INPUT_DEVICES, e))?;
+        let (name, switch) = find_inhibit_switch(&contents, Path::new(SYS_ROOT))
+            .ok_or_else(|| format!("no touchpad or trackpoint in {}", PROC_INPUT_DEVICES))?;
+        let value = fs::read_to_string(&switch).map_err(|e| format!("{}: {}", switch.display(), e))?;
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&switch)
+            .map_err(|e| format!("{} is not writable: {}; {}", switch.display(), e, permission_hint()))?;
+        Ok(format!("{} via {}, inhibited = {}", name, switch.display(), value.trim()))
+    }
+
+    fn probe_gsettings() -> Result<String, String> {
+        if !gnome_session() {
+            return Err("not a GNOME session, nothing applies the setting".to_string());
+        }
+        require_tool("gsettings").map_err(|e| e.to_string())?;
+        let value = read_send_events().map_err(|e| e.to_string())?;
+        parse_send_events(&value)
+            .ok_or_else(|| format!("unexpected {} send-events value {}", TOUCHPAD_SCHEMA, value.trim()))?;
+        Ok(format!("{} send-events = {}", TOUCHPAD_SCHEMA, value.trim()))
+    }
+
+    fn probe_sway() -> Result<String, String> {
+        if std::env::var_os("SWAYSOCK").is_none() {
+            return Err("not a sway session (SWAYSOCK is unset)".to_string());
+        }
+        require_tool("swaymsg").map_err(|e| e.to_string())?;
+        let output = sway_inputs().map_err(|e| e.to_string())?;
+        let (name, state) = parse_sway_touchpad(&output).ok_or("sway lists no touchpad")?;
+        Ok(format!("{}, events {:?}", name, state))
+    }
+
+    // `libinput list-devices` shows the devices but not whether they are
+    // enabled, and the tool has no command to switch them
+    fn probe_libinput() -> Result<String, String> {
+        require_tool(LIBINPUT_BACKEND_NAME).map_err(|e| e.to_string())?;
+        let output = run_checked(Command::new("libinput").arg("list-devices"), command_timeout())
+            .map_err(|e| e.to_string())?;
+        let name = output
+            .lines()
+            .filter_map(|line| line.strip_prefix("Device:"))
+            .map(str::trim)
+            .find(|name| name.to_ascii_lowercase().contains("touchpad"))
+            .ok_or("libinput lists no touchpad")?;
+        Err(format!("lists {} but can neither read nor switch its state", name))
+    }
+
+    fn probe_xinput() -> Result<String, String> {
+        if !display_available() {
+            return Err(ControllerError::NoDisplay.to_string());
+        }
+        require_tool(BACKEND).map_err(|e| e.to_string())?;
+        let device = select_device().map_err(|e| e.to_string())?;
+        let output = run_checked(
+            Command::new("xinput").args(["list-props", device.id.as_str()]),
+            command_timeout(),
+        )
+        .map_err(|e| e.to_string())?;
+        let value = parse_prop(&output, "Device Enabled")
+            .ok_or_else(|| format!("{} has no Device Enabled property", device.name))?;
+        Ok(format!("{} (id {}), Device Enabled = {}", device.name, device.id, value))
+    }
+
+    fn write_inhibit_switch(switch: &Path, inhibited: bool) -> Result<(), ControllerError> {
+        fs::write(switch, if inhibited { "1" } else { "0" })
+            .map_err(|_| ControllerError::PropertyUnsupported("inhibited"))
+    }
+
+    // Order of the flags in libinput's "Scroll Method" properties
+    const SCROLL_METHOD_ORDER: [ScrollMethod; 2] = [ScrollMethod::TwoFinger, ScrollMethod::Edge];
+
+    /// Value of exactly the property `prop` in `xinput list-props` output.
+    ///
+    /// Only "<prop> (<number>):" matches, so longer names sharing the prefix,
+    /// e.g. "Device Enabled Default" on some drivers, are ignored:
+    /// ```text
+    ///     Device Enabled (170):    1
+    ///     libinput Tapping Enabled (318):    1
+    /// ```
+    fn parse_prop(output: &str, prop: &str) -> Option<String> {
+        output.lines().find_map(|line| {
+            let rest = line.trim_start().strip_prefix(prop)?.strip_prefix(" (")?;
+            let (id, value) = rest.split_once("):")?;
+            if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            Some(value.trim().to_string())
+        })
+    }
+
+    /// Parses a flag list such as "1, 0, 0".
+    fn parse_flags(value: &str) -> Vec<bool> {
+        value.split(',').map(|flag| flag.trim() == "1").collect()
+    }
+
+    /// Parses an "Accel Speed" value such as "0.000000" or "-0.5".
+    fn parse_accel_speed(value: &str) -> Option<f64> {
+        value.trim().parse::<f64>().ok().filter(|v| v.is_finite())
+    }
+
+    pub struct LinuxTouchpadController {
+        // Can change after suspend/resume or a dock event, see `xinput`
+        device_id: Mutex<String>,
+        device_name: Mutex<String>,
+        /// When set, enable/disable bypass xinput.
+        switch: Mutex<Option<DirectSwitch>>,
+        /// Trackpoint next to the touchpad, always switched through xinput.
+        trackpoint: Mutex<Option<Device>>,
+        /// The trackpoint state last read or set, so `describe` doesn't run
+        /// xinput on every call.
+        trackpoint_state: Mutex<Option<TouchpadState>>,
+    }
+
+    fn list_devices() -> Result<String, ControllerError> {
+        run_checked(Command::new("xinput").arg("--list"), command_timeout())
+    }
+
+    /// The device to manage and a separate trackpoint, see `primary_and_trackpoint`.
+    fn select_devices() -> Result<(Device, Option<Device>), ControllerError> {
+        let (primary, trackpoint) = primary_and_trackpoint(&parse_xinput_list(&list_devices()?));
+        Ok((primary.ok_or(ControllerError::LinuxDeviceNotFound)?, trackpoint))
+    }
+
+    fn select_device() -> Result<Device, ControllerError> {
+        select_devices().map(|(device, _)| device)
     }
 
     impl LinuxTouchpadController {
         pub fn create() -> Result<Arc<Self>, ControllerError> {
-            let output = Command::new("xinput")
-                .arg("--list")
-                .output()
-                .map_err(|_| ControllerError::LinuxDeviceNotFound)?;
-            
-            let output_str = String::from_utf8_lossy(&output.stdout);
-            let device_line = output_str.lines()
-                .find(|line| line.contains("Touchpad") || line.contains("TrackPoint"))
+            // Works on X11 and Wayland alike, the kernel switch even without a display
+            if let Some((name, switch)) = direct_switch() {
+                log::info!("Switching {} through the {} backend", name, switch.backend());
+                let (device_id, trackpoint) = match display_available().then(select_devices) {
+                    Some(Ok((device, trackpoint))) => (device.id, trackpoint),
+                    _ => (String::new(), None),
+                };
+                return Ok(Arc::new(Self {
+                    device_id: Mutex::new(device_id),
+                    device_name: Mutex::new(name),
+                    switch: Mutex::new(Some(switch)),
+                    trackpoint: Mutex::new(trackpoint),
+                    trackpoint_state: Mutex::new(None),
+                }));
+            }
+
+            // Over SSH or on a server xinput fails confusingly, and the evdev
+            // backend is the only one that works without a display: say why it can't
+            let failure = headless_failure(display_available(), evdev_backend(), || {
+                probe_evdev()
+                    .err()
+                    .unwrap_or_else(|| format!("the {} backend is selected", preferred_backend().unwrap_or_else(|| BACKEND.to_string())))
+            });
+            if let Some(reason) = failure {
+                return Err(ControllerError::HeadlessUnavailable(reason));
+            }
+            require_tool(BACKEND)?;
+
+            let (device, trackpoint) = select_devices()?;
+            if let Some(trackpoint) = &trackpoint {
+                log::info!("Managing trackpoint {} (id {}) separately", trackpoint.name, trackpoint.id);
+            }
+            Ok(Arc::new(Self {
+                device_id: Mutex::new(device.id),
+                device_name: Mutex::new(device.name),
+                switch: Mutex::new(None),
+                trackpoint: Mutex::new(trackpoint),
+                trackpoint_state: Mutex::new(None),
+            }))
+        }
+
+        fn trackpoint(&self) -> Option<Device> {
+            lock_recovering(&self.trackpoint).clone()
+        }
+
+        /// Runs `xinput <command> <trackpoint id>`. If it fails, looks the
+        /// trackpoint up by name in case its id changed and retries once.
+        fn trackpoint_xinput(&self, command: &str) -> Result<String, ControllerError> {
+            let trackpoint = self.trackpoint().ok_or(ControllerError::PropertyUnsupported("trackpoint"))?;
+            let run = |device_id: &str| {
+                run_with_timeout(Command::new("xinput").arg(command).arg(device_id), command_timeout())
+            };
+
+            let (status, output) = run(&trackpoint.id)?;
+            if status.success() {
+                return Ok(output);
+            }
+            let current = parse_xinput_list(&list_devices()?)
+                .into_iter()
+                .find(|device| device.name == trackpoint.name)
                 .ok_or(ControllerError::LinuxDeviceNotFound)?;
-            
-            let device_id = device_line.split_whitespace()
-                .find(|part| part.starts_with("id="))
-                .and_then(|s| s.split('=').nth(1))
-                .ok_or(ControllerError::LinuxDeviceNotFound)?
-                .to_string();
-            
-            Ok(Arc::new(Self { device_id }))
+            let (status, output) = run(&current.id)?;
+            *lock_recovering(&self.trackpoint) = Some(current);
+            if !status.success() {
+                return Err(ControllerError::LinuxDeviceNotFound);
+            }
+            Ok(output)
+        }
+
+        fn cached_trackpoint_state(&self) -> Option<TouchpadState> {
+            let cached = *lock_recovering(&self.trackpoint_state);
+            cached.or_else(|| self.trackpoint().and_then(|_| self.get_trackpoint_state().ok()))
+        }
+
+        fn switch(&self) -> Option<DirectSwitch> {
+            lock_recovering(&self.switch).clone()
+        }
+
+        /// Looks the device up again by name and stores its current id.
+        /// Returns true if the id changed.
+        fn redetect(&self) -> bool {
+            let Ok(output_str) = list_devices() else {
+                return false;
+            };
+            let device_name = self.device_name();
+            let Some(new_id) = parse_xinput_list(&output_str)
+                .into_iter()
+                .find(|device| device.name == device_name)
+                .map(|device| device.id)
+            else {
+                return false;
+            };
+
+            let mut device_id = lock_recovering(&self.device_id);
+            if *device_id == new_id {
+                return false;
+            }
+            log::info!("Re-detected {} with id {} (was {})", device_name, new_id, *device_id);
+            *device_id = new_id;
+            true
+        }
+
+        /// Runs `xinput <command> <device id> <args...>`. If it fails, the id
+        /// may be stale, so re-detect the device and retry once. A non-zero
+        /// exit after that is a `CommandFailed`.
+        fn xinput(&self, command: &str, args: &[&str]) -> Result<String, ControllerError> {
+            // The evdev backend runs without a display and so without a device id
+            if self.device_id().is_empty() {
+                return Err(ControllerError::NoDisplay);
+            }
+            let run = |device_id: &str| {
+                run_checked(
+                    Command::new("xinput").arg(command).arg(device_id).args(args),
+                    command_timeout(),
+                )
+            };
+
+            match run(&self.device_id()) {
+                Err(ControllerError::CommandFailed(_)) if self.redetect() => run(&self.device_id()),
+                result => result,
+            }
+        }
+
+        /// Reads a device property value from `xinput list-props`.
+        fn read_prop(&self, prop: &'static str) -> Result<String, ControllerError> {
+            let output_str = self.xinput("list-props", &[])?;
+            parse_prop(&output_str, prop).ok_or(ControllerError::PropertyUnsupported(prop))
+        }
+
+        // xinput exits non-zero for a property the device doesn't have
+        fn set_prop(&self, prop: &'static str, values: &[&str]) -> Result<(), ControllerError> {
+            let mut args = vec![prop];
+            args.extend_from_slice(values);
+            match self.xinput("set-prop", &args) {
+                Ok(_) => Ok(()),
+                Err(ControllerError::CommandFailed(_)) => Err(ControllerError::PropertyUnsupported(prop)),
+                Err(e) => Err(e),
+            }
         }
     }
 
     impl TouchpadController for LinuxTouchpadController {
         fn enable(&self) -> Result<(), ControllerError> {
-            Command::new("xinput")
-                .args(["enable", &self.device_id])
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .status()
-                .map_err(|_| ControllerError::LinuxDeviceNotFound)?;
-                
-            match STATE.lock() {
-                Ok(mut state) => *state = TouchpadState::Enabled,
-                Err(e) => error!("Failed to acquire STATE lock: {}", e)
+            match self.switch() {
+                Some(switch) => switch.write(TouchpadState::Enabled)?,
+                None => {
+                    self.xinput("enable", &[])?;
+                }
             }
+                
+            *lock_recovering(&STATE) = TouchpadState::Enabled;
             
             Ok(())
         }
 
         fn disable(&self) -> Result<(), ControllerError> {
-            Command::new("xinput")
-                .args(["disable", &self.device_id])
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .status()
-                .map_err(|_| ControllerError::LinuxDeviceNotFound)?;
-                
-            match STATE.lock() {
-                Ok(mut state) => *state = TouchpadState::Disabled,
-                Err(e) => error!("Failed to acquire STATE lock: {}", e)
+            self.check_disable_allowed()?;
+            match self.switch() {
+                Some(switch) => switch.write(TouchpadState::Disabled)?,
+                None => {
+                    self.xinput("disable", &[])?;
+                }
             }
+                
+            *lock_recovering(&STATE) = TouchpadState::Disabled;
             
             Ok(())
         }
 
         fn get_state(&self) -> Result<TouchpadState, ControllerError> {
-            let output = Command::new("xinput")
-                .args(["list-props", &self.device_id])
-                .output()
-                .map_err(|_| ControllerError::LinuxDeviceNotFound)?;
-            
-            let output_str = String::from_utf8_lossy(&output.stdout);
+            if let Some(switch) = self.switch() {
+                let state = switch.read()?;
+                *lock_recovering(&STATE) = state;
+                return Ok(state);
+            }
+
+            let output_str = self.xinput("list-props", &[])?;
+
             // Parse the actual device state from xinput output
-            let enabled = if let Some(line) = output_str.lines().find(|line| line.contains("Device Enabled")) {
-                line.trim_end().ends_with("(1)")
-            } else {
-                false
-            };
+            let enabled = parse_prop(&output_str, "Device Enabled").as_deref() == Some("1");
             
             let state = if enabled {
                 TouchpadState::Enabled
@@ -277,13 +1652,310 @@ mod linux {
                 TouchpadState::Disabled
             };
             
-            match STATE.lock() {
-                Ok(mut state_guard) => *state_guard = state,
-                Err(e) => error!("Failed to acquire STATE lock: {}", e)
-            }
+            *lock_recovering(&STATE) = state;
             
             Ok(state)
         }
+
+        fn backend_name(&self) -> String {
+            self.switch().map_or(BACKEND, |switch| switch.backend()).to_string()
+        }
+
+        // The other backends only need xinput for device properties
+        fn required_tools(&self) -> Vec<&'static str> {
+            match self.switch() {
+                Some(switch) => switch.tool().into_iter().collect(),
+                None => vec![BACKEND],
+            }
+        }
+
+        fn describe(&self) -> DeviceInfo {
+            DeviceInfo {
+                name: self.device_name(),
+                backend: self.backend_name(),
+                natural_scrolling: self.get_natural_scrolling().ok(),
+                sensitivity: self.get_sensitivity().ok(),
+                scroll_method: self.get_scroll_method().ok(),
+                state: self.get_state().ok(),
+                trackpoint: self.trackpoint_name(),
+                trackpoint_state: self.cached_trackpoint_state(),
+            }
+        }
+
+        fn device_name(&self) -> String {
+            lock_recovering(&self.device_name).clone()
+        }
+
+        fn can_disable_device(&self) -> bool {
+            true
+        }
+
+        fn raw_diagnostics(&self) -> String {
+            let device_id = self.device_id();
+            let queries: [&[&str]; 2] = [&["--list"], &["list-props", &device_id]];
+            let mut report = String::new();
+            for args in queries {
+                report.push_str(&format!("$ xinput {}\n", args.join(" ")));
+                match run_with_timeout(Command::new("xinput").args(args), command_timeout()) {
+                    Ok((status, output)) => report.push_str(&format!("{}(exit: {})\n", output, status)),
+                    Err(e) => report.push_str(&format!("failed: {}\n", e)),
+                }
+            }
+            if let Some(switch) = self.switch() {
+                report.push_str(&switch.diagnostics());
+            }
+            report
+        }
+
+        fn redetect_device(&self) -> Result<DeviceInfo, ControllerError> {
+            if let Some((name, switch)) = direct_switch() {
+                log::info!("Now managing {} through the {} backend", name, switch.backend());
+                if let Ok((device, trackpoint)) = select_devices() {
+                    *lock_recovering(&self.device_id) = device.id;
+                    *lock_recovering(&self.trackpoint) = trackpoint;
+                }
+                *lock_recovering(&self.device_name) = name;
+                *lock_recovering(&self.switch) = Some(switch);
+                return Ok(self.describe());
+            }
+
+            let (device, trackpoint) = select_devices()?;
+            log::info!("Now managing {} (id {})", device.name, device.id);
+            *lock_recovering(&self.device_id) = device.id;
+            *lock_recovering(&self.device_name) = device.name;
+            *lock_recovering(&self.switch) = None;
+            *lock_recovering(&self.trackpoint) = trackpoint;
+            Ok(self.describe())
+        }
+
+        fn devices(&self) -> Result<Vec<Device>, ControllerError> {
+            Ok(parse_xinput_list(&list_devices()?))
+        }
+
+        fn device_id(&self) -> String {
+            lock_recovering(&self.device_id).clone()
+        }
+
+        fn device_present(&self) -> bool {
+            match self.switch() {
+                Some(DirectSwitch::Inhibit(switch)) => return switch.exists(),
+                Some(DirectSwitch::Gsettings) => {
+                    return fs::read_to_string(PROC_INPUT_DEVICES).map_or(true, |contents| {
+                        find_inhibit_switch(&contents, Path::new(SYS_ROOT)).is_some()
+                    })
+                }
+                Some(DirectSwitch::Sway) => {
+                    return sway_inputs().map_or(true, |output| parse_sway_touchpad(&output).is_some())
+                }
+                None => {}
+            }
+            let (device_id, device_name) = (self.device_id(), self.device_name());
+            let Ok(output) = list_devices() else {
+                return true;
+            };
+            let devices = parse_xinput_list(&output);
+            if devices.iter().any(|device| device.id == device_id && device.name == device_name) {
+                return true;
+            }
+            // xinput ids can change across a replug; adopt the same device's new id
+            match devices.into_iter().find(|device| device.name == device_name) {
+                Some(device) => {
+                    log::info!("{} is back with id {} (was {})", device_name, device.id, device_id);
+                    *lock_recovering(&self.device_id) = device.id;
+                    true
+                }
+                None => false,
+            }
+        }
+
+        // A disabled trackpoint can't stand in for the touchpad
+        fn can_disable_safely(&self) -> bool {
+            let mut devices = self.devices().unwrap_or_default();
+            if let Some(trackpoint) = self.trackpoint() {
+                if self.get_trackpoint_state().ok() == Some(TouchpadState::Disabled) {
+                    devices.retain(|device| device.name != trackpoint.name);
+                }
+            }
+            other_pointer_present(&devices, &self.device_id()) || mouse_detector::is_mouse_connected()
+        }
+
+        fn trackpoint_name(&self) -> Option<String> {
+            self.trackpoint().map(|trackpoint| trackpoint.name)
+        }
+
+        fn get_trackpoint_state(&self) -> Result<TouchpadState, ControllerError> {
+            let output = self.trackpoint_xinput("list-props")?;
+            let state = match parse_prop(&output, "Device Enabled").as_deref() {
+                Some("1") => TouchpadState::Enabled,
+                Some(_) => TouchpadState::Disabled,
+                None => return Err(ControllerError::PropertyUnsupported("Device Enabled")),
+            };
+            *lock_recovering(&self.trackpoint_state) = Some(state);
+            Ok(state)
+        }
+
+        fn set_trackpoint_state(&self, state: TouchpadState) -> Result<(), ControllerError> {
+            if state == TouchpadState::Disabled {
+                // With the touchpad off as well only an external mouse is left
+                let touchpad_off = self.get_state().ok() == Some(TouchpadState::Disabled);
+                if touchpad_off && !allow_disable_without_mouse() && !mouse_detector::is_mouse_connected() {
+                    return Err(ControllerError::WouldStrandUser);
+                }
+            }
+            let command = if state == TouchpadState::Enabled { "enable" } else { "disable" };
+            self.trackpoint_xinput(command)?;
+            *lock_recovering(&self.trackpoint_state) = Some(state);
+            Ok(())
+        }
+
+        fn set_tap_to_click(&self, enabled: bool) -> Result<(), ControllerError> {
+            self.set_prop("libinput Tapping Enabled", &[if enabled { "1" } else { "0" }])
+        }
+
+        fn get_tap_to_click(&self) -> Result<bool, ControllerError> {
+            Ok(self.read_prop("libinput Tapping Enabled")? == "1")
+        }
+
+        fn set_natural_scrolling(&self, enabled: bool) -> Result<(), ControllerError> {
+            self.set_prop("libinput Natural Scrolling Enabled", &[if enabled { "1" } else { "0" }])
+        }
+
+        fn get_natural_scrolling(&self) -> Result<bool, ControllerError> {
+            Ok(self.read_prop("libinput Natural Scrolling Enabled")? == "1")
+        }
+
+        // libinput already uses the normalized range
+        fn get_sensitivity(&self) -> Result<f64, ControllerError> {
+            parse_accel_speed(&self.read_prop("libinput Accel Speed")?)
+                .ok_or(ControllerError::PropertyUnsupported("libinput Accel Speed"))
+        }
+
+        fn set_sensitivity(&self, value: f64) -> Result<(), ControllerError> {
+            let value = format!("{:.6}", clamp_sensitivity(value)?);
+            self.set_prop("libinput Accel Speed", &[&value])
+        }
+
+        fn scroll_methods(&self) -> Result<Vec<ScrollMethod>, ControllerError> {
+            let available = parse_flags(&self.read_prop("libinput Scroll Methods Available")?);
+            Ok(SCROLL_METHOD_ORDER
+                .iter()
+                .zip(available)
+                .filter(|(_, available)| *available)
+                .map(|(method, _)| *method)
+                .collect())
+        }
+
+        fn get_scroll_method(&self) -> Result<ScrollMethod, ControllerError> {
+            let enabled = parse_flags(&self.read_prop("libinput Scroll Method Enabled")?);
+            SCROLL_METHOD_ORDER
+                .iter()
+                .zip(enabled)
+                .find(|(_, enabled)| *enabled)
+                .map(|(method, _)| *method)
+                .ok_or(ControllerError::PropertyUnsupported("libinput Scroll Method Enabled"))
+        }
+
+        fn set_scroll_method(&self, method: ScrollMethod) -> Result<(), ControllerError> {
+            if !self.scroll_methods()?.contains(&method) {
+                return Err(ControllerError::PropertyUnsupported("libinput Scroll Method Enabled"));
+            }
+            // One flag per method plus the trailing on-button-down flag, which stays off
+            let mut flags: Vec<&str> = SCROLL_METHOD_ORDER.iter().map(|m| if *m == method { "1" } else { "0" }).collect();
+            flags.push("0");
+            self.set_prop("libinput Scroll Method Enabled", &flags)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const LIST_PROPS: &str = "\
+Device 'SynPS/2 Synaptics TouchPad':
+	Device Enabled (170):	1
+	libinput Tapping Enabled (318):	1
+	libinput Accel Speed (327):	-0.250000
+	libinput Accel Speed Default (328):	0.000000
+	libinput Scroll Method Enabled (331):	1, 0, 0";
+
+        #[test]
+        fn reads_accel_speed_from_list_props() {
+            let value = parse_prop(LIST_PROPS, "libinput Accel Speed").unwrap();
+            assert_eq!(value, "-0.250000");
+            assert_eq!(parse_accel_speed(&value), Some(-0.25));
+            assert_eq!(parse_accel_speed("nan"), None);
+            // Older synaptics drivers have no libinput speed at all
+            assert_eq!(parse_prop("\tDevice Enabled (144):\t1", "libinput Accel Speed"), None);
+        }
+
+        #[test]
+        fn reads_session_touchpad_settings() {
+            assert_eq!(parse_send_events("'enabled'\n"), Some(TouchpadState::Enabled));
+            assert_eq!(parse_send_events("'disabled-on-external-mouse'"), Some(TouchpadState::Enabled));
+            assert_eq!(parse_send_events("'disabled'"), Some(TouchpadState::Disabled));
+            assert_eq!(parse_send_events("No such schema"), None);
+
+            let inputs = r#"[
+                {"identifier": "1:1:AT_Translated_Set_2_keyboard", "name": "AT Translated Set 2 keyboard", "type": "keyboard"},
+                {"identifier": "1739:52710:SYNA8004:00_06CB:CE67_Touchpad", "name": "SYNA8004:00 06CB:CE67 Touchpad",
+                 "type": "touchpad", "libinput": {"send_events": "disabled", "tap": "enabled"}}
+            ]"#;
+            assert_eq!(
+                parse_sway_touchpad(inputs),
+                Some(("SYNA8004:00 06CB:CE67 Touchpad".to_string(), TouchpadState::Disabled))
+            );
+            assert_eq!(parse_sway_touchpad("[]"), None);
+        }
+
+        #[test]
+        fn reads_only_the_exact_device_enabled_property() {
+            // Some drivers list look-alike properties before the real one
+            let output = "\
+Device 'ETPS/2 Elantech Touchpad':
+	Device Enabled Default (171):	1
+	Synaptics Device Enabled (290):	1
+	Device Enabled (170):	0
+	Device Enabled (abc):	1";
+            assert_eq!(parse_prop(output, "Device Enabled").as_deref(), Some("0"));
+            assert_eq!(parse_prop(LIST_PROPS, "Device Enabled").as_deref(), Some("1"));
+            assert_eq!(parse_prop("\tDevice Enabled Default (171):\t1", "Device Enabled"), None);
+        }
+
+        const PROC_DEVICES: &str = "\
+I: Bus=0011 Vendor=0002 Product=0007 Version=01b1
+N: Name=\"TPPS/2 IBM TrackPoint\"
+S: Sysfs=/devices/platform/i8042/serio1/serio2/input/input6
+
+I: Bus=0018 Vendor=06cb Product=ce67 Version=0100
+N: Name=\"SYNA8004:00 06CB:CE67 Touchpad\"
+S: Sysfs=/devices/pci0000:00/0000:00:15.1/i2c_designware.1/i2c-2/i2c-SYNA8004:00/0018:06CB:CE67.0002/input/input17
+";
+
+        #[test]
+        fn resolves_the_inhibit_switch_below_sys_root() {
+            let sys_root = Path::new("/tmp/sys-fixture");
+            let (name, switch) = find_inhibit_switch(PROC_DEVICES, sys_root).unwrap();
+            assert_eq!(name, "SYNA8004:00 06CB:CE67 Touchpad");
+            assert_eq!(
+                switch,
+                sys_root.join("devices/pci0000:00/0000:00:15.1/i2c_designware.1/i2c-2/i2c-SYNA8004:00/0018:06CB:CE67.0002/input/input17/inhibited")
+            );
+
+            // Without a touchpad the trackpoint is switched instead
+            let trackpoint_only = PROC_DEVICES.split("\n\n").next().unwrap();
+            let (name, switch) = find_inhibit_switch(trackpoint_only, sys_root).unwrap();
+            assert_eq!(name, "TPPS/2 IBM TrackPoint");
+            assert!(switch.ends_with("serio2/input/input6/inhibited"));
+            assert_eq!(find_inhibit_switch("", sys_root), None);
+        }
+
+        #[test]
+        fn detects_input_group_membership() {
+            let etc_group = "root:x:0:\nwheel:x:10:alice\ninput:x:104:alice\n";
+            assert!(in_input_group(etc_group, "Name:\tapp\nGroups:\t10 104 1000 \n"));
+            assert!(!in_input_group(etc_group, "Name:\tapp\nGroups:\t10 1000\n"));
+            assert!(!in_input_group("root:x:0:\n", "Groups:\t104\n"));
+        }
     }
 }
 
@@ -294,8 +1966,249 @@ pub use macos::MacosTouchpadController as PlatformTouchpadController;
 #[cfg(target_os = "linux")]
 pub use linux::LinuxTouchpadController as PlatformTouchpadController;
 
+/// Name of the mechanism the platform controller uses to switch the device.
+#[cfg(target_os = "windows")]
+pub const BACKEND: &str = "win32";
+#[cfg(target_os = "macos")]
+pub const BACKEND: &str = "applescript";
+#[cfg(target_os = "linux")]
+pub const BACKEND: &str = "xinput";
+
+/// Whether the OS lets the app switch the touchpad. Only macOS puts this
+/// behind a permission; elsewhere failures are reported per backend.
+pub fn permissions_granted() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        macos::accessibility_trusted()
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        true
+    }
+}
+
 impl PlatformTouchpadController {
     pub fn new() -> Result<Arc<Self>, ControllerError> {
         Self::create()
     }
 }
+
+/// Async counterparts of the controller calls, for commands. Each call runs
+/// on the blocking thread pool so a slow backend (AppleScript, a hung xinput)
+/// never stalls the caller. Switching goes through the hotkey manager's
+/// worker instead, and the sync trait stays for internal use.
+impl PlatformTouchpadController {
+    async fn run_blocking<T: Send + 'static>(
+        self: &Arc<Self>,
+        call: impl FnOnce(&Self) -> Result<T, ControllerError> + Send + 'static,
+    ) -> Result<T, ControllerError> {
+        let controller = Arc::clone(self);
+        tauri::async_runtime::spawn_blocking(move || call(&controller))
+            .await
+            .map_err(|e| ControllerError::TaskFailed(e.to_string()))?
+    }
+
+    pub async fn get_state_async(self: &Arc<Self>) -> Result<TouchpadState, ControllerError> {
+        self.run_blocking(|controller| controller.get_state()).await
+    }
+
+    pub async fn get_sensitivity_async(self: &Arc<Self>) -> Result<f64, ControllerError> {
+        self.run_blocking(|controller| controller.get_sensitivity()).await
+    }
+
+    pub async fn get_scroll_method_async(self: &Arc<Self>) -> Result<ScrollMethod, ControllerError> {
+        self.run_blocking(|controller| controller.get_scroll_method()).await
+    }
+
+    pub async fn scroll_methods_async(self: &Arc<Self>) -> Result<Vec<ScrollMethod>, ControllerError> {
+        self.run_blocking(|controller| controller.scroll_methods()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sensitivity_levels_cycle_and_wrap_around() {
+        let levels = [-0.5, 0.0, 0.5];
+        assert_eq!(next_sensitivity_level(&levels, Some(-0.5)), Some((1, 0.0)));
+        assert_eq!(next_sensitivity_level(&levels, Some(0.0)), Some((2, 0.5)));
+        assert_eq!(next_sensitivity_level(&levels, Some(0.5)), Some((0, -0.5)));
+        // Off-level speeds continue from the closest level, unknown ones start over
+        assert_eq!(next_sensitivity_level(&levels, Some(0.1)), Some((2, 0.5)));
+        assert_eq!(next_sensitivity_level(&levels, None), Some((0, -0.5)));
+        assert_eq!(next_sensitivity_level(&[], Some(0.0)), None);
+        assert_eq!(clamp_sensitivity(3.0).unwrap(), SENSITIVITY_MAX);
+        assert!(clamp_sensitivity(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn applescript_retries_only_a_set_state_script() {
+        // A mocked System Events: the checkbox changes, then the reply is lost twice
+        let mut checked = true;
+        let mut calls = 0;
+        let script = ignore_trackpad_script(false);
+        let result = run_script_with_retry(2, Duration::ZERO, || {
+            calls += 1;
+            if script.contains("if value of ignoreBox is not 0 then click") && checked {
+                checked = false;
+            }
+            if calls <= 2 {
+                Err(ScriptError { code: -1712, message: "AppleEvent timed out".to_string() })
+            } else {
+                Ok(())
+            }
+        });
+        assert!(result.is_ok());
+        assert_eq!(calls, 3);
+        // The retries found it already unchecked instead of clicking it back
+        assert!(!checked);
+        assert!(!script.contains("\nclick checkbox"));
+
+        let mut calls = 0;
+        let denied = run_script_with_retry(2, Duration::ZERO, || {
+            calls += 1;
+            Err(ScriptError { code: -1743, message: "Not authorized".to_string() })
+        });
+        assert!(matches!(denied, Err(ControllerError::MacOsPermissionRequired)));
+        assert_eq!(calls, 1);
+
+        let gave_up = run_script_with_retry(1, Duration::ZERO, || Err(ScriptError { code: -1, message: "busy".to_string() }));
+        assert!(matches!(gave_up, Err(ControllerError::MacOsScriptFailed(_))));
+    }
+
+    #[test]
+    fn missing_tool_is_reported_by_name() {
+        let tool = "enable-touchpad-no-such-tool";
+        assert_eq!(find_in_path(tool), None);
+        match require_tool(tool) {
+            Err(e @ ControllerError::BackendToolMissing(_)) => assert!(e.to_string().contains(tool)),
+            other => panic!("expected BackendToolMissing, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resolve_state_follows_the_state_source() {
+        let read_ok = || Ok(TouchpadState::Disabled);
+        let read_err = || Err(ControllerError::Timeout);
+        let intent = TouchpadState::Enabled;
+
+        // Cache never reads the device
+        let never = || -> Result<TouchpadState, ControllerError> { panic!("cache mode read the device") };
+        assert_eq!(resolve_state(StateSource::Cache, never, intent).unwrap(), intent);
+
+        assert_eq!(resolve_state(StateSource::Read, read_ok, intent).unwrap(), TouchpadState::Disabled);
+        assert!(matches!(resolve_state(StateSource::Read, read_err, intent), Err(ControllerError::Timeout)));
+
+        // Auto prefers the real state and falls back to the intent
+        assert_eq!(resolve_state(StateSource::Auto, read_ok, intent).unwrap(), TouchpadState::Disabled);
+        assert_eq!(resolve_state(StateSource::Auto, read_err, intent).unwrap(), intent);
+    }
+
+    #[test]
+    fn sensitivity_maps_onto_platform_scales() {
+        // Windows' 0..=20 slider with its default at 10
+        assert_eq!(sensitivity_to_platform(0.0, 0.0, 10.0, 20.0), 10.0);
+        assert_eq!(sensitivity_to_platform(-1.0, 0.0, 10.0, 20.0), 0.0);
+        assert_eq!(sensitivity_to_platform(0.5, 0.0, 10.0, 20.0), 15.0);
+        // macOS' lopsided 0..=3 scale with its default at 1
+        assert_eq!(sensitivity_from_platform(2.0, 0.0, 1.0, 3.0), 0.5);
+        assert_eq!(sensitivity_from_platform(0.5, 0.0, 1.0, 3.0), -0.5);
+        // Out-of-range raw values are clamped rather than overshooting
+        assert_eq!(sensitivity_from_platform(7.0, 0.0, 1.0, 3.0), 1.0);
+
+        assert_eq!(clamp_sensitivity(4.0).unwrap(), SENSITIVITY_MAX);
+        assert!(clamp_sensitivity(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn reads_sensitivity_from_windows_and_macos_output() {
+        let reg = "\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\PrecisionTouchPad\r\n    CursorSpeed    REG_DWORD    0xe\r\n";
+        assert_eq!(parse_reg_dword(reg, "CursorSpeed"), Some(14));
+        assert_eq!(parse_reg_dword(reg, "Cursor"), None);
+        assert_eq!(parse_reg_dword("    CursorSpeed    REG_SZ    fast", "CursorSpeed"), None);
+
+        assert_eq!(parse_defaults_float("0.6875\n"), Some(0.6875));
+        // Printed when the key was never set
+        assert_eq!(parse_defaults_float(""), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn run_with_timeout_kills_a_hung_command() {
+        let started = Instant::now();
+        let result = run_with_timeout(Command::new("sleep").arg("5"), Duration::from_millis(100));
+        assert!(matches!(result, Err(ControllerError::Timeout)));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn run_with_timeout_returns_the_output() {
+        let (status, output) = run_with_timeout(Command::new("echo").arg("1"), Duration::from_secs(2)).unwrap();
+        assert!(status.success());
+        assert_eq!(output.trim(), "1");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn run_checked_rejects_a_non_zero_exit() {
+        let result = run_checked(&mut Command::new("false"), Duration::from_secs(2));
+        assert!(matches!(result, Err(ControllerError::CommandFailed(_))));
+    }
+
+    #[test]
+    fn failed_read_falls_back_to_the_last_intent() {
+        let failed_read = || Err(ControllerError::CommandFailed("defaults read failed".to_string()));
+        let state = resolve_state(StateSource::Auto, failed_read, TouchpadState::Disabled);
+        assert_eq!(state.unwrap(), TouchpadState::Disabled);
+
+        let state = resolve_state(StateSource::Auto, || Ok(TouchpadState::Enabled), TouchpadState::Disabled);
+        assert_eq!(state.unwrap(), TouchpadState::Enabled);
+    }
+
+    fn device(id: &str, name: &str, kind: DeviceKind) -> Device {
+        Device { id: id.to_string(), name: name.to_string(), kind }
+    }
+
+    #[test]
+    fn touchpad_alone_is_the_last_pointer() {
+        let devices = [
+            device("4", "Virtual core XTEST pointer", DeviceKind::Other),
+            device("12", "SynPS/2 Synaptics TouchPad", DeviceKind::Touchpad),
+        ];
+        assert!(!other_pointer_present(&devices, "12"));
+        assert!(!other_pointer_present(&[], "12"));
+    }
+
+    #[test]
+    fn mouse_or_trackpoint_keeps_a_pointer() {
+        let touchpad = device("12", "SynPS/2 Synaptics TouchPad", DeviceKind::Touchpad);
+        let mouse = device("9", "Logitech USB Receiver", DeviceKind::Mouse);
+        let trackpoint = device("13", "TPPS/2 IBM TrackPoint", DeviceKind::Trackpoint);
+        assert!(other_pointer_present(&[touchpad.clone(), mouse], "12"));
+        assert!(other_pointer_present(&[touchpad.clone(), trackpoint], "12"));
+        // The managed device itself never counts
+        assert!(!other_pointer_present(&[touchpad], "12"));
+    }
+
+    #[test]
+    fn ignore_preference_only_disables_with_a_mouse() {
+        assert_eq!(trackpad_state_from(true, true), TouchpadState::Disabled);
+        assert_eq!(trackpad_state_from(true, false), TouchpadState::Enabled);
+        assert_eq!(trackpad_state_from(false, true), TouchpadState::Enabled);
+    }
+
+    #[test]
+    fn headless_failure_explains_why_no_backend_works() {
+        let unasked = || -> String { panic!("only asked without a display and with evdev on") };
+        assert_eq!(headless_failure(true, false, unasked), None);
+        assert_eq!(headless_failure(true, true, unasked), None);
+
+        let reason = headless_failure(false, false, unasked).unwrap();
+        assert!(reason.contains("evdev_backend setting"), "{}", reason);
+        let not_writable = || "/sys/devices/inhibited is not writable".to_string();
+        assert_eq!(headless_failure(false, true, not_writable).as_deref(), Some("/sys/devices/inhibited is not writable"));
+    }
+}
@@ -0,0 +1,45 @@
+//! Shared low-level keyboard (and pointer) listener.
+//!
+//! `rdev::listen` blocks forever and can't be stopped, so a single listener
+//! thread is started on first use and fans events out to subscribers.
+//! Subscribing and unsubscribing is cheap; with no subscribers events are dropped.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, Once};
+use once_cell::sync::Lazy;
+use log::warn;
+use rdev::EventType;
+use crate::core::state::lock_recovering;
+
+type KeyHandler = Box<dyn Fn(&EventType) + Send>;
+
+static SUBSCRIBERS: Lazy<Mutex<Vec<(u64, KeyHandler)>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static STARTED: Once = Once::new();
+
+fn ensure_started() {
+    STARTED.call_once(|| {
+        std::thread::spawn(|| {
+            let result = rdev::listen(|event| {
+                for (_, handler) in lock_recovering(&SUBSCRIBERS).iter() {
+                    handler(&event.event_type);
+                }
+            });
+            if let Err(e) = result {
+                warn!("Low-level key listener unavailable: {:?}", e);
+            }
+        });
+    });
+}
+
+/// Registers `handler` for every key and pointer event; handlers must not (un)subscribe.
+pub fn subscribe(handler: impl Fn(&EventType) + Send + 'static) -> u64 {
+    ensure_started();
+    let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+    lock_recovering(&SUBSCRIBERS).push((id, Box::new(handler)));
+    id
+}
+
+pub fn unsubscribe(id: u64) {
+    lock_recovering(&SUBSCRIBERS).retain(|(subscriber, _)| *subscriber != id);
+}
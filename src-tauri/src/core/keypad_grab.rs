@@ -0,0 +1,343 @@
+//! Takes the numeric keypad away from the focused application while mouse
+//! keys use it, so moving the cursor doesn't also type digits.
+//!
+//! Grabbed keys no longer reach the shared key listener on every platform
+//! (the OS may run our hook before the listener's), so the grab reports the
+//! keys it swallows to its own handler.
+
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+use rdev::{EventType, Key};
+use crate::core::state::lock_recovering;
+use crate::core::supervisor;
+
+const WORKER: &str = "keypad-grab";
+
+/// The keypad keys mouse keys moves the cursor with.
+#[cfg(any(target_os = "linux", test))]
+const GRABBED_KEYS: [Key; 8] =
+    [Key::Kp1, Key::Kp2, Key::Kp3, Key::Kp4, Key::Kp6, Key::Kp7, Key::Kp8, Key::Kp9];
+
+type KeyHandler = Box<dyn Fn(&EventType) + Send>;
+
+static HANDLER: Lazy<Mutex<Option<KeyHandler>>> = Lazy::new(|| Mutex::new(None));
+
+/// Swallows the mouse-keys keypad keys until `release`, passing their
+/// presses and releases to `handler` instead.
+#[allow(dead_code)]
+pub fn grab(handler: impl Fn(&EventType) + Send + 'static) {
+    release();
+    *lock_recovering(&HANDLER) = Some(Box::new(handler));
+    supervisor::spawn(WORKER, platform::run);
+}
+
+/// Gives the keypad back to applications. Idempotent.
+#[allow(dead_code)]
+pub fn release() {
+    supervisor::stop(WORKER);
+    lock_recovering(&HANDLER).take();
+}
+
+fn dispatch(event: EventType) {
+    if let Some(handler) = lock_recovering(&HANDLER).as_ref() {
+        handler(&event);
+    }
+}
+
+/// The keypad key for a low-level Windows key event. With Num Lock off the
+/// keypad sends navigation keys, told apart from the dedicated ones by the
+/// missing extended-key flag.
+#[cfg(any(target_os = "windows", test))]
+fn windows_keypad_key(vk: u32, extended: bool) -> Option<Key> {
+    match (vk, extended) {
+        (0x61, _) | (0x23, false) => Some(Key::Kp1),
+        (0x62, _) | (0x28, false) => Some(Key::Kp2),
+        (0x63, _) | (0x22, false) => Some(Key::Kp3),
+        (0x64, _) | (0x25, false) => Some(Key::Kp4),
+        (0x66, _) | (0x27, false) => Some(Key::Kp6),
+        (0x67, _) | (0x24, false) => Some(Key::Kp7),
+        (0x68, _) | (0x26, false) => Some(Key::Kp8),
+        (0x69, _) | (0x21, false) => Some(Key::Kp9),
+        _ => None,
+    }
+}
+
+/// The keypad key for a macOS virtual key code (`kVK_ANSI_Keypad*`).
+#[cfg(any(target_os = "macos", test))]
+fn macos_keypad_key(keycode: i64) -> Option<Key> {
+    match keycode {
+        0x53 => Some(Key::Kp1),
+        0x54 => Some(Key::Kp2),
+        0x55 => Some(Key::Kp3),
+        0x56 => Some(Key::Kp4),
+        0x58 => Some(Key::Kp6),
+        0x59 => Some(Key::Kp7),
+        0x5B => Some(Key::Kp8),
+        0x5C => Some(Key::Kp9),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use log::warn;
+    use rdev::EventType;
+    use windows::Win32::Foundation::{BOOL, HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, DispatchMessageW, MsgWaitForMultipleObjects, PeekMessageW, SetWindowsHookExW,
+        TranslateMessage, UnhookWindowsHookEx, HC_ACTION, HHOOK, KBDLLHOOKSTRUCT, LLKHF_EXTENDED, LLKHF_INJECTED,
+        MSG, PM_REMOVE, QS_ALLINPUT, WH_KEYBOARD_LL, WM_KEYDOWN, WM_KEYUP, WM_SYSKEYDOWN, WM_SYSKEYUP,
+    };
+    use super::{dispatch, windows_keypad_key};
+    use crate::core::supervisor::StopToken;
+
+    unsafe extern "system" fn hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code == HC_ACTION as i32 {
+            let event = &*(lparam.0 as *const KBDLLHOOKSTRUCT);
+            // Mouse keys never injects keys, but other tools might
+            if !event.flags.contains(LLKHF_INJECTED) {
+                let extended = event.flags.contains(LLKHF_EXTENDED);
+                if let Some(key) = windows_keypad_key(event.vkCode, extended) {
+                    match wparam.0 as u32 {
+                        WM_KEYDOWN | WM_SYSKEYDOWN => dispatch(EventType::KeyPress(key)),
+                        WM_KEYUP | WM_SYSKEYUP => dispatch(EventType::KeyRelease(key)),
+                        _ => {}
+                    }
+                    return LRESULT(1);
+                }
+            }
+        }
+        CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+    }
+
+    // Low-level hooks run on the installing thread, which must pump messages
+    pub fn run(token: StopToken) {
+        unsafe {
+            let instance = GetModuleHandleW(None).map(HINSTANCE::from).unwrap_or_default();
+            let hook = match SetWindowsHookExW(WH_KEYBOARD_LL, Some(hook), instance, 0) {
+                Ok(hook) => hook,
+                Err(e) => {
+                    warn!("Failed to grab keypad keys: {}", e);
+                    return;
+                }
+            };
+            let mut message = MSG::default();
+            while !token.is_stopped() {
+                while PeekMessageW(&mut message, HWND::default(), 0, 0, PM_REMOVE).as_bool() {
+                    let _ = TranslateMessage(&message);
+                    DispatchMessageW(&message);
+                }
+                // Wakes for hook calls, or to check the stop token
+                MsgWaitForMultipleObjects(None, BOOL(0), 50, QS_ALLINPUT);
+            }
+            let _ = UnhookWindowsHookEx(hook);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+    use std::ptr;
+    use std::sync::atomic::{AtomicPtr, Ordering};
+    use log::warn;
+    use rdev::EventType;
+    use super::{dispatch, macos_keypad_key};
+    use crate::core::supervisor::StopToken;
+
+    const SESSION_EVENT_TAP: u32 = 1;
+    const HEAD_INSERT_EVENT_TAP: u32 = 0;
+    const EVENT_TAP_OPTION_DEFAULT: u32 = 0;
+    const KEY_DOWN: u32 = 10;
+    const KEY_UP: u32 = 11;
+    const TAP_DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+    const KEYBOARD_EVENT_KEYCODE: u32 = 9;
+
+    type TapCallback = extern "C" fn(*mut c_void, u32, *mut c_void, *mut c_void) -> *mut c_void;
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn CGEventTapCreate(
+            tap: u32,
+            place: u32,
+            options: u32,
+            events_of_interest: u64,
+            callback: TapCallback,
+            user_info: *mut c_void,
+        ) -> *mut c_void;
+        fn CGEventTapEnable(tap: *mut c_void, enable: bool);
+        fn CGEventGetIntegerValueField(event: *mut c_void, field: u32) -> i64;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFRunLoopDefaultMode: *const c_void;
+        fn CFMachPortCreateRunLoopSource(allocator: *const c_void, port: *mut c_void, order: isize) -> *mut c_void;
+        fn CFMachPortInvalidate(port: *mut c_void);
+        fn CFRunLoopGetCurrent() -> *mut c_void;
+        fn CFRunLoopAddSource(run_loop: *mut c_void, source: *mut c_void, mode: *const c_void);
+        fn CFRunLoopRunInMode(mode: *const c_void, seconds: f64, return_after_source_handled: u8) -> i32;
+        fn CFRelease(object: *const c_void);
+    }
+
+    // For re-enabling the tap after the OS disabled it for being slow
+    static TAP: AtomicPtr<c_void> = AtomicPtr::new(ptr::null_mut());
+
+    extern "C" fn callback(_proxy: *mut c_void, kind: u32, event: *mut c_void, _user: *mut c_void) -> *mut c_void {
+        if kind == TAP_DISABLED_BY_TIMEOUT {
+            unsafe { CGEventTapEnable(TAP.load(Ordering::SeqCst), true) };
+            return event;
+        }
+        let keycode = unsafe { CGEventGetIntegerValueField(event, KEYBOARD_EVENT_KEYCODE) };
+        let Some(key) = macos_keypad_key(keycode) else {
+            return event;
+        };
+        match kind {
+            KEY_DOWN => dispatch(EventType::KeyPress(key)),
+            KEY_UP => dispatch(EventType::KeyRelease(key)),
+            _ => return event,
+        }
+        // Returning no event drops it
+        ptr::null_mut()
+    }
+
+    // Needs the Accessibility permission, like the key listener
+    pub fn run(token: StopToken) {
+        unsafe {
+            let mask = (1u64 << KEY_DOWN) | (1u64 << KEY_UP);
+            let tap = CGEventTapCreate(
+                SESSION_EVENT_TAP,
+                HEAD_INSERT_EVENT_TAP,
+                EVENT_TAP_OPTION_DEFAULT,
+                mask,
+                callback,
+                ptr::null_mut(),
+            );
+            if tap.is_null() {
+                warn!("Failed to grab keypad keys: event tap not permitted");
+                return;
+            }
+            TAP.store(tap, Ordering::SeqCst);
+            let source = CFMachPortCreateRunLoopSource(ptr::null(), tap, 0);
+            CFRunLoopAddSource(CFRunLoopGetCurrent(), source, kCFRunLoopDefaultMode);
+            CGEventTapEnable(tap, true);
+            while !token.is_stopped() {
+                CFRunLoopRunInMode(kCFRunLoopDefaultMode, 0.05, 0);
+            }
+            CGEventTapEnable(tap, false);
+            TAP.store(ptr::null_mut(), Ordering::SeqCst);
+            CFMachPortInvalidate(tap);
+            CFRelease(source);
+            CFRelease(tap);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::os::raw::c_int;
+    use std::ptr;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+    use log::warn;
+    use rdev::{EventType, Key};
+    use x11::{keysym, xlib};
+    use super::{dispatch, GRABBED_KEYS};
+    use crate::core::supervisor::StopToken;
+
+    static GRAB_FAILED: AtomicBool = AtomicBool::new(false);
+
+    // Another client holding a key grab makes XGrabKey fail with BadAccess,
+    // which the default handler would turn into an exit
+    unsafe extern "C" fn record_error(_display: *mut xlib::Display, _event: *mut xlib::XErrorEvent) -> c_int {
+        GRAB_FAILED.store(true, Ordering::SeqCst);
+        0
+    }
+
+    fn keysym(key: Key) -> u32 {
+        match key {
+            Key::Kp1 => keysym::XK_KP_1,
+            Key::Kp2 => keysym::XK_KP_2,
+            Key::Kp3 => keysym::XK_KP_3,
+            Key::Kp4 => keysym::XK_KP_4,
+            Key::Kp6 => keysym::XK_KP_6,
+            Key::Kp7 => keysym::XK_KP_7,
+            Key::Kp8 => keysym::XK_KP_8,
+            _ => keysym::XK_KP_9,
+        }
+    }
+
+    // Only X clients lose the keys; Wayland-native windows still get them
+    pub fn run(token: StopToken) {
+        unsafe {
+            let display = xlib::XOpenDisplay(ptr::null());
+            if display.is_null() {
+                warn!("Failed to grab keypad keys: no X display");
+                return;
+            }
+            let root = xlib::XDefaultRootWindow(display);
+            // Num Lock doesn't change the keycode, so one grab per key covers both layers
+            let keys: Vec<(c_int, Key)> = GRABBED_KEYS
+                .iter()
+                .map(|key| (xlib::XKeysymToKeycode(display, keysym(*key) as xlib::KeySym) as c_int, *key))
+                .filter(|(keycode, _)| *keycode != 0)
+                .collect();
+
+            GRAB_FAILED.store(false, Ordering::SeqCst);
+            let previous = xlib::XSetErrorHandler(Some(record_error));
+            for (keycode, _) in &keys {
+                xlib::XGrabKey(display, *keycode, xlib::AnyModifier, root, xlib::False, xlib::GrabModeAsync, xlib::GrabModeAsync);
+            }
+            xlib::XSync(display, xlib::False);
+            xlib::XSetErrorHandler(previous);
+            if GRAB_FAILED.load(Ordering::SeqCst) {
+                warn!("Some keypad keys are grabbed by another application and still reach it");
+            }
+
+            let mut event: xlib::XEvent = std::mem::zeroed();
+            'poll: loop {
+                while xlib::XPending(display) > 0 {
+                    xlib::XNextEvent(display, &mut event);
+                    let kind = event.get_type();
+                    if kind != xlib::KeyPress && kind != xlib::KeyRelease {
+                        continue;
+                    }
+                    let keycode = event.key.keycode as c_int;
+                    if let Some((_, key)) = keys.iter().find(|(grabbed, _)| *grabbed == keycode) {
+                        dispatch(if kind == xlib::KeyPress { EventType::KeyPress(*key) } else { EventType::KeyRelease(*key) });
+                    }
+                }
+                if !token.sleep(Duration::from_millis(10)) {
+                    break 'poll;
+                }
+            }
+            // Closing the connection releases its grabs
+            xlib::XCloseDisplay(display);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_both_num_lock_layers_on_windows() {
+        // VK_NUMPAD8 with Num Lock on, VK_UP from the keypad with it off
+        assert_eq!(windows_keypad_key(0x68, false), Some(Key::Kp8));
+        assert_eq!(windows_keypad_key(0x26, false), Some(Key::Kp8));
+        // The dedicated arrow keys are extended and stay with the application
+        assert_eq!(windows_keypad_key(0x26, true), None);
+        // Keypad 5 and 0 aren't used by mouse keys
+        assert_eq!(windows_keypad_key(0x65, false), None);
+        assert_eq!(windows_keypad_key(0x60, false), None);
+    }
+
+    #[test]
+    fn grabs_only_direction_keys() {
+        let mac: Vec<Key> = (0x52..=0x5C).filter_map(macos_keypad_key).collect();
+        assert_eq!(mac.len(), GRABBED_KEYS.len());
+        assert!(GRABBED_KEYS.iter().all(|key| mac.contains(key)));
+    }
+}
@@ -0,0 +1,113 @@
+//! Mirrors the touchpad state on a laptop LED, for feedback with the OSD off.
+//!
+//! Only Linux exposes LEDs we can drive, through `/sys/class/leds`. Elsewhere
+//! `available` is false and `show` and `restore` do nothing.
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use log::warn;
+    use crate::core::state::lock_recovering;
+
+    const LEDS_DIR: &str = "/sys/class/leds";
+
+    /// Name fragments of suitable LEDs, best match first. Only LEDs meant for
+    /// the touchpad qualify; borrowing another one, like the keyboard
+    /// backlight, would fight the user's own use of it.
+    const CANDIDATES: &[&str] = &["touchpad"];
+
+    /// Set once a write has failed, so a missing permission is logged once
+    /// instead of on every toggle.
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    /// The LED and its brightness before our first write, put back by `restore`.
+    static SAVED: Mutex<Option<(PathBuf, String)>> = Mutex::new(None);
+
+    fn find_led() -> Option<PathBuf> {
+        let names: Vec<String> = fs::read_dir(LEDS_DIR)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        CANDIDATES.iter().find_map(|fragment| {
+            names
+                .iter()
+                .find(|name| name.to_lowercase().contains(fragment))
+                .map(|name| PathBuf::from(LEDS_DIR).join(name))
+        })
+    }
+
+    pub fn available() -> bool {
+        find_led().is_some()
+    }
+
+    pub fn show(disabled: bool) {
+        let Some(led) = find_led() else {
+            return;
+        };
+        {
+            let mut saved = lock_recovering(&SAVED);
+            if saved.is_none() {
+                if let Ok(previous) = fs::read_to_string(led.join("brightness")) {
+                    *saved = Some((led.clone(), previous.trim().to_string()));
+                }
+            }
+        }
+        let brightness = if disabled {
+            fs::read_to_string(led.join("max_brightness"))
+                .map(|max| max.trim().to_string())
+                .unwrap_or_else(|_| "1".to_string())
+        } else {
+            "0".to_string()
+        };
+        write_brightness(&led, &brightness);
+    }
+
+    pub fn restore() {
+        if let Some((led, brightness)) = lock_recovering(&SAVED).take() {
+            write_brightness(&led, &brightness);
+        }
+    }
+
+    fn write_brightness(led: &std::path::Path, brightness: &str) {
+        match fs::write(led.join("brightness"), brightness) {
+            Ok(()) => WARNED.store(false, Ordering::Relaxed),
+            Err(e) => {
+                if !WARNED.swap(true, Ordering::Relaxed) {
+                    warn!("Failed to set LED {}: {}", led.display(), e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    pub fn available() -> bool {
+        false
+    }
+
+    pub fn show(_disabled: bool) {}
+
+    pub fn restore() {}
+}
+
+/// Whether there is an LED that could mirror the touchpad state.
+pub fn available() -> bool {
+    platform::available()
+}
+
+/// Lights the LED while the touchpad is disabled and turns it off otherwise.
+/// Missing or unwritable LEDs are ignored.
+pub fn show(disabled: bool) {
+    platform::show(disabled)
+}
+
+/// Puts the LED back the way it was before `show` first changed it, e.g.
+/// when `led_indicator` is switched off or the app exits.
+pub fn restore() {
+    platform::restore()
+}
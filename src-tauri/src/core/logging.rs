@@ -0,0 +1,159 @@
+//! Process-wide logger writing plain text or, with `log_format: "json"`,
+//! one JSON object per line for log aggregators.
+//!
+//! Key-value pairs attached to a record (`info!(device = name; "...")`) become
+//! fields of the JSON object, or trailing `key=value` pairs in text mode.
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::{Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crossbeam::channel::{bounded, Receiver, Sender, TrySendError};
+use log::kv::{Error as KvError, Key, Value as KvValue, VisitSource};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use serde_json::{Map, Value};
+use crate::core::settings::LogFormat;
+
+static JSON: AtomicBool = AtomicBool::new(false);
+static LOGGER: Logger = Logger;
+
+/// How many of the latest lines are kept in memory for diagnostics.
+const RECENT_LINES: usize = 2000;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Receives each new line while a live view is subscribed.
+static STREAM: Mutex<Option<Sender<String>>> = Mutex::new(None);
+
+/// Installs the logger; text until `set_log_format` says otherwise, since
+/// the settings aren't loaded yet at startup.
+pub fn init(level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+pub fn set_log_format(format: LogFormat) {
+    JSON.store(format == LogFormat::Json, Ordering::SeqCst);
+}
+
+/// The most recent log lines, oldest first.
+pub fn recent_lines() -> Vec<String> {
+    recent().iter().cloned().collect()
+}
+
+/// Starts streaming new lines, replacing any previous subscriber. Lines are
+/// dropped rather than buffered without bound if the receiver falls behind.
+pub fn subscribe() -> Receiver<String> {
+    let (sender, receiver) = bounded(RECENT_LINES);
+    *stream() = Some(sender);
+    receiver
+}
+
+pub fn unsubscribe() {
+    *stream() = None;
+}
+
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut fields = Fields(Vec::new());
+        // Only fails if a visitor does, ours never does
+        let _ = record.key_values().visit(&mut fields);
+
+        let line = if JSON.load(Ordering::SeqCst) {
+            let mut object = Map::new();
+            object.insert("timestamp".to_string(), Value::String(timestamp()));
+            object.insert("level".to_string(), Value::String(record.level().to_string()));
+            object.insert("target".to_string(), Value::String(record.target().to_string()));
+            object.insert("message".to_string(), Value::String(record.args().to_string()));
+            for (key, value) in fields.0 {
+                object.entry(key).or_insert(Value::String(value));
+            }
+            Value::Object(object).to_string()
+        } else {
+            let mut line = format!("{} {:<5} [{}] {}", timestamp(), record.level(), record.target(), record.args());
+            for (key, value) in fields.0 {
+                line.push_str(&format!(" {}={}", key, value));
+            }
+            line
+        };
+        let _ = writeln!(std::io::stdout().lock(), "{}", line);
+        let mut recent = recent();
+        if recent.len() == RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line.clone());
+        drop(recent);
+
+        let mut stream = stream();
+        if let Some(sender) = stream.as_ref() {
+            if let Err(TrySendError::Disconnected(_)) = sender.try_send(line) {
+                *stream = None;
+            }
+        }
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stdout().flush();
+    }
+}
+
+struct Fields(Vec<(String, String)>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: KvValue<'kvs>) -> Result<(), KvError> {
+        self.0.push((key.to_string(), value.to_string()));
+        Ok(())
+    }
+}
+
+// Not `lock_recovering`: its poison warning would log, and so re-enter here
+fn recent() -> MutexGuard<'static, VecDeque<String>> {
+    RECENT.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn stream() -> MutexGuard<'static, Option<Sender<String>>> {
+    STREAM.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Current UTC time as RFC 3339 with milliseconds, e.g. "2024-05-01T09:30:00.125Z".
+fn timestamp() -> String {
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let seconds_of_day = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian date (Howard Hinnant's algorithm)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
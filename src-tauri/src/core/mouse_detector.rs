@@ -0,0 +1,413 @@
+//! Detects whether an external mouse is connected.
+//!
+//! Enumeration can be slow (it shells out on Linux and macOS), so results are
+//! cached for a short time.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+use log::warn;
+use crate::core::device_registry::Device;
+#[cfg(any(not(target_os = "linux"), test))]
+use crate::core::device_registry::DeviceKind;
+use crate::core::state::lock_recovering;
+
+const CACHE_TTL: Duration = Duration::from_secs(2);
+
+static CACHE: Lazy<Mutex<Option<(Instant, bool)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Mirrors the `ignored_mouse_devices` setting.
+static IGNORED: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Set while `mouse_watcher::simulate` pretends a mouse is connected.
+static SIMULATED: AtomicBool = AtomicBool::new(false);
+
+/// Devices (by name or id) that never count as a connected mouse.
+pub fn set_ignored_devices(ignored: Vec<String>) {
+    *lock_recovering(&IGNORED) = ignored;
+    // Presence may change with the new list
+    *lock_recovering(&CACHE) = None;
+}
+
+/// Whether `device` matches an ignore entry, by id or name (both
+/// case-insensitive). On Windows, where names are derived from the hardware
+/// id, an entry also matches every collection of the hardware id it
+/// prefixes, e.g. `HID\VID_046D&PID_C52B` matches
+/// `HID\VID_046D&PID_C52B&MI_01&COL01`.
+pub fn is_ignored(device: &Device, ignored: &[String]) -> bool {
+    ignored.iter().map(|entry| entry.trim()).any(|entry| {
+        entry.eq_ignore_ascii_case(&device.id)
+            || entry.eq_ignore_ascii_case(&device.name)
+            || (cfg!(target_os = "windows") && is_hardware_id_prefix(entry, &device.id))
+    })
+}
+
+/// Whether `entry` is `id` cut at a `&` separator.
+fn is_hardware_id_prefix(entry: &str, id: &str) -> bool {
+    let entry = entry.to_ascii_uppercase();
+    id.to_ascii_uppercase()
+        .strip_prefix(&entry)
+        .is_some_and(|rest| rest.starts_with('&'))
+}
+
+/// Names of the `devices` that count for auto-toggle.
+fn present_mice(devices: Vec<Device>, ignored: &[String]) -> Vec<String> {
+    devices
+        .into_iter()
+        .filter(|device| !is_ignored(device, ignored))
+        .map(|device| device.name)
+        .collect()
+}
+
+/// Names of external pointing devices that count for auto-toggle: the
+/// built-in touchpad and ignored devices are excluded.
+pub fn enumerate_mice() -> Option<Vec<String>> {
+    let ignored = lock_recovering(&IGNORED).clone();
+    Some(present_mice(detect_mice()?, &ignored))
+}
+
+/// Counts a simulated mouse as connected until it is simulated away, so the
+/// auto-toggle rules see it like a real one.
+pub fn set_simulated(connected: bool) {
+    SIMULATED.store(connected, Ordering::SeqCst);
+}
+
+pub fn is_mouse_connected() -> bool {
+    if SIMULATED.load(Ordering::SeqCst) {
+        return true;
+    }
+    if let Ok(cache) = CACHE.lock() {
+        if let Some((checked_at, connected)) = *cache {
+            if checked_at.elapsed() < CACHE_TTL {
+                return connected;
+            }
+        }
+    }
+
+    let connected = match enumerate_mice() {
+        Some(mice) => !mice.is_empty(),
+        None => {
+            warn!("Failed to enumerate pointing devices");
+            false
+        }
+    };
+
+    match CACHE.lock() {
+        Ok(mut cache) => *cache = Some((Instant::now(), connected)),
+        Err(e) => warn!("Failed to acquire mouse cache lock: {}", e),
+    }
+    connected
+}
+
+/// External pointing devices (the built-in touchpad is excluded), including
+/// ignored ones.
+#[cfg(target_os = "linux")]
+pub fn detect_mice() -> Option<Vec<Device>> {
+    use std::process::Command;
+    use crate::core::input_controller::{command_timeout, run_checked};
+
+    let output = run_checked(Command::new("xinput").arg("--list"), command_timeout()).ok()?;
+    Some(parse_xinput_mice(&output))
+}
+
+#[cfg(target_os = "linux")]
+pub fn parse_xinput_mice(output: &str) -> Vec<Device> {
+    use crate::core::device_registry::{parse_xinput_list, DeviceKind};
+
+    parse_xinput_list(output)
+        .into_iter()
+        .filter(|device| device.kind == DeviceKind::Mouse)
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+pub fn detect_mice() -> Option<Vec<Device>> {
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::UI::Input::{
+        GetRawInputDeviceInfoW, GetRawInputDeviceList, RAWINPUTDEVICELIST, RIDI_DEVICEINFO,
+        RIDI_DEVICENAME, RID_DEVICE_INFO, RIM_TYPEHID, RIM_TYPEMOUSE,
+    };
+
+    unsafe fn device_path(handle: HANDLE) -> Option<String> {
+        let mut len = 0u32;
+        GetRawInputDeviceInfoW(handle, RIDI_DEVICENAME, None, &mut len);
+        let mut name = vec![0u16; len as usize];
+        let written = GetRawInputDeviceInfoW(
+            handle,
+            RIDI_DEVICENAME,
+            Some(name.as_mut_ptr().cast()),
+            &mut len,
+        );
+        if written == u32::MAX {
+            return None;
+        }
+        let end = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+        Some(String::from_utf16_lossy(&name[..end]))
+    }
+
+    unsafe fn hid_usage(handle: HANDLE) -> Option<(u16, u16)> {
+        let mut info = RID_DEVICE_INFO {
+            cbSize: std::mem::size_of::<RID_DEVICE_INFO>() as u32,
+            ..Default::default()
+        };
+        let mut len = info.cbSize;
+        let written = GetRawInputDeviceInfoW(
+            handle,
+            RIDI_DEVICEINFO,
+            Some((&mut info as *mut RID_DEVICE_INFO).cast()),
+            &mut len,
+        );
+        if written == u32::MAX {
+            return None;
+        }
+        Some((info.Anonymous.hid.usUsagePage, info.Anonymous.hid.usUsage))
+    }
+
+    let entry_size = std::mem::size_of::<RAWINPUTDEVICELIST>() as u32;
+    let mut count = 0u32;
+    unsafe {
+        if GetRawInputDeviceList(None, &mut count, entry_size) == u32::MAX {
+            return None;
+        }
+        let mut devices = vec![RAWINPUTDEVICELIST::default(); count as usize];
+        if GetRawInputDeviceList(Some(devices.as_mut_ptr()), &mut count, entry_size) == u32::MAX {
+            return None;
+        }
+        let raw: Vec<RawInputDevice> = devices
+            .iter()
+            .take(count as usize)
+            .filter(|d| d.dwType == RIM_TYPEMOUSE || d.dwType == RIM_TYPEHID)
+            .filter_map(|d| {
+                Some(RawInputDevice {
+                    path: device_path(d.hDevice)?,
+                    mouse: d.dwType == RIM_TYPEMOUSE,
+                    usage: if d.dwType == RIM_TYPEHID { hid_usage(d.hDevice) } else { None },
+                })
+            })
+            .collect();
+        Some(external_mice(&raw))
+    }
+}
+
+/// A raw input device as Windows reports it: its device interface path and,
+/// for generic HID collections, their usage page and usage.
+#[cfg(any(target_os = "windows", test))]
+pub struct RawInputDevice {
+    pub path: String,
+    pub mouse: bool,
+    pub usage: Option<(u16, u16)>,
+}
+
+/// Digitizer page, touch pad usage: the collection a precision touchpad exposes
+/// next to its mouse collection.
+#[cfg(any(target_os = "windows", test))]
+const PRECISION_TOUCHPAD_USAGE: (u16, u16) = (0x0D, 0x05);
+
+/// Hardware id prefixes of built-in PS/2 touchpads and pointing sticks.
+#[cfg(any(target_os = "windows", test))]
+const BUILT_IN_POINTERS: [&str; 6] = [
+    "ACPI\\SYN",
+    "ACPI\\ELAN",
+    "ACPI\\ETD",
+    "ACPI\\ALP",
+    "ACPI\\DLL",
+    "ACPI\\PNP0F",
+];
+
+/// Hardware id of a device interface path, e.g. `HID\VID_046D&PID_C52B&MI_01&COL01`
+/// for `\\?\HID#VID_046D&PID_C52B&MI_01&Col01#8&2b0c3e1f&0&0000#{378de44c-...}`.
+/// Unlike the instance part it stays the same across ports and reboots.
+#[cfg(any(target_os = "windows", test))]
+pub fn hardware_id(path: &str) -> String {
+    path.trim_start_matches("\\\\?\\")
+        .trim_start_matches("\\??\\")
+        .split('#')
+        .take(2)
+        .collect::<Vec<_>>()
+        .join("\\")
+        .to_uppercase()
+}
+
+// The physical device a top-level collection belongs to
+#[cfg(any(target_os = "windows", test))]
+fn physical_id(hardware_id: &str) -> &str {
+    hardware_id.split("&COL").next().unwrap_or(hardware_id)
+}
+
+/// External mice among the raw input devices. Mouse collections of a
+/// precision touchpad, built-in PS/2 pointers and virtual devices (remote
+/// desktop, `ROOT\...`) are excluded; every device is identified by its
+/// hardware id.
+#[cfg(any(target_os = "windows", test))]
+pub fn external_mice(devices: &[RawInputDevice]) -> Vec<Device> {
+    let touchpads: Vec<String> = devices
+        .iter()
+        .filter(|d| d.usage == Some(PRECISION_TOUCHPAD_USAGE))
+        .map(|d| physical_id(&hardware_id(&d.path)).to_string())
+        .collect();
+    devices
+        .iter()
+        .filter(|d| d.mouse)
+        .map(|d| hardware_id(&d.path))
+        .filter(|id| {
+            !touchpads.iter().any(|t| t == physical_id(id))
+                && !BUILT_IN_POINTERS.iter().any(|prefix| id.starts_with(prefix))
+                && !id.starts_with("ROOT\\")
+        })
+        .map(|id| {
+            let (bus, rest) = id.split_once('\\').unwrap_or(("", &id));
+            let model: Vec<&str> = rest.split('&').take(2).collect();
+            Device {
+                name: format!("{} mouse {}", bus, model.join("&")).trim().to_string(),
+                id,
+                kind: DeviceKind::Mouse,
+            }
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+pub fn detect_mice() -> Option<Vec<Device>> {
+    use std::process::Command;
+    use crate::core::input_controller::{command_timeout, run_checked};
+
+    let output = run_checked(
+        Command::new("ioreg").args(["-r", "-c", "IOHIDDevice", "-l"]),
+        command_timeout(),
+    )
+    .ok()?;
+    Some(parse_ioreg_mice(&output).into_iter().map(named_mouse).collect())
+}
+
+#[cfg(any(target_os = "macos", test))]
+pub fn parse_ioreg_mice(output: &str) -> Vec<String> {
+    output
+        .split("+-o ")
+        .filter(|block| {
+            block.contains("\"PrimaryUsagePage\" = 1")
+                && block.contains("\"PrimaryUsage\" = 2")
+                && !block.contains("\"Built-In\" = Yes")
+        })
+        .filter_map(|block| {
+            block.lines().find_map(|line| {
+                let value = line.trim().strip_prefix("\"Product\" = ")?;
+                Some(value.trim_matches('"').to_string())
+            })
+        })
+        .collect()
+}
+
+// Without stable ids on macOS the name doubles as the id
+#[cfg(target_os = "macos")]
+fn named_mouse(name: String) -> Device {
+    Device {
+        id: name.clone(),
+        name,
+        kind: DeviceKind::Mouse,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulated_mouse_counts_as_connected() {
+        set_simulated(true);
+        assert!(is_mouse_connected());
+        set_simulated(false);
+        assert!(!SIMULATED.load(Ordering::SeqCst));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn xinput_listing_yields_only_external_mice() {
+        let output = "\
+⎡ Virtual core pointer                    \tid=2\t[master pointer  (3)]
+⎜   ↳ Virtual core XTEST pointer              \tid=4\t[slave  pointer  (2)]
+⎜   ↳ SynPS/2 Synaptics TouchPad              \tid=12\t[slave  pointer  (2)]
+⎜   ↳ TPPS/2 IBM TrackPoint                   \tid=13\t[slave  pointer  (2)]
+⎜   ↳ Logitech USB Receiver                   \tid=9\t[slave  pointer  (2)]
+⎣ Virtual core keyboard                   \tid=3\t[master keyboard (2)]
+    ↳ AT Translated Set 2 keyboard            \tid=11\t[slave  keyboard (3)]";
+        let mice = parse_xinput_mice(output);
+        assert_eq!(mice.len(), 1);
+        assert_eq!(mice[0].id, "9");
+        assert_eq!(mice[0].name, "Logitech USB Receiver");
+    }
+
+    fn raw(path: &str, mouse: bool, usage: Option<(u16, u16)>) -> RawInputDevice {
+        RawInputDevice { path: path.to_string(), mouse, usage }
+    }
+
+    #[test]
+    fn raw_input_excludes_touchpads_and_virtual_devices() {
+        let devices = [
+            // Precision touchpad: a mouse collection next to the touch pad collection
+            raw(r"\\?\HID#VID_06CB&PID_CE26&Col01#5&1a2b3c4d&0&0000#{378de44c-56ef-11d1-bc8c-00a0c91405dd}", true, None),
+            raw(r"\\?\HID#VID_06CB&PID_CE26&Col02#5&1a2b3c4d&0&0001#{4d1e55b2-f16f-11cf-88cb-001111000030}", false, Some((0x0D, 0x05))),
+            raw(r"\\?\ACPI#SYN1B7F#4&2f94427b&0#{378de44c-56ef-11d1-bc8c-00a0c91405dd}", true, None),
+            raw(r"\\?\Root#RDP_MOU#0000#{378de44c-56ef-11d1-bc8c-00a0c91405dd}", true, None),
+            raw(r"\\?\HID#VID_046D&PID_C52B&MI_01&Col01#8&2b0c3e1f&0&0000#{378de44c-56ef-11d1-bc8c-00a0c91405dd}", true, None),
+        ];
+        let mice = external_mice(&devices);
+        assert_eq!(mice.len(), 1);
+        assert_eq!(mice[0].id, "HID\\VID_046D&PID_C52B&MI_01&COL01");
+        assert_eq!(mice[0].name, "HID mouse VID_046D&PID_C52B");
+    }
+
+    #[test]
+    fn ignored_devices_are_not_present() {
+        let tablet = Device {
+            id: "HID\\VID_056A&PID_0357&COL01".to_string(),
+            name: "HID mouse VID_056A&PID_0357".to_string(),
+            kind: DeviceKind::Mouse,
+        };
+        let mouse = Device {
+            id: "HID\\VID_046D&PID_C52B&MI_01&COL01".to_string(),
+            name: "HID mouse VID_046D&PID_C52B".to_string(),
+            kind: DeviceKind::Mouse,
+        };
+        let devices = || vec![tablet.clone(), mouse.clone()];
+        let present = |ignored: &[&str]| {
+            let ignored: Vec<String> = ignored.iter().map(|e| e.to_string()).collect();
+            present_mice(devices(), &ignored)
+        };
+
+        assert_eq!(present(&[]).len(), 2);
+        assert_eq!(present(&["hid\\vid_056a&pid_0357&col01"]), vec![mouse.name.clone()]);
+        assert_eq!(present(&["HID mouse VID_056A&PID_0357"]), vec![mouse.name.clone()]);
+        // Ignoring the only mouse leaves nothing to disable the touchpad for
+        assert!(present(&["HID\\VID_056A&PID_0357&COL01", &mouse.id]).is_empty());
+        assert!(is_hardware_id_prefix("HID\\VID_046D&PID_C52B", &mouse.id));
+        assert!(!is_hardware_id_prefix("HID\\VID_046D&PID_C5", &mouse.id));
+    }
+
+    #[test]
+    fn ioreg_listing_skips_built_in_devices() {
+        let output = r#"
++-o AppleMultitouchTrackpadHIDEventDriver  <class AppleMultitouchTrackpadHIDEventDriver>
+    {
+      "PrimaryUsagePage" = 1
+      "PrimaryUsage" = 2
+      "Built-In" = Yes
+      "Product" = "Apple Internal Keyboard / Trackpad"
+    }
++-o IOHIDInterface  <class IOHIDInterface>
+    {
+      "PrimaryUsagePage" = 1
+      "PrimaryUsage" = 2
+      "Built-In" = No
+      "Product" = "MX Master 3"
+    }
++-o IOHIDInterface  <class IOHIDInterface>
+    {
+      "PrimaryUsagePage" = 1
+      "PrimaryUsage" = 6
+      "Product" = "Magic Keyboard"
+    }
+"#;
+        assert_eq!(parse_ioreg_mice(output), vec!["MX Master 3".to_string()]);
+    }
+}
@@ -1,4 +1,6 @@
 use std::sync::Arc;
+use enigo::{Coordinate, Enigo, Mouse, Settings as EnigoSettings};
+use log::warn;
 
 #[allow(dead_code)]
 pub struct MouseEmulator;
@@ -7,17 +9,51 @@ impl MouseEmulator {
     pub fn new() -> Arc<Self> {
         Arc::new(Self)
     }
-    
+
+    // Enigo handles are not Send on every platform, so open one per call
+    fn enigo(&self) -> Option<Enigo> {
+        match Enigo::new(&EnigoSettings::default()) {
+            Ok(enigo) => Some(enigo),
+            Err(e) => {
+                warn!("Failed to initialize input emulation: {}", e);
+                None
+            }
+        }
+    }
+
     // Placeholder methods for mouse emulation functionality
     pub fn simulate_mouse_click(&self) {
         // Implementation would go here
     }
-    
-    pub fn simulate_mouse_move(&self, _x: i32, _y: i32) {
-        // Implementation would go here
+
+    pub fn simulate_mouse_move(&self, x: i32, y: i32) {
+        if let Some(mut enigo) = self.enigo() {
+            if let Err(e) = enigo.move_mouse(x, y, Coordinate::Abs) {
+                warn!("Failed to move cursor: {}", e);
+            }
+        }
     }
-    
+
+    pub fn simulate_mouse_move_relative(&self, dx: i32, dy: i32) {
+        if let Some(mut enigo) = self.enigo() {
+            if let Err(e) = enigo.move_mouse(dx, dy, Coordinate::Rel) {
+                warn!("Failed to move cursor: {}", e);
+            }
+        }
+    }
+
     pub fn simulate_mouse_scroll(&self, _delta: i32) {
         // Implementation would go here
     }
-}
\ No newline at end of file
+
+    pub fn cursor_position(&self) -> Option<(i32, i32)> {
+        let enigo = self.enigo()?;
+        match enigo.location() {
+            Ok(position) => Some(position),
+            Err(e) => {
+                warn!("Failed to read cursor position: {}", e);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Mouse keys: moves the cursor with the numeric keypad while the touchpad
+//! is disabled.
+//!
+//! OS key repeat fires at the system repeat rate and looks jerky, so repeats
+//! are ignored; keydown/keyup track which keys are physically held and a
+//! fixed-tick loop moves the cursor while they are.
+//!
+//! While active the direction keys are grabbed, so they don't also reach the
+//! focused application.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use rdev::{EventType, Key};
+use crate::core::key_listener;
+use crate::core::keypad_grab;
+use crate::core::mouse_emulator::MouseEmulator;
+use crate::core::state::{lock_recovering, SharedState, TouchpadState};
+use crate::core::supervisor;
+
+// How often to re-check whether mouse keys should be active
+const IDLE_CHECK: Duration = Duration::from_millis(250);
+
+fn direction(key: &Key) -> Option<(i32, i32)> {
+    match key {
+        Key::Kp8 => Some((0, -1)),
+        Key::Kp2 => Some((0, 1)),
+        Key::Kp4 => Some((-1, 0)),
+        Key::Kp6 => Some((1, 0)),
+        Key::Kp7 => Some((-1, -1)),
+        Key::Kp9 => Some((1, -1)),
+        Key::Kp1 => Some((-1, 1)),
+        Key::Kp3 => Some((1, 1)),
+        _ => None,
+    }
+}
+
+/// Direction keys currently held down, with the time each was first pressed.
+#[derive(Default)]
+pub struct HeldKeys(Vec<(Key, Instant)>);
+
+impl HeldKeys {
+    pub fn feed(&mut self, event: &EventType, now: Instant) {
+        match event {
+            // Repeats arrive as further presses; keep the original press time
+            EventType::KeyPress(key) if direction(key).is_some() && !self.0.iter().any(|(held, _)| held == key) => {
+                self.0.push((*key, now));
+            }
+            EventType::KeyRelease(key) => {
+                self.0.retain(|(held, _)| held != key);
+            }
+            _ => {}
+        }
+    }
+
+    /// Combined direction of keys held for at least `dead_time`, so a quick
+    /// tap doesn't move the cursor.
+    pub fn direction(&self, now: Instant, dead_time: Duration) -> (i32, i32) {
+        self.0
+            .iter()
+            .filter(|(_, pressed)| now.duration_since(*pressed) >= dead_time)
+            .filter_map(|(key, _)| direction(key))
+            .fold((0, 0), |(x, y), (dx, dy)| (x + dx, y + dy))
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Runs mouse keys on a supervised thread. The key listener is only
+/// subscribed while `mouse_keys` is on and the touchpad is disabled.
+pub fn start_mouse_keys(state: SharedState, emulator: Arc<MouseEmulator>) {
+    supervisor::spawn("mouse-keys", move |token| {
+        let held = Arc::new(Mutex::new(HeldKeys::default()));
+        let mut subscription = None;
+        loop {
+            let settings = state.get_settings();
+            let active = settings.mouse_keys && state.get_touchpad_state() == TouchpadState::Disabled;
+
+            if !active {
+                if let Some(id) = subscription.take() {
+                    key_listener::unsubscribe(id);
+                    keypad_grab::release();
+                    lock_recovering(&held).clear();
+                }
+                if !token.sleep(IDLE_CHECK) {
+                    break;
+                }
+                continue;
+            }
+
+            if subscription.is_none() {
+                // Either may see a key first; feeding a key twice is harmless
+                let listener_held = held.clone();
+                subscription = Some(key_listener::subscribe(move |event| {
+                    lock_recovering(&listener_held).feed(event, Instant::now());
+                }));
+                let grab_held = held.clone();
+                keypad_grab::grab(move |event| lock_recovering(&grab_held).feed(event, Instant::now()));
+            }
+
+            let dead_time = Duration::from_millis(settings.mouse_keys_dead_time_ms);
+            let (dx, dy) = lock_recovering(&held).direction(Instant::now(), dead_time);
+            if dx != 0 || dy != 0 {
+                let step = settings.mouse_keys_step_px as i32;
+                emulator.simulate_mouse_move_relative(dx.signum() * step, dy.signum() * step);
+            }
+            if !token.sleep(Duration::from_millis(settings.mouse_keys_tick_ms.max(1))) {
+                break;
+            }
+        }
+        if let Some(id) = subscription {
+            key_listener::unsubscribe(id);
+            keypad_grab::release();
+        }
+    });
+}
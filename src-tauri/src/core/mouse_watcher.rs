@@ -0,0 +1,204 @@
+//! Watches for external mice being connected or removed and drives the
+//! auto-toggle rules (`disable_on_mouse_connect` / `enable_on_mouse_disconnect`).
+
+use std::sync::Arc;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use log::{info, warn};
+use crate::core::command_queue::{ActionSource, TouchpadAction};
+use crate::core::cursor_confine;
+use crate::core::hotkey_manager::HotkeyManager;
+use crate::core::mouse_detector;
+use crate::core::settings::Settings;
+use crate::core::state::safe_mode;
+use crate::core::supervisor;
+
+const POLLING_WORKER: &str = "mouse-watcher";
+const SIMULATED_DEVICE: &str = "Simulated mouse";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MouseEvent {
+    Connected(String),
+    Disconnected(String),
+}
+
+/// How to resolve connect and disconnect events arriving together, e.g. from
+/// a dock exposing a composite device.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[allow(dead_code)]
+pub enum ConflictPolicy {
+    /// Any disconnect wins, so the touchpad is re-enabled.
+    DisconnectWins,
+    /// Any connect wins, so the touchpad is disabled.
+    ConnectWins,
+    /// The last event in the batch decides.
+    MostRecent,
+}
+
+/// Decides the auto-toggle action for a batch of events observed together.
+///
+/// Events are in arrival order. Returns `None` when no enabled rule applies.
+pub fn resolve_events(events: &[MouseEvent], settings: &Settings) -> Option<TouchpadAction> {
+    let connected = events.iter().any(|e| matches!(e, MouseEvent::Connected(_)));
+    let disconnected = events.iter().any(|e| matches!(e, MouseEvent::Disconnected(_)));
+
+    let connect_wins = match (connected, disconnected) {
+        (false, false) => return None,
+        (true, false) => true,
+        (false, true) => false,
+        (true, true) => match settings.auto_toggle_conflict_policy {
+            ConflictPolicy::DisconnectWins => false,
+            ConflictPolicy::ConnectWins => true,
+            ConflictPolicy::MostRecent => matches!(events.last(), Some(MouseEvent::Connected(_))),
+        },
+    };
+
+    if connect_wins {
+        settings.disable_on_mouse_connect.then_some(TouchpadAction::Disable)
+    } else {
+        settings.enable_on_mouse_disconnect.then_some(TouchpadAction::Enable)
+    }
+}
+
+/// Diff between two enumerations: removals first, then additions.
+pub fn diff_devices(previous: &[String], current: &[String]) -> Vec<MouseEvent> {
+    let removed = previous
+        .iter()
+        .filter(|name| !current.contains(name))
+        .map(|name| MouseEvent::Disconnected(name.clone()));
+    let added = current
+        .iter()
+        .filter(|name| !previous.contains(name))
+        .map(|name| MouseEvent::Connected(name.clone()));
+    removed.chain(added).collect()
+}
+
+pub fn handle_events(manager: &HotkeyManager, events: &[MouseEvent]) {
+    if events.is_empty() {
+        return;
+    }
+    info!("Mouse events: {:?}", events);
+    // A real mouse is back, never leave the cursor confined
+    if events.iter().any(|e| matches!(e, MouseEvent::Connected(_))) {
+        cursor_confine::release();
+    }
+    if manager.is_paused() {
+        info!("Auto-toggle is paused, ignoring mouse events");
+        return;
+    }
+    if safe_mode() {
+        info!("Safe mode is active, ignoring mouse events");
+        return;
+    }
+    if let Some(action) = resolve_events(events, &manager.settings()) {
+        manager.request(action, ActionSource::Auto);
+    }
+}
+
+/// Feeds a synthetic connect or disconnect through `handle_events`, so pause,
+/// safe mode, the auto-toggle rules and the queue's cooldown all apply as
+/// they would for a real mouse. The detector counts the simulated mouse too,
+/// so a disable isn't refused for stranding the user.
+pub fn simulate(manager: &HotkeyManager, connected: bool) {
+    mouse_detector::set_simulated(connected);
+    let name = SIMULATED_DEVICE.to_string();
+    let event = if connected { MouseEvent::Connected(name) } else { MouseEvent::Disconnected(name) };
+    handle_events(manager, &[event]);
+}
+
+/// Watches for mouse changes: event-driven on Windows, polling elsewhere or
+/// if the Windows listener can't be set up.
+pub fn start_mouse_watcher(manager: Arc<HotkeyManager>) {
+    #[cfg(target_os = "windows")]
+    {
+        let mut previous = mouse_detector::enumerate_mice().unwrap_or_default();
+        let listener_manager = manager.clone();
+        let started = crate::core::device_notify::start(move || {
+            let Some(current) = mouse_detector::enumerate_mice() else {
+                warn!("Failed to enumerate pointing devices");
+                return;
+            };
+            handle_events(&listener_manager, &diff_devices(&previous, &current));
+            previous = current;
+        });
+        if started {
+            return;
+        }
+        warn!("Device change listener unavailable, falling back to polling");
+    }
+
+    start_polling(manager);
+}
+
+/// Stops the watcher, whether event-driven or polling.
+pub fn stop_mouse_watcher() {
+    #[cfg(target_os = "windows")]
+    crate::core::device_notify::stop();
+    supervisor::stop(POLLING_WORKER);
+}
+
+/// Polls device enumeration on a supervised background thread.
+fn start_polling(manager: Arc<HotkeyManager>) {
+    supervisor::spawn(POLLING_WORKER, move |token| {
+        let mut previous = mouse_detector::enumerate_mice().unwrap_or_default();
+        loop {
+            let interval = manager.settings().mouse_poll_interval_ms.max(250);
+            if !token.sleep(Duration::from_millis(interval)) {
+                break;
+            }
+
+            let Some(current) = mouse_detector::enumerate_mice() else {
+                warn!("Failed to enumerate pointing devices");
+                continue;
+            };
+            handle_events(&manager, &diff_devices(&previous, &current));
+            previous = current;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(policy: ConflictPolicy) -> Settings {
+        Settings {
+            auto_toggle_conflict_policy: policy,
+            disable_on_mouse_connect: true,
+            enable_on_mouse_disconnect: true,
+            ..Settings::default()
+        }
+    }
+
+    fn dock_swap() -> Vec<MouseEvent> {
+        // A dock re-enumerating: the old composite device goes, a new one appears
+        diff_devices(&["Dock mouse".to_string()], &["Dock mouse (2)".to_string()])
+    }
+
+    #[test]
+    fn simultaneous_events_follow_the_policy() {
+        let events = dock_swap();
+        assert_eq!(
+            events,
+            vec![
+                MouseEvent::Disconnected("Dock mouse".to_string()),
+                MouseEvent::Connected("Dock mouse (2)".to_string()),
+            ]
+        );
+        assert_eq!(resolve_events(&events, &settings(ConflictPolicy::DisconnectWins)), Some(TouchpadAction::Enable));
+        assert_eq!(resolve_events(&events, &settings(ConflictPolicy::ConnectWins)), Some(TouchpadAction::Disable));
+        assert_eq!(resolve_events(&events, &settings(ConflictPolicy::MostRecent)), Some(TouchpadAction::Disable));
+
+        let reversed: Vec<_> = events.into_iter().rev().collect();
+        assert_eq!(resolve_events(&reversed, &settings(ConflictPolicy::MostRecent)), Some(TouchpadAction::Enable));
+    }
+
+    #[test]
+    fn disabled_rules_resolve_to_nothing() {
+        let mut settings = settings(ConflictPolicy::ConnectWins);
+        settings.disable_on_mouse_connect = false;
+        assert_eq!(resolve_events(&dock_swap(), &settings), None);
+        assert_eq!(resolve_events(&[], &settings), None);
+    }
+}
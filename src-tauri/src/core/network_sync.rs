@@ -0,0 +1,238 @@
+//! Mirrors the touchpad state across machines on the same LAN, e.g. laptops
+//! sharing a KVM switch.
+//!
+//! Security posture: off by default. Announcements are UDP broadcasts that
+//! anyone on the network segment can read, so they carry nothing but the
+//! state. Each one is signed with HMAC-SHA256 over a shared secret and
+//! carries a timestamp; unsigned, stale or replayed messages are dropped.
+//! A received state only goes through the action queue as
+//! `ActionSource::Network`, so safe mode, cooldowns and manual precedence
+//! all still apply, and it can't do anything besides enable or disable.
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use log::{debug, info, warn};
+use crate::core::command_queue::{ActionSource, TouchpadAction};
+use crate::core::hotkey_manager::HotkeyManager;
+use crate::core::settings::Settings;
+use crate::core::state::{lock_recovering, TouchpadState};
+use crate::core::supervisor;
+
+const WORKER_NAME: &str = "network-sync";
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+/// Announcements older than this, or this far in the future, are rejected.
+const MAX_CLOCK_SKEW_MS: u64 = 30_000;
+const MIN_SECRET_LEN: usize = 12;
+
+/// Tells our own broadcasts apart from other machines'.
+static INSTANCE: Lazy<String> = Lazy::new(|| format!("{:x}-{:x}", std::process::id(), now_ms()));
+
+/// Port and secret the listener is running with, if it is.
+static RUNNING: Mutex<Option<(u16, String)>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Announcement {
+    instance: String,
+    state: TouchpadState,
+    sent_at: u64,
+    /// Hex HMAC-SHA256 of the other fields, see `signed_payload`.
+    mac: String,
+}
+
+fn signed_payload(instance: &str, state: TouchpadState, sent_at: u64) -> String {
+    format!("{}|{:?}|{}", instance, state, sent_at)
+}
+
+fn signer(secret: &str) -> Hmac<Sha256> {
+    // HMAC accepts keys of any length
+    Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC key of any length")
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// Rejects enabling sync without a reasonably long secret.
+pub fn validate(settings: &Settings) -> Result<(), String> {
+    if !settings.network_sync_enabled {
+        return Ok(());
+    }
+    match &settings.network_sync_secret {
+        Some(secret) if secret.chars().count() >= MIN_SECRET_LEN => Ok(()),
+        _ => Err(format!("network_sync_secret must be at least {} characters", MIN_SECRET_LEN)),
+    }
+}
+
+/// Broadcasts `state` if sync is on. Failures are only logged.
+pub fn announce(settings: &Settings, state: TouchpadState) {
+    let (true, Some(secret)) = (settings.network_sync_enabled, settings.network_sync_secret.as_ref()) else {
+        return;
+    };
+    let sent_at = now_ms();
+    let mut mac = signer(secret);
+    mac.update(signed_payload(&INSTANCE, state, sent_at).as_bytes());
+    let announcement = Announcement {
+        instance: INSTANCE.clone(),
+        state,
+        sent_at,
+        mac: to_hex(&mac.finalize().into_bytes()),
+    };
+    let result = serde_json::to_vec(&announcement).map_err(|e| e.to_string()).and_then(|payload| {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|e| e.to_string())?;
+        socket.set_broadcast(true).map_err(|e| e.to_string())?;
+        socket
+            .send_to(&payload, (Ipv4Addr::BROADCAST, settings.network_sync_port))
+            .map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        warn!("Failed to announce touchpad state: {}", e);
+    }
+}
+
+/// Checks signature, age and ordering; returns the state to apply.
+fn verify(
+    announcement: &Announcement,
+    secret: &str,
+    last_seen: &mut HashMap<String, u64>,
+) -> Result<TouchpadState, &'static str> {
+    if announcement.instance == *INSTANCE {
+        return Err("own announcement");
+    }
+    let mut mac = signer(secret);
+    mac.update(signed_payload(&announcement.instance, announcement.state, announcement.sent_at).as_bytes());
+    let expected = from_hex(&announcement.mac).ok_or("malformed signature")?;
+    mac.verify_slice(&expected).map_err(|_| "bad signature")?;
+    if now_ms().abs_diff(announcement.sent_at) > MAX_CLOCK_SKEW_MS {
+        return Err("stale");
+    }
+    let last = last_seen.entry(announcement.instance.clone()).or_insert(0);
+    if announcement.sent_at <= *last {
+        return Err("replayed");
+    }
+    *last = announcement.sent_at;
+    Ok(announcement.state)
+}
+
+/// Starts, restarts or stops the listener to match `settings`.
+pub fn configure(manager: &Arc<HotkeyManager>, settings: &Settings) {
+    let wanted = match (settings.network_sync_enabled, &settings.network_sync_secret) {
+        (true, Some(secret)) => Some((settings.network_sync_port, secret.clone())),
+        _ => None,
+    };
+    let mut running = lock_recovering(&RUNNING);
+    if *running == wanted {
+        return;
+    }
+    supervisor::stop(WORKER_NAME);
+    *running = None;
+    let Some((port, secret)) = wanted else {
+        return;
+    };
+
+    let socket = match UdpSocket::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port))) {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!("Network sync unavailable, can't listen on UDP port {}: {}", port, e);
+            return;
+        }
+    };
+    if let Err(e) = socket.set_read_timeout(Some(READ_TIMEOUT)) {
+        warn!("Network sync unavailable: {}", e);
+        return;
+    }
+    info!("Network sync listening on UDP port {}", port);
+    *running = Some((port, secret.clone()));
+
+    let manager = manager.clone();
+    supervisor::spawn(WORKER_NAME, move |token| {
+        let mut last_seen = HashMap::new();
+        let mut buffer = [0u8; 1024];
+        while !token.is_stopped() {
+            // Times out every second so a stop is noticed
+            let Ok((len, from)) = socket.recv_from(&mut buffer) else {
+                continue;
+            };
+            let Ok(announcement) = serde_json::from_slice::<Announcement>(&buffer[..len]) else {
+                debug!("Ignoring malformed sync message from {}", from);
+                continue;
+            };
+            let state = match verify(&announcement, &secret, &mut last_seen) {
+                Ok(state) => state,
+                Err(reason) => {
+                    debug!("Ignoring sync message from {}: {}", from, reason);
+                    continue;
+                }
+            };
+            // Also ends the echo when the other machine re-announces our change
+            if state == manager.tracked_state() {
+                continue;
+            }
+            info!("Applying {:?} announced by {}", state, from);
+            let action = if state == TouchpadState::Enabled { TouchpadAction::Enable } else { TouchpadAction::Disable };
+            manager.request(action, ActionSource::Network);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "correct horse battery";
+
+    fn signed(instance: &str, state: TouchpadState, sent_at: u64, secret: &str) -> Announcement {
+        let mut mac = signer(secret);
+        mac.update(signed_payload(instance, state, sent_at).as_bytes());
+        Announcement {
+            instance: instance.to_string(),
+            state,
+            sent_at,
+            mac: to_hex(&mac.finalize().into_bytes()),
+        }
+    }
+
+    #[test]
+    fn verify_accepts_only_fresh_signed_announcements_from_others() {
+        let mut last_seen = HashMap::new();
+        let now = now_ms();
+
+        let fresh = signed("peer", TouchpadState::Disabled, now, SECRET);
+        assert_eq!(verify(&fresh, SECRET, &mut last_seen), Ok(TouchpadState::Disabled));
+        assert_eq!(verify(&fresh, SECRET, &mut last_seen), Err("replayed"));
+        let older = signed("peer", TouchpadState::Enabled, now - 1, SECRET);
+        assert_eq!(verify(&older, SECRET, &mut last_seen), Err("replayed"));
+        let newer = signed("peer", TouchpadState::Enabled, now + 1, SECRET);
+        assert_eq!(verify(&newer, SECRET, &mut last_seen), Ok(TouchpadState::Enabled));
+
+        let wrong_secret = signed("other", TouchpadState::Enabled, now, "another secret!");
+        assert_eq!(verify(&wrong_secret, SECRET, &mut last_seen), Err("bad signature"));
+        let mut tampered = signed("other", TouchpadState::Enabled, now, SECRET);
+        tampered.state = TouchpadState::Disabled;
+        assert_eq!(verify(&tampered, SECRET, &mut last_seen), Err("bad signature"));
+        let mut malformed = signed("other", TouchpadState::Enabled, now, SECRET);
+        malformed.mac = "xyz".to_string();
+        assert_eq!(verify(&malformed, SECRET, &mut last_seen), Err("malformed signature"));
+
+        let stale = signed("other", TouchpadState::Enabled, now - MAX_CLOCK_SKEW_MS - 1_000, SECRET);
+        assert_eq!(verify(&stale, SECRET, &mut last_seen), Err("stale"));
+        let own = signed(&INSTANCE, TouchpadState::Enabled, now, SECRET);
+        assert_eq!(verify(&own, SECRET, &mut last_seen), Err("own announcement"));
+    }
+}
@@ -0,0 +1,147 @@
+//! Best-effort detection of whether the machine runs on battery or AC, for
+//! `power_profiles`. An undeterminable source counts as AC, the state of a
+//! desktop or a docked laptop.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::core::supervisor;
+
+const WORKER_NAME: &str = "power-watch";
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+pub enum PowerSource {
+    Battery,
+    Ac,
+}
+
+impl PowerSource {
+    /// Key of this source in `power_profiles`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            PowerSource::Battery => "battery",
+            PowerSource::Ac => "ac",
+        }
+    }
+}
+
+/// Valid keys of `power_profiles`.
+pub const PROFILE_KEYS: [&str; 2] = ["battery", "ac"];
+
+/// The current power source, AC if it can't be determined.
+pub fn current() -> PowerSource {
+    detect().unwrap_or(PowerSource::Ac)
+}
+
+#[cfg(target_os = "linux")]
+fn detect() -> Option<PowerSource> {
+    let supplies = std::fs::read_dir("/sys/class/power_supply").ok()?;
+    let supplies: Vec<(String, bool)> = supplies
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let kind = std::fs::read_to_string(entry.path().join("type")).ok()?;
+            let online = std::fs::read_to_string(entry.path().join("online")).unwrap_or_default();
+            Some((kind.trim().to_string(), online.trim() == "1"))
+        })
+        .collect();
+    source_from_supplies(&supplies)
+}
+
+/// Decides from `(type, online)` pairs of `/sys/class/power_supply`: any
+/// online mains or USB supply means AC, a battery with none online means
+/// battery, and anything else is unknown.
+#[allow(dead_code)]
+fn source_from_supplies(supplies: &[(String, bool)]) -> Option<PowerSource> {
+    let external = |kind: &str| kind == "Mains" || kind.starts_with("USB");
+    if supplies.iter().any(|(kind, online)| external(kind) && *online) {
+        return Some(PowerSource::Ac);
+    }
+    if supplies.iter().any(|(kind, _)| kind == "Battery") {
+        return Some(PowerSource::Battery);
+    }
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn detect() -> Option<PowerSource> {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    unsafe { GetSystemPowerStatus(&mut status) }.ok()?;
+    match status.ACLineStatus {
+        0 => Some(PowerSource::Battery),
+        1 => Some(PowerSource::Ac),
+        // 255: unknown
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn detect() -> Option<PowerSource> {
+    use crate::core::input_controller::{command_timeout, run_checked};
+    // First line reads e.g. "Now drawing from 'Battery Power'"
+    let output = run_checked(std::process::Command::new("pmset").args(["-g", "batt"]), command_timeout()).ok()?;
+    let first = output.lines().next()?;
+    if first.contains("'Battery Power'") {
+        Some(PowerSource::Battery)
+    } else if first.contains("'AC Power'") {
+        Some(PowerSource::Ac)
+    } else {
+        None
+    }
+}
+
+/// Polls the power source and calls `on_change` with each new one.
+pub fn start_watcher(initial: PowerSource, mut on_change: impl FnMut(PowerSource) + Send + 'static) {
+    supervisor::spawn(WORKER_NAME, move |token| {
+        let mut last = initial;
+        while token.sleep(POLL_INTERVAL) {
+            let source = current();
+            if source != last {
+                on_change(source);
+                last = source;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Map, Value};
+    use crate::core::settings::Settings;
+
+    fn supplies(ac_online: bool) -> Vec<(String, bool)> {
+        vec![("Mains".to_string(), ac_online), ("Battery".to_string(), false)]
+    }
+
+    #[test]
+    fn unplugging_switches_to_the_battery_profile_and_back() {
+        let mut settings = Settings::default();
+        let Value::Object(battery) = json!({ "osd_duration_ms": 2500 }) else { unreachable!() };
+        settings.power_profiles.insert("battery".to_string(), battery);
+
+        let mut last = source_from_supplies(&supplies(true)).unwrap();
+        assert_eq!(last, PowerSource::Ac);
+        let mut effective = settings.effective(last, &Map::new()).unwrap();
+        assert_eq!(effective.osd_duration_ms, settings.osd_duration_ms);
+
+        // What the watcher does on each poll
+        for (ac_online, expected, osd_duration_ms) in
+            [(false, PowerSource::Battery, 2500), (true, PowerSource::Ac, settings.osd_duration_ms)]
+        {
+            let source = source_from_supplies(&supplies(ac_online)).unwrap();
+            assert_eq!(source, expected);
+            assert_ne!(source, last);
+            effective = settings.effective(source, &Map::new()).unwrap();
+            assert_eq!(effective.osd_duration_ms, osd_duration_ms);
+            last = source;
+        }
+
+        // USB-C charging counts as AC, a desktop without a battery is unknown
+        assert_eq!(source_from_supplies(&[("USB_PD".to_string(), true), ("Battery".to_string(), false)]), Some(PowerSource::Ac));
+        assert_eq!(source_from_supplies(&[]), None);
+    }
+}
@@ -0,0 +1,69 @@
+//! Two-press confirmation for disabling from a hotkey, so a stray press can't
+//! leave the user without a pointer.
+
+use std::time::{Duration, Instant};
+
+/// Recognizes a second press within `window` of the first.
+///
+/// The first press arms the confirmation and the second one completes it. A
+/// second press after the window has expired arms it again instead.
+#[derive(Debug, Default)]
+pub struct PressConfirmation {
+    armed_at: Option<Instant>,
+}
+
+impl PressConfirmation {
+    /// Feeds one press; returns true when it confirms the action.
+    pub fn press(&mut self, now: Instant, window: Duration) -> bool {
+        match self.armed_at.take() {
+            Some(armed_at) if now.duration_since(armed_at) <= window => true,
+            _ => {
+                self.armed_at = Some(now);
+                false
+            }
+        }
+    }
+
+    /// Forgets a pending first press.
+    pub fn reset(&mut self) {
+        self.armed_at = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_millis(500);
+
+    #[test]
+    fn second_press_at_the_end_of_the_window_confirms() {
+        let mut confirmation = PressConfirmation::default();
+        let first = Instant::now();
+        assert!(!confirmation.press(first, WINDOW));
+        assert!(confirmation.press(first + WINDOW, WINDOW));
+        // Confirming disarms: the next press starts over
+        assert!(!confirmation.press(first + WINDOW + Duration::from_millis(1), WINDOW));
+    }
+
+    #[test]
+    fn late_second_press_arms_again() {
+        let mut confirmation = PressConfirmation::default();
+        let first = Instant::now();
+        assert!(!confirmation.press(first, WINDOW));
+        let late = first + WINDOW + Duration::from_millis(1);
+        assert!(!confirmation.press(late, WINDOW));
+        // The window now runs from the late press
+        assert!(confirmation.press(late + Duration::from_millis(400), WINDOW));
+    }
+
+    #[test]
+    fn reset_drops_the_pending_press() {
+        let mut confirmation = PressConfirmation::default();
+        let first = Instant::now();
+        assert!(!confirmation.press(first, WINDOW));
+        confirmation.reset();
+        assert!(!confirmation.press(first + Duration::from_millis(100), WINDOW));
+        assert!(confirmation.press(first + Duration::from_millis(200), WINDOW));
+    }
+}
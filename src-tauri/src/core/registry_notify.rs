@@ -0,0 +1,80 @@
+//! Event-driven notification of Precision Touchpad setting changes on Windows.
+//!
+//! `RegNotifyChangeKeyValue` signals an event whenever a value under the
+//! per-user touchpad key is written, by us, the Settings app or the
+//! hardware touchpad key, so external changes are noticed without polling.
+
+use std::sync::atomic::{AtomicIsize, Ordering};
+use log::{info, warn};
+use windows::core::w;
+use windows::Win32::Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0};
+use windows::Win32::System::Registry::{
+    RegCloseKey, RegNotifyChangeKeyValue, RegOpenKeyExW, HKEY, HKEY_CURRENT_USER, KEY_NOTIFY,
+    REG_NOTIFY_CHANGE_LAST_SET,
+};
+use windows::Win32::System::Threading::{CreateEventW, SetEvent, WaitForMultipleObjects, INFINITE};
+
+// Event that ends the running listener, 0 when stopped
+static STOP_EVENT: AtomicIsize = AtomicIsize::new(0);
+
+/// Starts the listener on its own thread, calling `on_change` there after
+/// each write to the touchpad key. Returns false if it couldn't be set up.
+pub fn start(on_change: impl FnMut() + Send + 'static) -> bool {
+    let (ready_tx, ready_rx) = crossbeam::channel::bounded(1);
+    std::thread::spawn(move || {
+        let ok = unsafe { run_listener(&ready_tx, on_change) };
+        if !ok {
+            let _ = ready_tx.send(false);
+        }
+    });
+    ready_rx.recv().unwrap_or(false)
+}
+
+/// Signals the listener to stop.
+pub fn stop() {
+    let event = STOP_EVENT.swap(0, Ordering::SeqCst);
+    if event == 0 {
+        return;
+    }
+    if let Err(e) = unsafe { SetEvent(HANDLE(event)) } {
+        warn!("Failed to stop registry listener: {}", e);
+    }
+}
+
+unsafe fn run_listener(ready: &crossbeam::channel::Sender<bool>, mut on_change: impl FnMut()) -> bool {
+    let mut key = HKEY::default();
+    let subkey = w!(r"Software\Microsoft\Windows\CurrentVersion\PrecisionTouchPad");
+    if let Err(e) = RegOpenKeyExW(HKEY_CURRENT_USER, subkey, 0, KEY_NOTIFY, &mut key).ok() {
+        warn!("Failed to open the touchpad registry key: {}", e);
+        return false;
+    }
+    let (Ok(changed), Ok(stop)) = (CreateEventW(None, false, false, None), CreateEventW(None, true, false, None)) else {
+        warn!("Failed to create registry listener events");
+        let _ = RegCloseKey(key);
+        return false;
+    };
+
+    STOP_EVENT.store(stop.0, Ordering::SeqCst);
+    let _ = ready.send(true);
+    info!("Touchpad registry listener started");
+
+    loop {
+        // The registration is one-shot, so renew it before every wait
+        if let Err(e) = RegNotifyChangeKeyValue(key, true, REG_NOTIFY_CHANGE_LAST_SET, changed, true).ok() {
+            warn!("Failed to watch the touchpad registry key: {}", e);
+            break;
+        }
+        let signaled = WaitForMultipleObjects(&[changed, stop], false, INFINITE);
+        if signaled != WAIT_OBJECT_0 {
+            break;
+        }
+        on_change();
+    }
+
+    STOP_EVENT.store(0, Ordering::SeqCst);
+    let _ = CloseHandle(changed);
+    let _ = CloseHandle(stop);
+    let _ = RegCloseKey(key);
+    info!("Touchpad registry listener stopped");
+    true
+}
@@ -0,0 +1,244 @@
+//! Notices the machine waking from sleep.
+//!
+//! Each platform's own wake signal is followed where it can be: logind's
+//! `PrepareForSleep(false)` on Linux, `PBT_APMRESUMEAUTOMATIC` on Windows and
+//! `NSWorkspaceDidWakeNotification` on macOS. The fallback that works
+//! everywhere is the wall clock: threads don't run while the system is
+//! suspended, so a short periodic tick that took far longer by the wall
+//! clock means the machine was asleep. That notices a resume up to `TICK`
+//! late, and a large wall clock adjustment looks like one too, which is
+//! harmless for callers that only re-check state. Either way each wake is
+//! reported once.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use crate::core::state::lock_recovering;
+use crate::core::supervisor;
+
+const WORKER_NAME: &str = "resume-watch";
+const TICK: Duration = Duration::from_secs(2);
+/// Extra wall clock time in one tick that counts as a sleep.
+const SLEEP_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// Wall clock time of the watcher's last tick and of the last resume
+/// reported, see `resume_pending`.
+static LAST_TICK: Mutex<Option<SystemTime>> = Mutex::new(None);
+static LAST_RESUME: Mutex<Option<SystemTime>> = Mutex::new(None);
+
+/// Whether a tick expected to take `tick` but measured as `elapsed` on the
+/// wall clock spanned a sleep.
+pub fn slept_during(tick: Duration, elapsed: Duration) -> bool {
+    elapsed > tick + SLEEP_THRESHOLD
+}
+
+/// Whether the machine has just woken up at `now`: asleep since the
+/// watcher's `last_tick` but not noticed yet, or `last_resume` reported
+/// within the last tick. Other threads can run first after a wake, and
+/// anything they see changed then is for the resume handling to judge.
+pub fn resume_pending_at(now: SystemTime, last_tick: Option<SystemTime>, last_resume: Option<SystemTime>) -> bool {
+    let since = |then: Option<SystemTime>| then.and_then(|then| now.duration_since(then).ok());
+    let unnoticed = since(last_tick).is_some_and(|elapsed| slept_during(TICK, elapsed));
+    let recent = since(last_resume).is_some_and(|elapsed| elapsed <= TICK);
+    unnoticed || recent
+}
+
+/// `resume_pending_at` now, for the running watcher.
+pub fn resume_pending() -> bool {
+    resume_pending_at(SystemTime::now(), *lock_recovering(&LAST_TICK), *lock_recovering(&LAST_RESUME))
+}
+
+/// Whether a wake noticed at `now` is one not reported yet. The platform
+/// signal and the wall clock check usually both notice the same wake, in
+/// either order: one reported after the watcher's `last_tick`, or within
+/// `SLEEP_THRESHOLD`, is the same.
+pub fn is_new_resume(now: SystemTime, last_tick: Option<SystemTime>, last_resume: Option<SystemTime>) -> bool {
+    let Some(reported) = last_resume else {
+        return true;
+    };
+    let before_last_tick = last_tick.is_some_and(|tick| reported < tick);
+    let long_ago = now.duration_since(reported).is_ok_and(|elapsed| elapsed > SLEEP_THRESHOLD);
+    before_last_tick && long_ago
+}
+
+/// Whether a logind monitor line is `PrepareForSleep(false)`, sent on wake.
+pub fn is_logind_wake(line: &str) -> bool {
+    line.contains(".PrepareForSleep (false")
+}
+
+// Records a wake at `now` and calls `on_resume` with the approximate time
+// asleep, unless this wake was already reported
+fn report_resume(now: SystemTime, on_resume: &(dyn Fn(Duration) + Send + Sync)) {
+    let slept = {
+        let mut last_resume = lock_recovering(&LAST_RESUME);
+        let last_tick = *lock_recovering(&LAST_TICK);
+        if !is_new_resume(now, last_tick, *last_resume) {
+            return;
+        }
+        *last_resume = Some(now);
+        let asleep = last_tick.and_then(|tick| now.duration_since(tick).ok()).unwrap_or_default();
+        asleep.saturating_sub(TICK)
+    };
+    on_resume(slept);
+}
+
+/// Calls `on_resume` with the approximate sleep duration after each wake.
+pub fn start_watcher(on_resume: impl Fn(Duration) + Send + Sync + 'static) {
+    let on_resume: Arc<dyn Fn(Duration) + Send + Sync> = Arc::new(on_resume);
+    let on_wake = Arc::clone(&on_resume);
+    platform::hook(move || report_resume(SystemTime::now(), &*on_wake));
+
+    supervisor::spawn(WORKER_NAME, move |token| {
+        let mut last = SystemTime::now();
+        *lock_recovering(&LAST_TICK) = Some(last);
+        while token.sleep(TICK) {
+            let now = SystemTime::now();
+            // A clock set backwards reads as no time passed
+            let elapsed = now.duration_since(last).unwrap_or_default();
+            last = now;
+            if slept_during(TICK, elapsed) {
+                report_resume(now, &*on_resume);
+            }
+            *lock_recovering(&LAST_TICK) = Some(now);
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+    use crossbeam::channel::{select, unbounded};
+    use log::{info, warn};
+    use crate::core::supervisor;
+
+    const WORKER_NAME: &str = "resume-signal";
+
+    /// Follows logind's sleep signal through `gdbus monitor`. Without gdbus
+    /// or a system bus only the wall clock check is left.
+    pub fn hook(on_wake: impl Fn() + Send + Sync + 'static) {
+        let mut child = match Command::new("gdbus")
+            .args(["monitor", "--system", "--dest", "org.freedesktop.login1", "--object-path", "/org/freedesktop/login1"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                info!("Not following logind sleep signals: {}", e);
+                return;
+            }
+        };
+        let Some(stdout) = child.stdout.take() else {
+            return;
+        };
+        supervisor::spawn(WORKER_NAME, move |token| {
+            let (line_tx, lines) = unbounded();
+            std::thread::scope(|scope| {
+                // Ends at EOF, once the monitor is killed below
+                scope.spawn(move || {
+                    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                        if line_tx.send(line).is_err() {
+                            break;
+                        }
+                    }
+                });
+                loop {
+                    select! {
+                        recv(token.channel()) -> _ => break,
+                        recv(lines) -> line => match line {
+                            Ok(line) if super::is_logind_wake(&line) => on_wake(),
+                            Ok(_) => {}
+                            Err(_) => {
+                                warn!("logind monitor exited, resume is only noticed by the wall clock");
+                                break;
+                            }
+                        },
+                    }
+                }
+                let _ = child.kill();
+            });
+            let _ = child.wait();
+        });
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use log::warn;
+
+    /// Listens for `PBT_APMRESUMEAUTOMATIC`, see `device_notify::start_power`.
+    pub fn hook(on_wake: impl Fn() + Send + Sync + 'static) {
+        if !crate::core::device_notify::start_power(on_wake) {
+            warn!("Not listening for resume notifications, resume is only noticed by the wall clock");
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc::declare::ClassDecl;
+    use objc::runtime::{Object, Sel};
+    use objc::{class, msg_send, sel, sel_impl};
+    use objc_foundation::{INSString, NSString};
+    use once_cell::sync::OnceCell;
+
+    static ON_WAKE: OnceCell<Box<dyn Fn() + Send + Sync>> = OnceCell::new();
+
+    extern "C" fn did_wake(_this: &Object, _cmd: Sel, _notification: *mut Object) {
+        if let Some(on_wake) = ON_WAKE.get() {
+            on_wake();
+        }
+    }
+
+    /// Observes `NSWorkspaceDidWakeNotification`, which is posted on the
+    /// main thread. The observer lives as long as the app.
+    pub fn hook(on_wake: impl Fn() + Send + Sync + 'static) {
+        if ON_WAKE.set(Box::new(on_wake)).is_err() {
+            return;
+        }
+        let Some(mut decl) = ClassDecl::new("EnableTouchpadWakeObserver", class!(NSObject)) else {
+            return;
+        };
+        unsafe {
+            decl.add_method(sel!(didWake:), did_wake as extern "C" fn(&Object, Sel, *mut Object));
+            let observer_class = decl.register();
+            let observer: *mut Object = msg_send![observer_class, new];
+            let workspace: *mut Object = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let center: *mut Object = msg_send![workspace, notificationCenter];
+            let name = NSString::from_str("NSWorkspaceDidWakeNotification");
+            let _: () = msg_send![center, addObserver: observer selector: sel!(didWake:) name: &*name object: std::ptr::null_mut::<Object>()];
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+mod platform {
+    pub fn hook(_on_wake: impl Fn() + Send + Sync + 'static) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_wake_is_reported_once_whichever_notices_it_first() {
+        let asleep = SystemTime::now() - Duration::from_secs(3600);
+        let woke = SystemTime::now();
+
+        // The platform signal first, then the watcher's tick for the same wake
+        assert!(is_new_resume(woke, Some(asleep), None));
+        assert!(!is_new_resume(woke + Duration::from_secs(1), Some(asleep), Some(woke)));
+        // The watcher first, then a late platform signal
+        assert!(!is_new_resume(woke + Duration::from_secs(5), Some(woke + Duration::from_secs(4)), Some(woke)));
+        // The next sleep is a new wake
+        let next = woke + Duration::from_secs(7200);
+        assert!(is_new_resume(next, Some(woke + Duration::from_secs(60)), Some(woke)));
+    }
+
+    #[test]
+    fn only_the_wake_half_of_prepare_for_sleep_counts() {
+        assert!(is_logind_wake("/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (false,)"));
+        assert!(!is_logind_wake("/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)"));
+        assert!(!is_logind_wake("/org/freedesktop/login1: org.freedesktop.login1.Manager.SessionNew ('3', objectpath '/org/freedesktop/login1/session/_33')"));
+    }
+}
@@ -0,0 +1,193 @@
+//! Parsing for scheduled enable/disable windows.
+//!
+//! Times are accepted in 24h ("14:30") and 12h ("2:30 PM") form and stored
+//! as minutes since midnight so comparisons don't depend on the locale.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+#[derive(Error, Debug, PartialEq)]
+#[allow(dead_code)]
+pub enum ScheduleError {
+    #[error("empty time")]
+    Empty,
+    #[error("\"{0}\" is not a time, expected e.g. {1}")]
+    Malformed(String, &'static str),
+    #[error("hour out of range in \"{0}\"")]
+    HourOutOfRange(String),
+    #[error("minute out of range in \"{0}\"")]
+    MinuteOutOfRange(String),
+}
+
+/// A window during which the touchpad is kept disabled, as entered by the user.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[allow(dead_code)]
+pub struct ScheduleEntry {
+    pub start: String,
+    pub end: String,
+}
+
+/// A parsed window; `start > end` wraps past midnight (e.g. 22:00–02:00).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
+pub struct Schedule {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl Schedule {
+    pub fn parse(entry: &ScheduleEntry) -> Result<Self, ScheduleError> {
+        Ok(Self {
+            start: parse_time(&entry.start)?,
+            end: parse_time(&entry.end)?,
+        })
+    }
+
+    pub fn contains(&self, minute: u16) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+/// Whether the user's locale conventionally writes times in 12h form.
+pub fn prefers_12_hour() -> bool {
+    let locale = ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_default();
+    ["en_US", "en_CA", "en_AU", "en_NZ", "en_PH", "en_IN", "hi_IN", "ar_"]
+        .iter()
+        .any(|prefix| locale.starts_with(prefix))
+}
+
+fn example() -> &'static str {
+    if prefers_12_hour() { "\"2:30 PM\"" } else { "\"14:30\"" }
+}
+
+/// Parses "14:30", "2:30 PM", "2:30pm" or "2 PM" into minutes since midnight.
+pub fn parse_time(input: &str) -> Result<u16, ScheduleError> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(ScheduleError::Empty);
+    }
+
+    let lower = trimmed.to_ascii_lowercase();
+    let (clock, meridiem) = if let Some(rest) = lower.strip_suffix("am") {
+        (rest.trim_end(), Some(false))
+    } else if let Some(rest) = lower.strip_suffix("pm") {
+        (rest.trim_end(), Some(true))
+    } else {
+        (lower.as_str(), None)
+    };
+
+    let malformed = || ScheduleError::Malformed(trimmed.to_string(), example());
+    let (hour, minute) = match clock.split_once(':') {
+        Some((h, m)) if m.len() == 2 => (h, m),
+        Some(_) => return Err(malformed()),
+        // "2 PM" has no minutes, bare "14" is ambiguous
+        None if meridiem.is_some() => (clock, "00"),
+        None => return Err(malformed()),
+    };
+    if hour.is_empty() || hour.len() > 2 {
+        return Err(malformed());
+    }
+    let hour: u16 = hour.parse().map_err(|_| malformed())?;
+    let minute: u16 = minute.parse().map_err(|_| malformed())?;
+
+    if minute >= 60 {
+        return Err(ScheduleError::MinuteOutOfRange(trimmed.to_string()));
+    }
+    let hour = match meridiem {
+        Some(pm) => {
+            if !(1..=12).contains(&hour) {
+                return Err(ScheduleError::HourOutOfRange(trimmed.to_string()));
+            }
+            (hour % 12) + if pm { 12 } else { 0 }
+        }
+        None => {
+            if hour >= 24 {
+                return Err(ScheduleError::HourOutOfRange(trimmed.to_string()));
+            }
+            hour
+        }
+    };
+
+    Ok((hour * 60 + minute) % MINUTES_PER_DAY)
+}
+
+/// Formats minutes since midnight the way the user's locale expects.
+pub fn format_time(minutes: u16) -> String {
+    let (hour, minute) = (minutes / 60, minutes % 60);
+    if prefers_12_hour() {
+        let display_hour = if hour % 12 == 0 { 12 } else { hour % 12 };
+        format!("{}:{:02} {}", display_hour, minute, if hour < 12 { "AM" } else { "PM" })
+    } else {
+        format!("{:02}:{:02}", hour, minute)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(start: &str, end: &str) -> Schedule {
+        Schedule::parse(&ScheduleEntry { start: start.into(), end: end.into() }).unwrap()
+    }
+
+    #[test]
+    fn parses_24_hour_times() {
+        assert_eq!(parse_time("00:00"), Ok(0));
+        assert_eq!(parse_time("14:30"), Ok(14 * 60 + 30));
+        assert_eq!(parse_time(" 9:05 "), Ok(9 * 60 + 5));
+        assert_eq!(parse_time("23:59"), Ok(MINUTES_PER_DAY - 1));
+    }
+
+    #[test]
+    fn parses_12_hour_times() {
+        assert_eq!(parse_time("2:30 PM"), Ok(14 * 60 + 30));
+        assert_eq!(parse_time("2:30pm"), Ok(14 * 60 + 30));
+        assert_eq!(parse_time("2 PM"), Ok(14 * 60));
+        assert_eq!(parse_time("12:00 AM"), Ok(0));
+        assert_eq!(parse_time("12:15 pm"), Ok(12 * 60 + 15));
+    }
+
+    #[test]
+    fn rejects_invalid_times() {
+        assert_eq!(parse_time("  "), Err(ScheduleError::Empty));
+        for input in ["14", "14:3", "14:300", "abc", ":30", "123:00", "two PM"] {
+            assert!(
+                matches!(parse_time(input), Err(ScheduleError::Malformed(..))),
+                "{input:?} should be malformed"
+            );
+        }
+        assert_eq!(parse_time("24:00"), Err(ScheduleError::HourOutOfRange("24:00".into())));
+        assert_eq!(parse_time("13:00 PM"), Err(ScheduleError::HourOutOfRange("13:00 PM".into())));
+        assert_eq!(parse_time("0:30 AM"), Err(ScheduleError::HourOutOfRange("0:30 AM".into())));
+        assert_eq!(parse_time("10:60"), Err(ScheduleError::MinuteOutOfRange("10:60".into())));
+    }
+
+    #[test]
+    fn window_wraps_past_midnight() {
+        let night = window("22:00", "2:00 AM");
+        assert!(night.contains(23 * 60));
+        assert!(night.contains(0));
+        assert!(night.contains(60));
+        assert!(!night.contains(2 * 60));
+        assert!(!night.contains(12 * 60));
+        assert!(!night.contains(22 * 60 - 1));
+    }
+
+    #[test]
+    fn window_within_a_day() {
+        let work = window("9:00 AM", "17:00");
+        assert!(work.contains(9 * 60));
+        assert!(work.contains(16 * 60 + 59));
+        assert!(!work.contains(17 * 60));
+        assert!(!work.contains(8 * 60));
+    }
+}
@@ -0,0 +1,1153 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use log::{info, warn};
+use crate::core::double_tap::DoubleTapModifier;
+use crate::core::mouse_watcher::ConflictPolicy;
+use crate::core::accelerator::normalize_accelerator;
+use crate::core::corner_toggle::Corner;
+use crate::core::cursor_confine;
+use crate::core::fullscreen;
+use crate::core::input_controller::{StateSource, SENSITIVITY_MAX, SENSITIVITY_MIN};
+use crate::core::schedule::{Schedule, ScheduleEntry};
+use crate::core::theme::PanelTheme;
+use crate::core::network_sync;
+use crate::core::power::{self, PowerSource};
+use crate::core::webhook;
+
+/// Lowest OSD opacity that still keeps the overlay readable.
+pub const MIN_OSD_OPACITY: f64 = 0.2;
+
+/// What to do when permissions are missing at startup.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+pub enum PermissionPromptMode {
+    /// Show a one-time warning.
+    Auto,
+    /// Open the settings window so the user can walk through the checklist.
+    Onboarding,
+    /// Stay quiet until the first action fails.
+    Silent,
+}
+
+/// Touchpad state to apply when the app starts.
+///
+/// Precedence: `Enabled`/`Disabled` always win. `Last` re-applies the state
+/// saved by `persist_state` and does nothing if persistence is off or no state
+/// was saved. `restore_on_exit` re-enables the device on a clean exit but does
+/// not overwrite the persisted state, so a touchpad the user disabled comes back
+/// disabled on the next start.
+///
+/// Nothing re-enables the device if the app crashes, so persistence is off by
+/// default: with the defaults the app leaves the device alone at startup, as
+/// before `startup_state` existed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+pub enum StartupState {
+    Enabled,
+    Disabled,
+    Last,
+}
+
+/// Tray icon look.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+pub enum TrayIconStyle {
+    Color,
+    Monochrome,
+    /// Follows the system theme: monochrome template icons on macOS, whose
+    /// menu bar inverts them, and monochrome icons shaded for the panel
+    /// elsewhere. Color if the theme can't be determined.
+    Auto,
+}
+
+impl TrayIconStyle {
+    pub fn is_monochrome(&self, theme: Option<PanelTheme>) -> bool {
+        match self {
+            TrayIconStyle::Color => false,
+            TrayIconStyle::Monochrome => true,
+            TrayIconStyle::Auto => cfg!(target_os = "macos") || theme.is_some(),
+        }
+    }
+}
+
+/// What to do when the managed device disappears, e.g. a detachable keyboard
+/// cover or a driver reload.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+pub enum DeviceLostAction {
+    /// Wait for the same device to come back and manage it again.
+    Redetect,
+    /// Switch to any other touchpad or trackpoint that is present.
+    Fallback,
+    /// Only tell the user.
+    Notify,
+}
+
+/// Which way a toggle goes when the current state can't be read.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+pub enum UnknownToggleTarget {
+    /// The default: if the guess is wrong the touchpad is merely left on,
+    /// whereas a wrong disable could leave the user without a pointer.
+    Enable,
+    Disable,
+}
+
+/// What to do when another app or the hardware key changes the state.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+pub enum ExternalChangePolicy {
+    /// Accept the new state.
+    Defer,
+    /// Switch back to the state we set.
+    Reclaim,
+}
+
+/// How log lines are written.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per line, for log aggregators.
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(dead_code)]
+pub struct Settings {
+    pub enable_on_mouse_disconnect: bool,
+    pub disable_on_mouse_connect: bool,
+    pub auto_toggle_conflict_policy: ConflictPolicy,
+    pub mouse_poll_interval_ms: u64,
+    /// Devices (by name or id) that never count as a mouse, e.g. a tablet or KVM.
+    /// On Windows the id is the hardware id, and a prefix such as
+    /// `HID\\VID_056A&PID_0357` covers all of the device's collections.
+    pub ignored_mouse_devices: Vec<String>,
+    pub enable_hotkey: String,
+    pub disable_hotkey: String,
+    pub settings_hotkey: String,
+    /// Toggles tap-to-click only; empty means unbound.
+    pub tap_to_click_hotkey: String,
+    pub natural_scrolling_hotkey: String,
+    /// Toggles "taps disabled": keeps the touchpad on but turns tap-to-click off.
+    pub taps_disabled_hotkey: String,
+    /// Cycles through the scroll methods the touchpad supports.
+    pub scroll_method_hotkey: String,
+    /// Cycles the pointer speed through `sensitivity_levels`.
+    pub sensitivity_hotkey: String,
+    /// Switches a separately listed trackpoint, e.g. on ThinkPads.
+    pub trackpoint_hotkey: String,
+    /// Toggles the touchpad and switches the trackpoint to match.
+    pub toggle_both_hotkey: String,
+    /// Normalized pointer speeds (-1.0 to 1.0) for `sensitivity_hotkey`,
+    /// e.g. low, medium and high.
+    pub sensitivity_levels: Vec<f64>,
+    /// Toggles the touchpad on a double-tap of this modifier; `None` disables it.
+    pub double_tap_modifier: Option<DoubleTapModifier>,
+    /// Maximum time for each tap and between the two taps.
+    pub double_tap_window_ms: u64,
+    /// Toggles the touchpad on a dwell in this screen corner followed by
+    /// leaving and returning; `None` disables it.
+    pub corner_toggle: Option<Corner>,
+    /// How long the pointer must rest in the corner before leaving it.
+    pub corner_toggle_dwell_ms: u64,
+    pub tray_icon_style: TrayIconStyle,
+    /// Ask before quitting from the tray while the touchpad is disabled.
+    pub confirm_quit: bool,
+    /// Show the time left on a temporary enable, re-enable safeguard or
+    /// timed pause in the tray tooltip.
+    pub tray_countdown: bool,
+    /// Flash the tray icon while a device is lost or a permission is missing.
+    pub tray_alert_flash: bool,
+    /// Light a touchpad or keyboard LED while the touchpad is disabled.
+    pub led_indicator: bool,
+    pub show_osd: bool,
+    pub osd_opacity: f64,
+    pub osd_animate: bool,
+    /// How long the OSD stays visible before fading out.
+    pub osd_duration_ms: u64,
+    /// Show a bar that depletes over `osd_duration_ms`.
+    pub osd_progress_bar: bool,
+    /// Suppress the OSD while the OS is in Do Not Disturb / Focus mode.
+    pub respect_dnd: bool,
+    pub play_sound: bool,
+    /// Custom sound files; `None` uses the bundled defaults.
+    pub sound_enabled_path: Option<String>,
+    pub sound_disabled_path: Option<String>,
+    pub sound_warning_path: Option<String>,
+    /// Delay before re-reading the device state after a toggle; some backends
+    /// apply changes asynchronously.
+    pub verify_delay_ms: u64,
+    pub restore_cursor_position: bool,
+    /// Move the cursor with the numeric keypad while the touchpad is disabled.
+    pub mouse_keys: bool,
+    /// Interval between cursor steps while a direction key is held.
+    pub mouse_keys_tick_ms: u64,
+    /// How long a key must be held before the cursor starts moving.
+    pub mouse_keys_dead_time_ms: u64,
+    pub mouse_keys_step_px: u32,
+    /// Confine the cursor to the active window while mouse keys are on and
+    /// the touchpad was disabled by hand; auto-disables never confine. Not
+    /// supported on macOS.
+    pub confine_cursor: bool,
+    /// Disable the touchpad while the focused window fills its monitor, and
+    /// restore it afterwards or when the monitors change. Not supported on
+    /// macOS.
+    pub disable_in_fullscreen: bool,
+    pub startup_state: StartupState,
+    /// Apply `startup_state` without the OSD, so autostart stays quiet.
+    pub suppress_startup_osd: bool,
+    /// Save the touchpad state on every change so `StartupState::Last` can restore it.
+    pub persist_state: bool,
+    /// Re-enable the touchpad when the app exits.
+    pub restore_on_exit: bool,
+    /// Allow disabling even when no other pointing device would remain.
+    pub allow_disable_without_mouse: bool,
+    /// Re-enable a touchpad left disabled this long without a mouse present.
+    pub auto_reenable_after_minutes: Option<u64>,
+    /// Windows during which the touchpad is disabled.
+    pub schedules: Vec<ScheduleEntry>,
+    /// Natural scrolling to apply to the device; `None` leaves the OS setting alone.
+    pub natural_scrolling: Option<bool>,
+    pub permission_prompt_mode: PermissionPromptMode,
+    /// Minimum interval between two applied state changes.
+    pub toggle_cooldown_ms: u64,
+    /// How long auto-toggles are ignored after a manual state change; 0
+    /// only cancels the auto-toggles already queued.
+    pub manual_override_ms: u64,
+    /// Timeout for external backend commands such as xinput.
+    pub command_timeout_ms: u64,
+    /// Linux: switch the device through the kernel's inhibit interface when
+    /// it is writable, instead of xinput. Takes effect on re-detection.
+    pub evdev_backend: bool,
+    /// macOS: whether the touchpad state is read, taken from what we last
+    /// applied, or read with that as the fallback.
+    pub macos_state_source: StateSource,
+    /// macOS: retries after a transient AppleScript failure, e.g. System
+    /// Events not ready yet.
+    pub applescript_retries: u32,
+    /// Backend picked by the last probe, for diagnostics; every start probes again.
+    pub probed_backend: Option<String>,
+    pub on_device_lost: DeviceLostAction,
+    /// Used by toggles when reading the state fails for a reason other
+    /// than a missing permission.
+    pub unknown_toggle_target: UnknownToggleTarget,
+    /// After waking from sleep, put back the state we last applied if the
+    /// firmware changed it, e.g. re-enabled the touchpad.
+    pub reapply_state_on_resume: bool,
+    /// A disable from a hotkey only applies when pressed twice within
+    /// `double_press_window_ms`; enabling never needs confirmation.
+    pub double_press_to_disable: bool,
+    pub double_press_window_ms: u64,
+    /// An external disable that leaves no mouse is always undone while
+    /// `enable_on_mouse_disconnect` is on, whatever this says.
+    pub external_change_policy: ExternalChangePolicy,
+    pub log_format: LogFormat,
+    /// POSTs each state change as JSON to this URL; `None` or an empty
+    /// string, as a cleared field in the UI sends, disables it.
+    #[serde(deserialize_with = "empty_as_none")]
+    pub webhook_url: Option<String>,
+    /// Broadcast state changes on the LAN and apply ones signed with the same
+    /// `network_sync_secret`, see `network_sync`.
+    pub network_sync_enabled: bool,
+    pub network_sync_secret: Option<String>,
+    pub network_sync_port: u16,
+    /// Allows `simulate_hotkey` in release builds.
+    pub allow_hotkey_simulation: bool,
+    /// Allows `simulate_mouse_event` in release builds.
+    pub allow_mouse_simulation: bool,
+    /// Named partial overrides of these settings, keyed by profile name.
+    pub profiles: BTreeMap<String, Map<String, Value>>,
+    pub active_profile: Option<String>,
+    /// Overrides applied automatically on "battery" or "ac" power, on top of
+    /// the active profile.
+    pub power_profiles: BTreeMap<String, Map<String, Value>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            enable_on_mouse_disconnect: true,
+            disable_on_mouse_connect: true,
+            auto_toggle_conflict_policy: ConflictPolicy::DisconnectWins,
+            mouse_poll_interval_ms: 2000,
+            ignored_mouse_devices: Vec::new(),
+            enable_hotkey: "Ctrl+Shift+T".to_string(),
+            disable_hotkey: "Ctrl+Shift+Y".to_string(),
+            settings_hotkey: "Ctrl+Shift+O".to_string(),
+            tap_to_click_hotkey: String::new(),
+            natural_scrolling_hotkey: String::new(),
+            taps_disabled_hotkey: String::new(),
+            scroll_method_hotkey: String::new(),
+            sensitivity_hotkey: String::new(),
+            trackpoint_hotkey: String::new(),
+            toggle_both_hotkey: String::new(),
+            sensitivity_levels: vec![-0.5, 0.0, 0.5],
+            double_tap_modifier: None,
+            double_tap_window_ms: 300,
+            corner_toggle: None,
+            corner_toggle_dwell_ms: 800,
+            tray_icon_style: TrayIconStyle::Auto,
+            confirm_quit: true,
+            tray_countdown: true,
+            tray_alert_flash: true,
+            led_indicator: false,
+            show_osd: true,
+            osd_opacity: 0.9,
+            osd_animate: true,
+            osd_duration_ms: 1500,
+            osd_progress_bar: true,
+            respect_dnd: true,
+            play_sound: false,
+            sound_enabled_path: None,
+            sound_disabled_path: None,
+            sound_warning_path: None,
+            verify_delay_ms: 150,
+            restore_cursor_position: false,
+            mouse_keys: false,
+            mouse_keys_tick_ms: 16,
+            mouse_keys_dead_time_ms: 0,
+            mouse_keys_step_px: 4,
+            confine_cursor: false,
+            disable_in_fullscreen: false,
+            startup_state: StartupState::Last,
+            suppress_startup_osd: true,
+            persist_state: false,
+            restore_on_exit: false,
+            allow_disable_without_mouse: true,
+            auto_reenable_after_minutes: None,
+            schedules: Vec::new(),
+            natural_scrolling: None,
+            permission_prompt_mode: PermissionPromptMode::Auto,
+            toggle_cooldown_ms: 200,
+            manual_override_ms: 5000,
+            command_timeout_ms: 2000,
+            evdev_backend: true,
+            macos_state_source: StateSource::Auto,
+            applescript_retries: 2,
+            probed_backend: None,
+            on_device_lost: DeviceLostAction::Redetect,
+            unknown_toggle_target: UnknownToggleTarget::Enable,
+            reapply_state_on_resume: true,
+            double_press_to_disable: false,
+            double_press_window_ms: 1000,
+            external_change_policy: ExternalChangePolicy::Defer,
+            log_format: LogFormat::Text,
+            webhook_url: None,
+            network_sync_enabled: false,
+            network_sync_secret: None,
+            network_sync_port: 47810,
+            allow_hotkey_simulation: false,
+            allow_mouse_simulation: false,
+            profiles: BTreeMap::new(),
+            active_profile: None,
+            power_profiles: BTreeMap::new(),
+        }
+    }
+}
+
+/// Settings file admins can deploy for every user of the machine; each
+/// user's own settings take precedence over it.
+pub fn system_settings_path() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        let program_data = std::env::var_os("ProgramData").unwrap_or_else(|| r"C:\ProgramData".into());
+        PathBuf::from(program_data).join("enable-touchpad").join("settings.json")
+    }
+    #[cfg(target_os = "macos")]
+    {
+        PathBuf::from("/Library/Application Support/enable-touchpad/settings.json")
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        PathBuf::from("/etc/enable-touchpad/settings.json")
+    }
+}
+
+/// Reads the system-wide defaults at `path`. A missing file means no
+/// defaults; an unreadable or invalid one is logged and ignored, as are
+/// unknown keys and values `Settings::validate` rejects.
+pub fn load_system_defaults(path: &Path) -> Map<String, Value> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Map::new(),
+        Err(e) => {
+            warn!("Failed to read system settings {}: {}", path.display(), e);
+            return Map::new();
+        }
+    };
+    let mut map = match serde_json::from_str(&contents) {
+        Ok(Value::Object(map)) => map,
+        Ok(_) => {
+            warn!("System settings {} are not a JSON object", path.display());
+            return Map::new();
+        }
+        Err(e) => {
+            warn!("Failed to parse system settings {}: {}", path.display(), e);
+            return Map::new();
+        }
+    };
+    let known = file_keys_of(&Settings::default());
+    map.retain(|key, _| {
+        let keep = known.contains(key);
+        if !keep {
+            warn!("Ignoring unknown system setting: {}", key);
+        }
+        keep
+    });
+    let map = validated_system_layer(map, path);
+    info!("Loaded {} system default(s) from {}", map.len(), path.display());
+    map
+}
+
+// If the layer is invalid, drops the defaults that are invalid on their own,
+// then the whole layer if the rest is still invalid together. Values come back as validated, e.g. with
+// hotkeys normalized and numbers clamped.
+fn validated_system_layer(mut map: Map<String, Value>, path: &Path) -> Map<String, Value> {
+    let validate = |layer: &Map<String, Value>| -> Result<Settings, String> {
+        let mut settings = Settings::default().with_layer(layer)?;
+        settings.validate()?;
+        Ok(settings)
+    };
+    if validate(&map).is_err() {
+        map.retain(|key, value| match validate(&Map::from_iter([(key.clone(), value.clone())])) {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("Ignoring system setting {}: {}", key, e);
+                false
+            }
+        });
+    }
+    let validated = match validate(&map).and_then(|settings| serde_json::to_value(settings).map_err(|e| e.to_string())) {
+        Ok(Value::Object(validated)) => validated,
+        Ok(_) => return Map::new(),
+        Err(e) => {
+            warn!("Ignoring system settings {}: {}", path.display(), e);
+            return Map::new();
+        }
+    };
+    map.into_iter()
+        .filter_map(|(key, _)| validated.get(&key).cloned().map(|value| (key, value)))
+        .collect()
+}
+
+/// Keys the user changed in the settings file at `path`: ones whose value
+/// differs from the built-in default, so a file saved in full before system
+/// defaults were deployed doesn't shadow them. Empty if it can't be read.
+pub fn user_keys(path: &Path) -> Vec<String> {
+    let Ok(Value::Object(defaults)) = serde_json::to_value(Settings::default()) else {
+        return Vec::new();
+    };
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Map<String, Value>>(&contents).ok())
+        .map(|map| {
+            map.into_iter()
+                .filter(|(key, value)| defaults.get(key) != Some(value))
+                .map(|(key, _)| key)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn file_keys_of(settings: &Settings) -> Vec<String> {
+    match serde_json::to_value(settings) {
+        Ok(Value::Object(map)) => map.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl Settings {
+    /// Loads settings from `path`, falling back to defaults if missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return Self::default(),
+        };
+        match serde_json::from_str(&contents) {
+            Ok(settings) => settings,
+            Err(e) => {
+                warn!("Failed to parse settings file {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Like `load`, but if `path` exists and fails to parse, the settings
+    /// at `last_good` are copied over it and loaded instead.
+    pub fn load_or_restore(path: &Path, last_good: &Path) -> Self {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        let error = match serde_json::from_str(&contents) {
+            Ok(settings) => return settings,
+            Err(e) => e,
+        };
+        warn!("Failed to parse settings file {}: {}", path.display(), error);
+        if !last_good.is_file() {
+            return Self::default();
+        }
+        match restore_file(last_good, path) {
+            Ok(()) => {
+                warn!("Restored last known good settings from {}", last_good.display());
+                Self::load(path)
+            }
+            Err(e) => {
+                warn!("Failed to restore last known good settings: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    /// Fills in `system` defaults for every key not in `user_keys`, the keys
+    /// the user's own file sets. Values that don't fit are logged and skipped.
+    pub fn with_system_defaults(self, system: &Map<String, Value>, user_keys: &[String]) -> Settings {
+        if system.is_empty() {
+            return self;
+        }
+        let layer = system
+            .iter()
+            .filter(|(key, _)| !user_keys.contains(key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        match self.with_layer(&layer) {
+            Ok(settings) => settings,
+            Err(e) => {
+                warn!("Ignoring system settings: {}", e);
+                self
+            }
+        }
+    }
+
+    // These settings with the values in `layer` written over them as is
+    fn with_layer(&self, layer: &Map<String, Value>) -> Result<Settings, String> {
+        let Value::Object(mut map) = serde_json::to_value(self).map_err(|e| e.to_string())? else {
+            return Err("settings did not serialize to an object".to_string());
+        };
+        map.extend(layer.iter().map(|(key, value)| (key.clone(), value.clone())));
+        serde_json::from_value(Value::Object(map)).map_err(|e| e.to_string())
+    }
+
+    /// Saves only the values that differ from `base`, so keys left at the
+    /// system defaults keep following them.
+    pub fn save_over(&self, path: &Path, base: &Settings) -> Result<(), String> {
+        let (Value::Object(mut map), Value::Object(base)) = (
+            serde_json::to_value(self).map_err(|e| e.to_string())?,
+            serde_json::to_value(base).map_err(|e| e.to_string())?,
+        ) else {
+            return Err("settings did not serialize to an object".to_string());
+        };
+        map.retain(|key, value| base.get(key) != Some(value));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let contents = serde_json::to_string_pretty(&map).map_err(|e| e.to_string())?;
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    /// These settings as JSON with secrets such as the webhook URL masked,
+    /// including inside profiles, for sharing in bug reports.
+    pub fn redacted(&self) -> Result<Value, String> {
+        let mut value = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        let redact = |map: &mut Map<String, Value>| {
+            if let Some(Value::String(url)) = map.get("webhook_url") {
+                let redacted = webhook::redact_url(url);
+                map.insert("webhook_url".to_string(), Value::String(redacted));
+            }
+            if let Some(Value::String(_)) = map.get("network_sync_secret") {
+                map.insert("network_sync_secret".to_string(), Value::String("<redacted>".to_string()));
+            }
+        };
+        if let Value::Object(map) = &mut value {
+            redact(map);
+            for key in ["profiles", "power_profiles"] {
+                if let Some(Value::Object(profiles)) = map.get_mut(key) {
+                    for profile in profiles.values_mut() {
+                        if let Value::Object(profile) = profile {
+                            redact(profile);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    /// Resolves the settings actually in effect: these base settings, then the
+    /// active profile's overrides, then `live` overrides, later layers winning.
+    pub fn merged(&self, live: &Map<String, Value>) -> Result<Settings, String> {
+        let mut merged = match serde_json::to_value(self).map_err(|e| e.to_string())? {
+            Value::Object(map) => map,
+            _ => return Err("settings did not serialize to an object".to_string()),
+        };
+
+        let profile = self.active_profile.as_ref().and_then(|name| self.profiles.get(name));
+        for layer in profile.into_iter().chain(std::iter::once(live)) {
+            for (key, value) in layer {
+                // Profiles can't switch or redefine profiles
+                if key == "profiles" || key == "active_profile" || key == "power_profiles" {
+                    continue;
+                }
+                if !merged.contains_key(key) {
+                    return Err(format!("unknown setting: {}", key));
+                }
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+
+        serde_json::from_value(Value::Object(merged)).map_err(|e| e.to_string())
+    }
+
+    /// The settings in effect on `power_source` with `live` overrides:
+    /// base < active profile < power profile < live, later layers winning.
+    pub fn effective(&self, power_source: PowerSource, live: &Map<String, Value>) -> Result<Settings, String> {
+        let mut overrides = self.power_profiles.get(power_source.key()).cloned().unwrap_or_default();
+        overrides.extend(live.clone());
+        self.merged(&overrides)
+    }
+
+    /// A single field by its serialized name.
+    pub fn get_field(&self, key: &str) -> Result<Value, String> {
+        match serde_json::to_value(self).map_err(|e| e.to_string())? {
+            Value::Object(mut map) => map.remove(key).ok_or_else(|| format!("unknown setting: {}", key)),
+            _ => Err("settings did not serialize to an object".to_string()),
+        }
+    }
+
+    /// A copy with one field replaced and validated.
+    pub fn with_field(&self, key: &str, value: Value) -> Result<Settings, String> {
+        let mut map = match serde_json::to_value(self).map_err(|e| e.to_string())? {
+            Value::Object(map) => map,
+            _ => return Err("settings did not serialize to an object".to_string()),
+        };
+        if !map.contains_key(key) {
+            return Err(format!("unknown setting: {}", key));
+        }
+        map.insert(key.to_string(), value);
+        let mut updated: Settings =
+            serde_json::from_value(Value::Object(map)).map_err(|e| format!("invalid value for {}: {}", key, e))?;
+        updated.validate()?;
+        Ok(updated)
+    }
+
+    /// Adds an empty profile, i.e. one that changes nothing yet.
+    pub fn create_profile(&mut self, name: &str) -> Result<(), String> {
+        let name = self.new_profile_name(name)?;
+        self.profiles.insert(name, Map::new());
+        Ok(())
+    }
+
+    /// Renames a profile, keeping it active if it was.
+    pub fn rename_profile(&mut self, old: &str, new: &str) -> Result<(), String> {
+        if !self.profiles.contains_key(old) {
+            return Err(format!("Unknown profile: {}", old));
+        }
+        let new = self.new_profile_name(new)?;
+        let overrides = self.profiles.remove(old).unwrap_or_default();
+        self.profiles.insert(new.clone(), overrides);
+        if self.active_profile.as_deref() == Some(old) {
+            self.active_profile = Some(new);
+        }
+        Ok(())
+    }
+
+    /// Removes a profile. Deleting the active one falls back to no profile,
+    /// i.e. the base settings.
+    pub fn delete_profile(&mut self, name: &str) -> Result<(), String> {
+        if self.profiles.remove(name).is_none() {
+            return Err(format!("Unknown profile: {}", name));
+        }
+        if self.active_profile.as_deref() == Some(name) {
+            self.active_profile = None;
+        }
+        Ok(())
+    }
+
+    /// Copies a profile to the first free "<name> copy", "<name> copy 2", ...
+    /// and returns the new name.
+    pub fn duplicate_profile(&mut self, name: &str) -> Result<String, String> {
+        let overrides = self.profiles.get(name).cloned().ok_or_else(|| format!("Unknown profile: {}", name))?;
+        let copy = (1..)
+            .map(|n| if n == 1 { format!("{} copy", name) } else { format!("{} copy {}", name, n) })
+            .find(|candidate| !self.profiles.contains_key(candidate))
+            .unwrap_or_default();
+        self.profiles.insert(copy.clone(), overrides);
+        Ok(copy)
+    }
+
+    // Trimmed `name`, if it is non-empty and not taken
+    fn new_profile_name(&self, name: &str) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Profile name must not be empty".to_string());
+        }
+        if self.profiles.contains_key(name) {
+            return Err(format!("A profile named {} already exists", name));
+        }
+        Ok(name.to_string())
+    }
+
+    /// Parsed schedules; entries are validated on save so bad ones are skipped here.
+    pub fn parsed_schedules(&self) -> Vec<Schedule> {
+        self.schedules.iter().filter_map(|entry| Schedule::parse(entry).ok()).collect()
+    }
+
+    /// Checks a settings file without applying it. Returns warnings (unknown
+    /// keys, clamped values, anything `validate` rejects); errors only if the
+    /// file isn't a parseable settings object.
+    pub fn lint_file(path: &Path) -> Result<Vec<String>, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let value: Value = serde_json::from_str(&contents).map_err(|e| format!("Invalid JSON: {}", e))?;
+        let Value::Object(map) = &value else {
+            return Err("Settings file must contain a JSON object".to_string());
+        };
+
+        let mut warnings = Vec::new();
+        let known = match serde_json::to_value(Settings::default()) {
+            Ok(Value::Object(known)) => known,
+            _ => Map::new(),
+        };
+        for key in map.keys().filter(|key| !known.contains_key(*key)) {
+            warnings.push(format!("unknown setting: {}", key));
+        }
+
+        let mut settings: Settings = match serde_json::from_value(value.clone()) {
+            Ok(settings) => settings,
+            Err(e) => {
+                warnings.push(format!("invalid value: {}", e));
+                return Ok(warnings);
+            }
+        };
+        warnings.extend(settings.validate_and_clamp());
+        if let Err(e) = settings.validate() {
+            warnings.push(e);
+        }
+        Ok(warnings)
+    }
+
+    fn hotkeys_mut(&mut self) -> [(&'static str, &mut String); 10] {
+        [
+            ("enable_hotkey", &mut self.enable_hotkey),
+            ("disable_hotkey", &mut self.disable_hotkey),
+            ("settings_hotkey", &mut self.settings_hotkey),
+            ("tap_to_click_hotkey", &mut self.tap_to_click_hotkey),
+            ("natural_scrolling_hotkey", &mut self.natural_scrolling_hotkey),
+            ("taps_disabled_hotkey", &mut self.taps_disabled_hotkey),
+            ("scroll_method_hotkey", &mut self.scroll_method_hotkey),
+            ("sensitivity_hotkey", &mut self.sensitivity_hotkey),
+            ("trackpoint_hotkey", &mut self.trackpoint_hotkey),
+            ("toggle_both_hotkey", &mut self.toggle_both_hotkey),
+        ]
+    }
+
+    /// Writes the hotkeys in canonical form for display; a hand-edited file
+    /// may not be. Unparsable ones stay as written for `validate` to report.
+    pub fn normalize_hotkeys(mut self) -> Self {
+        for (_, hotkey) in self.hotkeys_mut() {
+            if let Ok(normalized) = normalize_accelerator(hotkey) {
+                *hotkey = normalized;
+            }
+        }
+        self
+    }
+
+    /// Rejects values that can't be applied and clamps the rest into range.
+    pub fn validate(&mut self) -> Result<(), String> {
+        for warning in self.validate_and_clamp() {
+            warn!("{}", warning);
+        }
+        let mut hotkeys = self.hotkeys_mut();
+        // Stored in canonical form so comparisons and display agree
+        for (name, hotkey) in hotkeys.iter_mut().filter(|(_, hotkey)| !hotkey.is_empty()) {
+            **hotkey = normalize_accelerator(hotkey).map_err(|e| format!("{}: {}", name, e))?;
+        }
+        for (i, (name, hotkey)) in hotkeys.iter().enumerate() {
+            for (other_name, other) in &hotkeys[i + 1..] {
+                if !hotkey.is_empty() && hotkey == other {
+                    return Err(format!("{} conflicts with {}: {}", name, other_name, hotkey));
+                }
+            }
+        }
+        let sounds = [
+            ("sound_enabled_path", &self.sound_enabled_path),
+            ("sound_disabled_path", &self.sound_disabled_path),
+            ("sound_warning_path", &self.sound_warning_path),
+        ];
+        for (name, path) in sounds {
+            if let Some(path) = path {
+                if !Path::new(path).is_file() {
+                    return Err(format!("{} does not point to a file: {}", name, path));
+                }
+            }
+        }
+        for (i, entry) in self.schedules.iter().enumerate() {
+            Schedule::parse(entry).map_err(|e| format!("schedule {}: {}", i + 1, e))?;
+        }
+        if let Some(name) = &self.active_profile {
+            if !self.profiles.contains_key(name) {
+                return Err(format!("active_profile refers to unknown profile: {}", name));
+            }
+        }
+        // Each profile must yield valid settings on its own, not just parse
+        let mut base = self.clone();
+        base.active_profile = None;
+        base.profiles.clear();
+        base.power_profiles.clear();
+        for (name, profile) in &self.profiles {
+            base.merged(profile)
+                .and_then(|mut merged| merged.validate())
+                .map_err(|e| format!("invalid profile override in {}: {}", name, e))?;
+        }
+        for (source, profile) in &self.power_profiles {
+            if !power::PROFILE_KEYS.contains(&source.as_str()) {
+                return Err(format!("power_profiles: unknown power source {} (expected battery or ac)", source));
+            }
+            base.merged(profile)
+                .and_then(|mut merged| merged.validate())
+                .map_err(|e| format!("invalid {} power profile: {}", source, e))?;
+        }
+        network_sync::validate(self)?;
+        if let Some(url) = &self.webhook_url {
+            webhook::validate_url(url)?;
+        }
+        if !self.sensitivity_hotkey.is_empty() && self.sensitivity_levels.is_empty() {
+            return Err("sensitivity_levels must not be empty when sensitivity_hotkey is set".to_string());
+        }
+        if self.confine_cursor && !cursor_confine::SUPPORTED {
+            return Err("confine_cursor is not supported on this platform".to_string());
+        }
+        if self.disable_in_fullscreen && !fullscreen::SUPPORTED {
+            return Err("disable_in_fullscreen is not supported on this platform".to_string());
+        }
+        Ok(())
+    }
+
+    /// Clamps every numeric setting into its supported range. Returns a
+    /// warning for each value that was adjusted.
+    pub fn validate_and_clamp(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let w = &mut warnings;
+        clamp_field(w, "mouse_poll_interval_ms", &mut self.mouse_poll_interval_ms, 100, 60_000);
+        clamp_field(w, "double_tap_window_ms", &mut self.double_tap_window_ms, 50, 2_000);
+        clamp_field(w, "corner_toggle_dwell_ms", &mut self.corner_toggle_dwell_ms, 1, 10_000);
+        if !self.osd_opacity.is_finite() {
+            w.push(format!("osd_opacity {} adjusted to 1", self.osd_opacity));
+            self.osd_opacity = 1.0;
+        }
+        clamp_field(w, "osd_opacity", &mut self.osd_opacity, MIN_OSD_OPACITY, 1.0);
+        clamp_field(w, "osd_duration_ms", &mut self.osd_duration_ms, 100, 10_000);
+        clamp_field(w, "verify_delay_ms", &mut self.verify_delay_ms, 0, 5_000);
+        clamp_field(w, "mouse_keys_tick_ms", &mut self.mouse_keys_tick_ms, 1, 1_000);
+        clamp_field(w, "mouse_keys_dead_time_ms", &mut self.mouse_keys_dead_time_ms, 0, 5_000);
+        clamp_field(w, "mouse_keys_step_px", &mut self.mouse_keys_step_px, 1, 100);
+        if let Some(minutes) = &mut self.auto_reenable_after_minutes {
+            clamp_field(w, "auto_reenable_after_minutes", minutes, 1, 24 * 60);
+        }
+        clamp_field(w, "toggle_cooldown_ms", &mut self.toggle_cooldown_ms, 0, 10_000);
+        clamp_field(w, "manual_override_ms", &mut self.manual_override_ms, 0, 600_000);
+        clamp_field(w, "command_timeout_ms", &mut self.command_timeout_ms, 100, 60_000);
+        clamp_field(w, "applescript_retries", &mut self.applescript_retries, 0, 10);
+        clamp_field(w, "double_press_window_ms", &mut self.double_press_window_ms, 100, 5_000);
+        clamp_field(w, "network_sync_port", &mut self.network_sync_port, 1024, u16::MAX);
+        // Levels that aren't numbers can't be clamped, so they're dropped
+        self.sensitivity_levels.retain(|level| {
+            let finite = level.is_finite();
+            if !finite {
+                w.push(format!("sensitivity_levels: dropped {}", level));
+            }
+            finite
+        });
+        for level in &mut self.sensitivity_levels {
+            clamp_field(w, "sensitivity_levels", level, SENSITIVITY_MIN, SENSITIVITY_MAX);
+        }
+        // Profiles are clamped here too, so merging them never has to
+        let mut base = self.clone();
+        base.active_profile = None;
+        base.profiles.clear();
+        base.power_profiles.clear();
+        for (name, profile) in &mut self.profiles {
+            for warning in base.clamp_layer(profile) {
+                warnings.push(format!("profile {}: {}", name, warning));
+            }
+        }
+        for (source, profile) in &mut self.power_profiles {
+            for warning in base.clamp_layer(profile) {
+                warnings.push(format!("{} power profile: {}", source, warning));
+            }
+        }
+        warnings
+    }
+
+    /// Clamps the values in an override `layer` on top of these settings, in
+    /// place. A layer that doesn't merge is left for `validate` to reject.
+    pub fn clamp_layer(&self, layer: &mut Map<String, Value>) -> Vec<String> {
+        let Ok(mut merged) = self.merged(layer) else {
+            return Vec::new();
+        };
+        let warnings = merged.validate_and_clamp();
+        if let Ok(Value::Object(clamped)) = serde_json::to_value(&merged) {
+            for (key, value) in layer.iter_mut() {
+                if let Some(clamped) = clamped.get(key) {
+                    *value = clamped.clone();
+                }
+            }
+        }
+        warnings
+    }
+}
+
+fn empty_as_none<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.filter(|value| !value.trim().is_empty()))
+}
+
+fn clamp_field<T: PartialOrd + Copy + std::fmt::Display>(warnings: &mut Vec<String>, name: &str, value: &mut T, min: T, max: T) {
+    let clamped = if *value < min { min } else if *value > max { max } else { *value };
+    if clamped != *value {
+        warnings.push(format!("{} {} adjusted to {}", name, value, clamped));
+        *value = clamped;
+    }
+}
+
+/// Copies `from` over `to`, keeping the replaced file next to it as `.bak`.
+/// Leaves `to` untouched if there is nothing to restore.
+pub fn restore_file(from: &Path, to: &Path) -> Result<(), String> {
+    if !from.is_file() {
+        return Err(format!("{} does not exist", from.display()));
+    }
+    if to.is_file() {
+        fs::copy(to, to.with_extension("json.bak")).map_err(|e| e.to_string())?;
+    }
+    fs::copy(from, to).map(|_| ()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn user_settings_take_precedence_over_system_defaults() {
+        let dir = std::env::temp_dir().join("enable-touchpad-system-layer");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let system_path = dir.join("system.json");
+        fs::write(&system_path, json!({
+            "osd_duration_ms": 4000,
+            "toggle_cooldown_ms": 500,
+            "enable_hotkey": "control+alt+e",
+            "schedules": [{ "start": "25:00", "end": "02:00" }],
+        }).to_string()).unwrap();
+        let system = load_system_defaults(&system_path);
+        // Validated: normalized, and the invalid value dropped
+        assert_eq!(system.get("enable_hotkey"), Some(&json!("Ctrl+Alt+E")));
+        assert!(!system.contains_key("schedules"));
+
+        // A value the user changed wins; one saved at the built-in default doesn't
+        let user_path = dir.join("settings.json");
+        let user = Settings { osd_duration_ms: 2500, ..Settings::default() };
+        user.save(&user_path).unwrap();
+        let keys = user_keys(&user_path);
+        assert_eq!(keys, ["osd_duration_ms"]);
+        let merged = Settings::load(&user_path).with_system_defaults(&system, &keys);
+        assert_eq!(merged.osd_duration_ms, 2500);
+        assert_eq!(merged.toggle_cooldown_ms, 500);
+        assert_eq!(merged.enable_hotkey, "Ctrl+Alt+E");
+
+        // Saving keeps only what differs from system plus built-in defaults
+        let base = Settings::default().with_system_defaults(&system, &[]);
+        merged.save_over(&user_path, &base).unwrap();
+        let saved: Map<String, Value> = serde_json::from_str(&fs::read_to_string(&user_path).unwrap()).unwrap();
+        assert_eq!(saved.keys().collect::<Vec<_>>(), ["osd_duration_ms"]);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn cleared_webhook_url_disables_the_webhook() {
+        let mut settings: Settings = serde_json::from_value(json!({ "webhook_url": "" })).unwrap();
+        assert_eq!(settings.webhook_url, None);
+        assert!(settings.validate().is_ok());
+        let settings: Settings = serde_json::from_value(json!({ "webhook_url": "https://example.com/hook" })).unwrap();
+        assert_eq!(settings.webhook_url.as_deref(), Some("https://example.com/hook"));
+    }
+
+    #[test]
+    fn restore_file_needs_something_to_restore() {
+        let dir = std::env::temp_dir().join("enable-touchpad-restore-file");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let settings = dir.join("settings.json");
+        let last_good = dir.join("settings.lastgood.json");
+        let backup = dir.join("settings.json.bak");
+        fs::write(&settings, "{\"osd_duration_ms\": 1}").unwrap();
+
+        assert!(restore_file(&last_good, &settings).is_err());
+        assert!(!backup.exists());
+        assert_eq!(fs::read_to_string(&settings).unwrap(), "{\"osd_duration_ms\": 1}");
+
+        fs::write(&last_good, "{}").unwrap();
+        restore_file(&last_good, &settings).unwrap();
+        assert_eq!(fs::read_to_string(&settings).unwrap(), "{}");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "{\"osd_duration_ms\": 1}");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn corrupt_settings_are_restored_from_the_last_good_copy() {
+        let dir = std::env::temp_dir().join("enable-touchpad-load-or-restore");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let settings = dir.join("settings.json");
+        let last_good = dir.join("settings.lastgood.json");
+        let backup = dir.join("settings.json.bak");
+
+        // Nothing to restore from: the defaults, with the broken file left alone
+        fs::write(&settings, "{\"osd_duration_ms\": ").unwrap();
+        let loaded = Settings::load_or_restore(&settings, &last_good);
+        assert_eq!(loaded.osd_duration_ms, Settings::default().osd_duration_ms);
+        assert!(!backup.exists());
+
+        Settings { osd_duration_ms: 4321, ..Settings::default() }.save(&last_good).unwrap();
+        let loaded = Settings::load_or_restore(&settings, &last_good);
+        assert_eq!(loaded.osd_duration_ms, 4321);
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "{\"osd_duration_ms\": ");
+        assert_eq!(Settings::load(&settings).osd_duration_ms, 4321);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    fn layer(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    fn with_profile(overrides: Value) -> Settings {
+        let mut settings = Settings { osd_duration_ms: 1000, ..Settings::default() };
+        settings.profiles.insert("work".to_string(), layer(overrides));
+        settings.active_profile = Some("work".to_string());
+        settings
+    }
+
+    #[test]
+    fn active_profile_overrides_the_base_value() {
+        let settings = with_profile(json!({ "osd_duration_ms": 3000 }));
+        let effective = settings.effective(PowerSource::Ac, &Map::new()).unwrap();
+        assert_eq!(effective.osd_duration_ms, 3000);
+        // Keys the profile leaves alone keep the base value
+        assert_eq!(effective.toggle_cooldown_ms, settings.toggle_cooldown_ms);
+    }
+
+    #[test]
+    fn power_profile_and_live_overrides_win_over_the_profile() {
+        let mut settings = with_profile(json!({ "osd_duration_ms": 3000, "toggle_cooldown_ms": 500 }));
+        settings.power_profiles.insert("battery".to_string(), layer(json!({ "osd_duration_ms": 2000 })));
+        let live = layer(json!({ "toggle_cooldown_ms": 900 }));
+
+        let on_battery = settings.effective(PowerSource::Battery, &live).unwrap();
+        assert_eq!(on_battery.osd_duration_ms, 2000);
+        assert_eq!(on_battery.toggle_cooldown_ms, 900);
+
+        let on_ac = settings.effective(PowerSource::Ac, &Map::new()).unwrap();
+        assert_eq!(on_ac.osd_duration_ms, 3000);
+        assert_eq!(on_ac.toggle_cooldown_ms, 500);
+    }
+
+    #[test]
+    fn renaming_onto_an_existing_profile_is_refused() {
+        let mut settings = with_profile(json!({ "osd_duration_ms": 3000 }));
+        settings.create_profile("home").unwrap();
+
+        assert!(settings.rename_profile("work", " home ").is_err());
+        assert!(settings.rename_profile("nope", "other").is_err());
+        // Nothing moved
+        assert_eq!(settings.profiles.keys().collect::<Vec<_>>(), ["home", "work"]);
+        assert_eq!(settings.active_profile.as_deref(), Some("work"));
+
+        settings.rename_profile("work", "office").unwrap();
+        assert_eq!(settings.active_profile.as_deref(), Some("office"));
+        assert_eq!(settings.effective(PowerSource::Ac, &Map::new()).unwrap().osd_duration_ms, 3000);
+    }
+
+    #[test]
+    fn deleting_the_active_profile_falls_back_to_the_base_settings() {
+        let mut settings = with_profile(json!({ "osd_duration_ms": 3000 }));
+        settings.delete_profile("work").unwrap();
+        assert_eq!(settings.active_profile, None);
+        assert!(settings.profiles.is_empty());
+        assert_eq!(settings.effective(PowerSource::Ac, &Map::new()).unwrap().osd_duration_ms, 1000);
+        assert!(settings.delete_profile("work").is_err());
+    }
+
+    #[test]
+    fn single_field_update_is_validated() {
+        let settings = Settings::default();
+        let updated = settings.with_field("osd_duration_ms", json!(2500)).unwrap();
+        assert_eq!(updated.osd_duration_ms, 2500);
+        assert_eq!(updated.get_field("osd_duration_ms").unwrap(), json!(2500));
+        // Everything else is left alone
+        assert_eq!(updated.enable_hotkey, settings.enable_hotkey);
+    }
+
+    #[test]
+    fn invalid_single_field_update_is_rejected() {
+        let settings = Settings::default();
+        assert!(settings.with_field("no_such_setting", json!(1)).is_err());
+        assert!(settings.get_field("no_such_setting").is_err());
+        assert!(settings.with_field("osd_duration_ms", json!("long")).is_err());
+        // Hotkeys go through the shared validators, which reject duplicates
+        let duplicate = json!(settings.enable_hotkey.clone());
+        let error = settings.with_field("settings_hotkey", duplicate).unwrap_err();
+        assert!(error.contains("conflicts"), "{}", error);
+    }
+
+    #[test]
+    fn validate_rejects_an_invalid_profile_entry() {
+        let mut settings = with_profile(json!({ "no_such_setting": 1 }));
+        assert!(settings.validate().is_err());
+
+        let mut settings = with_profile(json!({ "schedules": [{ "start": "25:00", "end": "02:00" }] }));
+        let error = settings.validate().unwrap_err();
+        assert!(error.contains("work"), "{}", error);
+    }
+
+    #[test]
+    fn imported_values_are_clamped_including_profiles() {
+        // Parsed and clamped the way `import_settings` does
+        let contents = r#"{
+            "corner_toggle_dwell_ms": 0,
+            "osd_duration_ms": 1,
+            "profiles": { "work": { "osd_duration_ms": 99999 } }
+        }"#;
+        let mut settings: Settings = serde_json::from_str(contents).unwrap();
+        let warnings = settings.validate_and_clamp();
+
+        assert_eq!(settings.corner_toggle_dwell_ms, 1);
+        assert_eq!(settings.osd_duration_ms, 100);
+        assert_eq!(settings.profiles["work"]["osd_duration_ms"], json!(10_000));
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings.contains(&"profile work: osd_duration_ms 99999 adjusted to 10000".to_string()));
+        assert!(settings.validate().is_ok());
+        // Already in range, so clamping again changes nothing
+        assert!(settings.validate_and_clamp().is_empty());
+    }
+}
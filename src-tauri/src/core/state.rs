@@ -1,44 +1,723 @@
 use tauri::AppHandle;
-use std::sync::{Arc, Mutex};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::core::command_queue::ActionSource;
+use crate::core::hotkey_manager::HotkeyConflict;
+use crate::core::input_controller::{self, BackendProbeReport, ControllerError};
+use crate::core::power::{self, PowerSource};
+use crate::core::settings::{load_system_defaults, user_keys, restore_file, system_settings_path, Settings};
 
+static POISON_WARNED: AtomicBool = AtomicBool::new(false);
+static SAFE_MODE: AtomicBool = AtomicBool::new(false);
+static RESTORE_LAST_GOOD: AtomicBool = AtomicBool::new(false);
+
+/// Makes the next `AppState::new` swap in the last known good settings,
+/// for `--restore-last-good`.
+pub fn request_last_good_restore() {
+    RESTORE_LAST_GOOD.store(true, Ordering::SeqCst);
+}
+
+/// Whether safe mode is on: every automatic behavior stands down and only
+/// manual hotkey/tray control remains, for isolating a misbehaving feature.
+pub fn safe_mode() -> bool {
+    SAFE_MODE.load(Ordering::SeqCst)
+}
+
+pub fn set_safe_mode(enabled: bool) {
+    SAFE_MODE.store(enabled, Ordering::SeqCst);
+    if enabled {
+        log::warn!("SAFE MODE ACTIVE: auto-toggle, schedules and the startup state are off");
+    } else {
+        log::info!("Safe mode off, automatic behaviors resumed");
+    }
+}
+
+/// Locks `mutex`, recovering the guard if a panicking thread poisoned it.
+///
+/// The protected values are plain state that stays valid after a panic, so
+/// treating poisoning as fatal (or as "disabled") would only degrade the app.
+pub fn lock_recovering<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        if !POISON_WARNED.swap(true, Ordering::SeqCst) {
+            log::warn!("Recovered poisoned state lock after a panic");
+        }
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+const SETTINGS_FILE: &str = "settings.json";
+/// Settings that ran for `LAST_GOOD_GRACE` without being changed again.
+const LAST_GOOD_FILE: &str = "settings.lastgood.json";
+const STATE_FILE: &str = "state.json";
+const WINDOW_FILE: &str = "window.json";
+
+/// The `alert` set while a permission is missing, cleared by `check_permissions`.
+pub const PERMISSION_ALERT: &str = "Permission needed";
+
+/// A timer that will change the state or behavior by itself.
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[allow(dead_code)]
+pub enum PendingTimer {
+    /// A temporary enable reverting to disabled.
+    TemporaryEnable,
+    /// `auto_reenable_after_minutes` re-enabling the touchpad.
+    Safeguard,
+    /// A timed pause of auto-toggle ending.
+    Pause,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub enum TouchpadState {
     Enabled,
     Disabled,
 }
 
+/// The device state combined with tap-to-click, shown in the tray and OSD.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum PointerMode {
+    Enabled,
+    /// Cursor movement stays on but taps don't click.
+    TapsDisabled,
+    Disabled,
+}
+
+impl PointerMode {
+    pub fn from_parts(state: TouchpadState, tap_to_click: bool) -> Self {
+        match (state, tap_to_click) {
+            (TouchpadState::Disabled, _) => PointerMode::Disabled,
+            (TouchpadState::Enabled, false) => PointerMode::TapsDisabled,
+            (TouchpadState::Enabled, true) => PointerMode::Enabled,
+        }
+    }
+
+    /// The mode the taps-disabled toggle switches to: back to fully enabled
+    /// from taps-disabled, otherwise into taps-disabled, which enables a
+    /// disabled touchpad.
+    #[allow(dead_code)]
+    pub fn toggled_taps(self) -> Self {
+        match self {
+            PointerMode::TapsDisabled => PointerMode::Enabled,
+            PointerMode::Enabled | PointerMode::Disabled => PointerMode::TapsDisabled,
+        }
+    }
+}
+
+/// Last position and size of the settings window, in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
 #[allow(dead_code)]
 pub struct AppState {
     pub app_handle: AppHandle,
+    pub config_dir: Option<PathBuf>,
+    /// Admin-provided defaults from `system_settings_path`, under the user's settings.
+    pub system_defaults: Map<String, Value>,
     pub touchpad_state: Arc<Mutex<TouchpadState>>,
+    /// The state we last applied successfully. Unlike `touchpad_state`, this
+    /// doesn't follow changes made outside the app.
+    pub intended_state: Mutex<Option<TouchpadState>>,
+    pub settings: Arc<Mutex<Settings>>,
+    /// `get_settings` result, dropped whenever one of its inputs changes.
+    effective_settings: Mutex<Option<Settings>>,
+    /// Live overrides layered on top of the stored settings and active profile.
+    pub overrides: Mutex<Map<String, Value>>,
+    pub hotkeys_available: AtomicBool,
+    pub hotkey_conflicts: Mutex<Vec<HotkeyConflict>>,
+    /// Most recent controller failure, cleared by the next successful operation.
+    pub last_error: LastError,
+    pub history: StateHistory,
+    /// Selects the `power_profiles` entry layered into the effective settings.
+    pub power_source: Mutex<PowerSource>,
+    /// A persistent problem such as a lost device or missing permission,
+    /// flashed in the tray until it is resolved.
+    pub alert: Mutex<Option<String>>,
+    pub saved_cursor_position: Mutex<Option<(i32, i32)>>,
+    pub device_name: Mutex<String>,
+    pub tap_to_click: AtomicBool,
+    /// Central pause flag: while set, automatic toggling is suspended.
+    pub pause: PauseState,
+    /// Deadlines of the action worker's safeguard re-enable and temporary
+    /// enable revert, mirrored for display.
+    pub reenable_at: Mutex<Option<Instant>>,
+    pub revert_at: Mutex<Option<Instant>>,
+}
+
+/// The most recent controller failure and when it happened.
+#[derive(Default)]
+pub struct LastError(Mutex<Option<(Instant, ControllerError)>>);
+
+impl LastError {
+    pub fn get(&self) -> Option<(Instant, ControllerError)> {
+        lock_recovering(&self.0).clone()
+    }
+
+    pub fn record(&self, error: ControllerError) {
+        *lock_recovering(&self.0) = Some((Instant::now(), error));
+    }
+
+    pub fn clear(&self) {
+        *lock_recovering(&self.0) = None;
+    }
+}
+
+/// How many state changes `StateHistory` keeps.
+const HISTORY_LEN: usize = 100;
+
+/// One touchpad state change and what caused it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct StateChange {
+    /// Milliseconds since the Unix epoch.
+    pub at: u64,
+    pub state: TouchpadState,
+    pub source: ActionSource,
+}
+
+/// The latest state changes, including those made outside the app.
+#[derive(Default)]
+pub struct StateHistory(Mutex<VecDeque<StateChange>>);
+
+impl StateHistory {
+    pub fn record(&self, state: TouchpadState, source: ActionSource) {
+        let at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        let mut entries = lock_recovering(&self.0);
+        if entries.len() == HISTORY_LEN {
+            entries.pop_front();
+        }
+        entries.push_back(StateChange { at, state, source });
+    }
+
+    /// Oldest first.
+    pub fn entries(&self) -> Vec<StateChange> {
+        lock_recovering(&self.0).iter().copied().collect()
+    }
+}
+
+/// The pause flag and the deadline of a timed pause.
+#[derive(Default)]
+pub struct PauseState {
+    paused: AtomicBool,
+    until: Mutex<Option<Instant>>,
+    // Bumped on every pause change so a stale unpause timer does nothing
+    generation: AtomicU64,
+}
+
+impl PauseState {
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Sets the pause flag indefinitely, cancelling any scheduled unpause.
+    pub fn set_paused(&self, paused: bool) -> u64 {
+        self.paused.store(paused, Ordering::SeqCst);
+        *lock_recovering(&self.until) = None;
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Pauses until `duration` has elapsed. Returns the generation the
+    /// unpause timer must still match when it fires.
+    pub fn pause_for(&self, duration: Duration) -> Result<u64, String> {
+        let until = Instant::now()
+            .checked_add(duration)
+            .ok_or_else(|| format!("Pause of {:?} is too long", duration))?;
+        let generation = self.set_paused(true);
+        *lock_recovering(&self.until) = Some(until);
+        Ok(generation)
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        let until = (*lock_recovering(&self.until))?;
+        Some(until.saturating_duration_since(Instant::now()))
+    }
+
+    /// Clears a timed pause if it is still the one identified by `generation`.
+    pub fn expire(&self, generation: u64) -> bool {
+        if self.generation.load(Ordering::SeqCst) != generation {
+            return false;
+        }
+        self.set_paused(false);
+        true
+    }
+
+    /// Blocks until the timed pause `generation` ends, calling `on_tick` at
+    /// least every `tick` meanwhile, then clears it. `sleep` returns false
+    /// once the timer is stopped, which leaves the pause alone. Returns
+    /// whether it was still current, i.e. not cancelled, replaced or stopped.
+    pub fn run_timer(
+        &self,
+        generation: u64,
+        tick: Duration,
+        mut sleep: impl FnMut(Duration) -> bool,
+        mut on_tick: impl FnMut(),
+    ) -> bool {
+        while let Some(remaining) = self.remaining() {
+            if remaining.is_zero() {
+                break;
+            }
+            if !sleep(remaining.min(tick)) {
+                return false;
+            }
+            on_tick();
+        }
+        self.expire(generation)
+    }
 }
 
 impl AppState {
-    pub fn new(app_handle: &AppHandle) -> Self {
+    /// `config_dir` is `None` when it couldn't be created; settings then
+    /// stay in memory.
+    pub fn new(app_handle: &AppHandle, config_dir: Option<PathBuf>) -> Self {
+        if let Some(dir) = config_dir.as_ref().filter(|_| RESTORE_LAST_GOOD.load(Ordering::SeqCst)) {
+            match restore_file(&dir.join(LAST_GOOD_FILE), &dir.join(SETTINGS_FILE)) {
+                Ok(()) => log::warn!("Restored last known good settings"),
+                Err(e) => log::error!("Failed to restore last known good settings: {}", e),
+            }
+        }
+        let system_defaults = load_system_defaults(&system_settings_path());
+        let settings = config_dir
+            .as_ref()
+            .map(|dir| Settings::load_or_restore(&dir.join(SETTINGS_FILE), &dir.join(LAST_GOOD_FILE)))
+            .unwrap_or_default();
+        let user_keys = config_dir.as_ref().map(|dir| user_keys(&dir.join(SETTINGS_FILE))).unwrap_or_default();
+        let settings = settings.with_system_defaults(&system_defaults, &user_keys);
+
         Self {
             app_handle: app_handle.clone(),
+            config_dir,
+            system_defaults,
             touchpad_state: Arc::new(Mutex::new(TouchpadState::Disabled)),
+            intended_state: Mutex::new(None),
+            settings: Arc::new(Mutex::new(settings)),
+            effective_settings: Mutex::new(None),
+            overrides: Mutex::new(Map::new()),
+            hotkeys_available: AtomicBool::new(false),
+            hotkey_conflicts: Mutex::new(Vec::new()),
+            last_error: LastError::default(),
+            history: StateHistory::default(),
+            alert: Mutex::new(None),
+            power_source: Mutex::new(power::current()),
+            saved_cursor_position: Mutex::new(None),
+            device_name: Mutex::new(String::new()),
+            tap_to_click: AtomicBool::new(true),
+            pause: PauseState::default(),
+            reenable_at: Mutex::new(None),
+            revert_at: Mutex::new(None),
         }
     }
-    
+
     pub fn get_touchpad_state(&self) -> TouchpadState {
-        match self.touchpad_state.lock() {
-            Ok(guard) => *guard,
-            Err(_) => {
-                eprintln!("Failed to acquire touchpad state lock");
-                TouchpadState::Disabled
+        *lock_recovering(&self.touchpad_state)
+    }
+
+    pub fn set_touchpad_state(&self, state: TouchpadState) {
+        *lock_recovering(&self.touchpad_state) = state;
+    }
+
+    pub fn intended_state(&self) -> Option<TouchpadState> {
+        *lock_recovering(&self.intended_state)
+    }
+
+    pub fn set_intended_state(&self, state: TouchpadState) {
+        *lock_recovering(&self.intended_state) = Some(state);
+    }
+
+    /// The settings as stored in the config file.
+    pub fn get_stored_settings(&self) -> Settings {
+        lock_recovering(&self.settings).clone()
+    }
+
+    /// The effective settings driving behavior, see `Settings::effective`.
+    ///
+    /// Called from hot paths like the mouse-keys tick, so the merge is cached
+    /// until the stored settings, overrides or power source change.
+    pub fn get_settings(&self) -> Settings {
+        let mut cache = lock_recovering(&self.effective_settings);
+        if let Some(settings) = cache.as_ref() {
+            return settings.clone();
+        }
+        let stored = self.get_stored_settings();
+        let live = lock_recovering(&self.overrides).clone();
+        // Profiles and overrides were clamped when set, so this only merges
+        let settings = match stored.effective(self.power_source(), &live) {
+            Ok(settings) => settings,
+            Err(e) => {
+                log::error!("Failed to apply setting overrides: {}", e);
+                stored
             }
+        };
+        *cache = Some(settings.clone());
+        settings
+    }
+
+    fn invalidate_settings(&self) {
+        *lock_recovering(&self.effective_settings) = None;
+    }
+
+    /// Overrides one setting until cleared, clamped into range like a
+    /// saved value.
+    pub fn set_override(&self, key: &str, value: Value) {
+        let mut layer = Map::new();
+        layer.insert(key.to_string(), value);
+        for warning in self.get_stored_settings().clamp_layer(&mut layer) {
+            log::warn!("Setting override: {}", warning);
         }
+        lock_recovering(&self.overrides).extend(layer);
+        self.invalidate_settings();
     }
-    
-    pub fn set_touchpad_state(&self, state: TouchpadState) {
-        match self.touchpad_state.lock() {
-            Ok(mut guard) => *guard = state,
-            Err(_) => eprintln!("Failed to acquire touchpad state lock"),
+
+    pub fn clear_override(&self, key: &str) {
+        lock_recovering(&self.overrides).remove(key);
+        self.invalidate_settings();
+    }
+
+    /// Replaces one stored setting under the settings lock, so concurrent
+    /// single-field updates don't overwrite each other.
+    pub fn update_setting(&self, key: &str, value: Value) -> Result<(), String> {
+        let mut guard = lock_recovering(&self.settings);
+        *guard = guard.with_field(key, value)?;
+        self.invalidate_settings();
+        Ok(())
+    }
+
+    /// Changes the stored settings with `change` under the settings lock and
+    /// keeps the result only if it validates.
+    pub fn modify_settings<T>(&self, change: impl FnOnce(&mut Settings) -> Result<T, String>) -> Result<T, String> {
+        let mut guard = lock_recovering(&self.settings);
+        let mut updated = guard.clone();
+        let result = change(&mut updated)?;
+        updated.validate()?;
+        *guard = updated;
+        self.invalidate_settings();
+        Ok(result)
+    }
+
+    pub fn set_settings(&self, settings: Settings) {
+        *lock_recovering(&self.settings) = settings;
+        self.invalidate_settings();
+    }
+
+    /// Writes the current settings to the config file.
+    pub fn save_settings(&self) -> Result<(), String> {
+        let dir = self.config_dir.as_ref().ok_or("Config directory is unavailable")?;
+        // Only what the user changed, so defaults deployed later still apply
+        let base = Settings::default().with_system_defaults(&self.system_defaults, &[]);
+        self.get_stored_settings().save_over(&dir.join(SETTINGS_FILE), &base)
+    }
+
+    /// System defaults currently in effect, i.e. not overridden by the user.
+    pub fn settings_from_system(&self) -> Map<String, Value> {
+        let stored = self.get_stored_settings();
+        self.system_defaults
+            .iter()
+            .filter(|(key, value)| stored.get_field(key).ok().as_ref() == Some(*value))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Selects the backend to use and caches the choice in the settings.
+    /// Takes effect when the controller is created or the device re-detected.
+    pub fn select_backend(&self, full_probe: bool) -> BackendProbeReport {
+        let (selected, probes) = input_controller::select_backend(full_probe);
+        let mut settings = self.get_stored_settings();
+        match &selected {
+            Some(backend) if settings.probed_backend.as_ref().is_some_and(|cached| cached != backend) => {
+                log::info!("Selected the {} backend instead of the {} one", backend, settings.probed_backend.as_deref().unwrap_or_default())
+            }
+            Some(backend) => log::info!("Selected the {} backend", backend),
+            None => log::warn!("No backend could read the device state"),
         }
+        input_controller::set_preferred_backend(selected.clone());
+        if selected.is_some() && settings.probed_backend != selected {
+            settings.probed_backend = selected.clone();
+            self.set_settings(settings);
+            if let Err(e) = self.save_settings() {
+                log::warn!("Failed to cache the probed backend: {}", e);
+            }
+        }
+        BackendProbeReport { selected, probes }
+    }
+
+    /// Snapshots the stored settings as the ones to fall back to.
+    pub fn save_last_good_settings(&self) -> Result<(), String> {
+        let dir = self.config_dir.as_ref().ok_or("Config directory is unavailable")?;
+        self.get_stored_settings().save(&dir.join(LAST_GOOD_FILE))
+    }
+
+    /// Records `state` so it can be re-applied on the next start.
+    pub fn persist_touchpad_state(&self, state: TouchpadState) {
+        let Some(dir) = self.config_dir.as_ref() else {
+            return;
+        };
+        let result = fs::create_dir_all(dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| serde_json::to_string(&state).map_err(|e| e.to_string()))
+            .and_then(|contents| fs::write(dir.join(STATE_FILE), contents).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::warn!("Failed to persist touchpad state: {}", e);
+        }
+    }
+
+    pub fn load_persisted_state(&self) -> Option<TouchpadState> {
+        let contents = fs::read_to_string(self.config_dir.as_ref()?.join(STATE_FILE)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    pub fn persist_window_geometry(&self, geometry: WindowGeometry) {
+        let Some(dir) = self.config_dir.as_ref() else {
+            return;
+        };
+        let result = fs::create_dir_all(dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| serde_json::to_string(&geometry).map_err(|e| e.to_string()))
+            .and_then(|contents| fs::write(dir.join(WINDOW_FILE), contents).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::warn!("Failed to persist window geometry: {}", e);
+        }
+    }
+
+    pub fn load_window_geometry(&self) -> Option<WindowGeometry> {
+        let contents = fs::read_to_string(self.config_dir.as_ref()?.join(WINDOW_FILE)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    pub fn save_cursor_position(&self, position: Option<(i32, i32)>) {
+        *lock_recovering(&self.saved_cursor_position) = position;
+    }
+
+    pub fn take_cursor_position(&self) -> Option<(i32, i32)> {
+        lock_recovering(&self.saved_cursor_position).take()
+    }
+
+    pub fn get_device_name(&self) -> String {
+        lock_recovering(&self.device_name).clone()
+    }
+
+    pub fn set_device_name(&self, name: String) {
+        *lock_recovering(&self.device_name) = name;
+    }
+
+    pub fn tap_to_click(&self) -> bool {
+        self.tap_to_click.load(Ordering::SeqCst)
+    }
+
+    pub fn set_tap_to_click(&self, enabled: bool) {
+        self.tap_to_click.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn pointer_mode(&self) -> PointerMode {
+        PointerMode::from_parts(self.get_touchpad_state(), self.tap_to_click())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause.is_paused()
+    }
+
+    pub fn set_paused(&self, paused: bool) -> u64 {
+        self.pause.set_paused(paused)
+    }
+
+    pub fn pause_remaining(&self) -> Option<Duration> {
+        self.pause.remaining()
+    }
+
+    pub fn set_timer_deadlines(&self, reenable_at: Option<Instant>, revert_at: Option<Instant>) {
+        *lock_recovering(&self.reenable_at) = reenable_at;
+        *lock_recovering(&self.revert_at) = revert_at;
+    }
+
+    /// The running timer due soonest and the time left on it.
+    pub fn next_timer(&self) -> Option<(PendingTimer, Duration)> {
+        let now = Instant::now();
+        [
+            (PendingTimer::TemporaryEnable, *lock_recovering(&self.revert_at)),
+            (PendingTimer::Safeguard, *lock_recovering(&self.reenable_at)),
+            (PendingTimer::Pause, *lock_recovering(&self.pause.until)),
+        ]
+        .into_iter()
+        .filter_map(|(timer, at)| Some((timer, at?.saturating_duration_since(now))))
+        .min_by_key(|(_, remaining)| *remaining)
+    }
+
+    pub fn hotkey_conflicts(&self) -> Vec<HotkeyConflict> {
+        lock_recovering(&self.hotkey_conflicts).clone()
+    }
+
+    pub fn set_hotkey_conflicts(&self, conflicts: Vec<HotkeyConflict>) {
+        *lock_recovering(&self.hotkey_conflicts) = conflicts;
+    }
+
+    pub fn last_error(&self) -> Option<(Instant, ControllerError)> {
+        self.last_error.get()
+    }
+
+    pub fn set_last_error(&self, error: ControllerError) {
+        self.last_error.record(error);
+    }
+
+    pub fn clear_last_error(&self) {
+        self.last_error.clear();
+    }
+
+    pub fn power_source(&self) -> PowerSource {
+        *lock_recovering(&self.power_source)
+    }
+
+    pub fn set_power_source(&self, source: PowerSource) {
+        *lock_recovering(&self.power_source) = source;
+        self.invalidate_settings();
+    }
+
+    pub fn alert(&self) -> Option<String> {
+        lock_recovering(&self.alert).clone()
+    }
+
+    pub fn set_alert(&self, reason: String) {
+        *lock_recovering(&self.alert) = Some(reason);
+    }
+
+    pub fn clear_alert(&self) {
+        *lock_recovering(&self.alert) = None;
+    }
+
+    pub fn hotkeys_available(&self) -> bool {
+        self.hotkeys_available.load(Ordering::SeqCst)
+    }
+
+    pub fn set_hotkeys_available(&self, available: bool) {
+        self.hotkeys_available.store(available, Ordering::SeqCst);
     }
 }
 
 #[allow(dead_code)]
-pub type SharedState = Arc<AppState>;
\ No newline at end of file
+pub type SharedState = Arc<AppState>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poisoned_lock_keeps_the_last_value() {
+        let state = Arc::new(Mutex::new(TouchpadState::Enabled));
+        let poisoner = Arc::clone(&state);
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("poison the state lock");
+        })
+        .join();
+        assert!(state.is_poisoned());
+
+        assert_eq!(*lock_recovering(&state), TouchpadState::Enabled);
+        *lock_recovering(&state) = TouchpadState::Disabled;
+        assert!(!state.is_poisoned());
+        assert_eq!(*lock_recovering(&state), TouchpadState::Disabled);
+    }
+
+    #[test]
+    fn taps_disabled_is_its_own_mode() {
+        use PointerMode::*;
+        assert_eq!(PointerMode::from_parts(TouchpadState::Enabled, true), Enabled);
+        assert_eq!(PointerMode::from_parts(TouchpadState::Enabled, false), TapsDisabled);
+        // Tap-to-click doesn't matter while the device is off
+        assert_eq!(PointerMode::from_parts(TouchpadState::Disabled, false), Disabled);
+        assert_eq!(PointerMode::from_parts(TouchpadState::Disabled, true), Disabled);
+
+        assert_eq!(Enabled.toggled_taps(), TapsDisabled);
+        assert_eq!(Disabled.toggled_taps(), TapsDisabled);
+        assert_eq!(TapsDisabled.toggled_taps(), Enabled);
+        assert_eq!(Enabled.toggled_taps().toggled_taps(), Enabled);
+    }
+
+    // Fails until `fixed` is set, like a backend whose device went missing
+    struct MockController {
+        fixed: AtomicBool,
+    }
+
+    impl MockController {
+        fn enable(&self) -> Result<(), ControllerError> {
+            if self.fixed.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err(ControllerError::LinuxDeviceNotFound)
+            }
+        }
+    }
+
+    // What the hotkey manager does with every controller result
+    fn apply(last_error: &LastError, controller: &MockController) {
+        match controller.enable() {
+            Ok(()) => last_error.clear(),
+            Err(e) => last_error.record(e),
+        }
+    }
+
+    #[test]
+    fn failure_is_recorded_and_success_clears_it() {
+        let last_error = LastError::default();
+        let controller = MockController { fixed: AtomicBool::new(false) };
+
+        apply(&last_error, &controller);
+        let (at, error) = last_error.get().expect("failure should be recorded");
+        assert!(matches!(error, ControllerError::LinuxDeviceNotFound));
+        assert!(at <= Instant::now());
+
+        controller.fixed.store(true, Ordering::SeqCst);
+        apply(&last_error, &controller);
+        assert!(last_error.get().is_none());
+    }
+
+    fn sleep(duration: Duration) -> bool {
+        std::thread::sleep(duration);
+        true
+    }
+
+    #[test]
+    fn timed_pause_clears_itself() {
+        let pause = PauseState::default();
+        let generation = pause.pause_for(Duration::from_millis(50)).unwrap();
+        assert!(pause.is_paused());
+
+        let mut ticks = 0;
+        assert!(pause.run_timer(generation, Duration::from_millis(20), sleep, || ticks += 1));
+        assert!(!pause.is_paused());
+        assert!(ticks >= 2);
+        assert_eq!(pause.remaining(), None);
+    }
+
+    #[test]
+    fn manual_unpause_cancels_the_timer() {
+        let pause = PauseState::default();
+        let generation = pause.pause_for(Duration::from_millis(10)).unwrap();
+        pause.set_paused(false);
+        pause.set_paused(true);
+        assert!(!pause.run_timer(generation, Duration::from_millis(10), sleep, || {}));
+        assert!(pause.is_paused());
+    }
+
+    #[test]
+    fn stopped_timer_leaves_the_pause_alone() {
+        let pause = PauseState::default();
+        let generation = pause.pause_for(Duration::from_secs(60)).unwrap();
+        assert!(!pause.run_timer(generation, Duration::from_millis(10), |_| false, || {}));
+        assert!(pause.is_paused());
+        assert!(pause.remaining().is_some());
+    }
+
+    #[test]
+    fn overlong_pause_is_rejected() {
+        let pause = PauseState::default();
+        assert!(pause.pause_for(Duration::MAX).is_err());
+        assert!(!pause.is_paused());
+    }
+}
@@ -0,0 +1,24 @@
+use crate::core::state::TouchpadState;
+
+/// Template used when `--status-format` is given without one.
+pub const DEFAULT_TEMPLATE: &str = "TP:{state}";
+
+/// Expands a status bar template.
+///
+/// Placeholders:
+/// - `{state}`: `on`, `off` or `unknown` if the state couldn't be read
+/// - `{device}`: name of the managed touchpad
+/// - `{paused}`: `paused` while auto-toggle is paused, empty otherwise
+///
+/// Unknown placeholders are left as-is.
+pub fn format_status(template: &str, state: Option<TouchpadState>, device: &str, paused: bool) -> String {
+    let state = match state {
+        Some(TouchpadState::Enabled) => "on",
+        Some(TouchpadState::Disabled) => "off",
+        None => "unknown",
+    };
+    template
+        .replace("{state}", state)
+        .replace("{device}", device)
+        .replace("{paused}", if paused { "paused" } else { "" })
+}
@@ -0,0 +1,131 @@
+//! Tracks long-running background threads so they can be stopped and joined
+//! on exit or when their feature is turned off.
+
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use crossbeam::channel::{bounded, Receiver, RecvTimeoutError, Sender};
+use once_cell::sync::Lazy;
+use log::{info, warn};
+use crate::core::state::lock_recovering;
+
+struct Worker {
+    name: String,
+    // Dropping the sender disconnects the worker's token
+    stop: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+static WORKERS: Lazy<Mutex<Vec<Worker>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Handed to each worker; tells it when to stop.
+#[derive(Clone)]
+pub struct StopToken(Receiver<()>);
+
+impl StopToken {
+    pub fn is_stopped(&self) -> bool {
+        !matches!(self.0.try_recv(), Err(crossbeam::channel::TryRecvError::Empty))
+    }
+
+    /// Sleeps for `duration` unless stopped first. Returns false once stopped.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        matches!(self.0.recv_timeout(duration), Err(RecvTimeoutError::Timeout))
+    }
+
+    /// Becomes ready when the worker should stop, for use with `select!`.
+    pub fn channel(&self) -> &Receiver<()> {
+        &self.0
+    }
+}
+
+/// Spawns `work` on a named thread tracked by the supervisor.
+pub fn spawn(name: &str, work: impl FnOnce(StopToken) + Send + 'static) {
+    let (stop, token) = bounded(0);
+    let handle = match std::thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || work(StopToken(token)))
+    {
+        Ok(handle) => handle,
+        Err(e) => {
+            warn!("Failed to start {} thread: {}", name, e);
+            return;
+        }
+    };
+    lock_recovering(&WORKERS).push(Worker { name: name.to_string(), stop, handle });
+}
+
+/// Stops every worker named `name` and waits for it to finish.
+pub fn stop(name: &str) {
+    let stopped: Vec<Worker> = {
+        let mut workers = lock_recovering(&WORKERS);
+        let (stopped, running) = workers.drain(..).partition(|worker| worker.name == name);
+        *workers = running;
+        stopped
+    };
+    join(stopped);
+}
+
+/// Stops and joins all workers, e.g. on exit.
+pub fn stop_all() {
+    let stopped: Vec<Worker> = lock_recovering(&WORKERS).drain(..).collect();
+    join(stopped);
+}
+
+fn join(workers: Vec<Worker>) {
+    for Worker { name, stop, handle } in workers {
+        drop(stop);
+        if handle.join().is_err() {
+            warn!("{} thread panicked", name);
+        } else {
+            info!("Stopped {} thread", name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    fn running(name: &str) -> usize {
+        lock_recovering(&WORKERS).iter().filter(|worker| worker.name == name).count()
+    }
+
+    #[test]
+    fn stopping_a_worker_ends_its_thread() {
+        let finished = Arc::new(AtomicBool::new(false));
+        let (started_tx, started) = bounded(1);
+        let flag = finished.clone();
+        spawn("test-sleeper", move |token| {
+            let _ = started_tx.send(());
+            while token.sleep(Duration::from_secs(60)) {}
+            flag.store(true, Ordering::SeqCst);
+        });
+        started.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(running("test-sleeper"), 1);
+
+        // Returns only after the thread has been joined
+        stop("test-sleeper");
+        assert!(finished.load(Ordering::SeqCst));
+        assert_eq!(running("test-sleeper"), 0);
+    }
+
+    #[test]
+    fn stop_leaves_other_workers_running() {
+        let (_events, idle) = bounded::<()>(0);
+        spawn("test-selecting", move |token| {
+            crossbeam::channel::select! {
+                recv(token.channel()) -> _ => {}
+                recv(idle) -> _ => {}
+            }
+            assert!(token.is_stopped());
+        });
+        spawn("test-other", |token| while token.sleep(Duration::from_secs(60)) {});
+
+        stop("test-selecting");
+        assert_eq!(running("test-selecting"), 0);
+        assert_eq!(running("test-other"), 1);
+        stop("test-other");
+    }
+}
@@ -0,0 +1,85 @@
+//! Best-effort detection of the desktop's light or dark theme, so monochrome
+//! tray icons stay visible on the panel.
+//!
+//! Every probe returns `None` when the theme can't be determined.
+
+use std::process::Command;
+use crate::core::input_controller::{command_timeout, run_checked};
+#[cfg(any(target_os = "windows", test))]
+use crate::core::input_controller::parse_reg_dword;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PanelTheme {
+    Light,
+    Dark,
+}
+
+#[cfg(target_os = "windows")]
+pub fn panel_theme() -> Option<PanelTheme> {
+    const KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize";
+    // The taskbar follows the system theme, not the apps theme
+    let output = run_checked(
+        Command::new("reg").args(["query", KEY, "/v", "SystemUsesLightTheme"]),
+        command_timeout(),
+    )
+    .ok()?;
+    let light = parse_reg_dword(&output, "SystemUsesLightTheme")?;
+    Some(if light == 0 { PanelTheme::Dark } else { PanelTheme::Light })
+}
+
+#[cfg(target_os = "macos")]
+pub fn panel_theme() -> Option<PanelTheme> {
+    // The key only exists in dark mode, so a failed read means light
+    let dark = run_checked(
+        Command::new("defaults").args(["read", "-g", "AppleInterfaceStyle"]),
+        command_timeout(),
+    )
+    .is_ok_and(|style| style.trim() == "Dark");
+    Some(if dark { PanelTheme::Dark } else { PanelTheme::Light })
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn panel_theme() -> Option<PanelTheme> {
+    let gsettings = |key: &str| {
+        run_checked(
+            Command::new("gsettings").args(["get", "org.gnome.desktop.interface", key]),
+            command_timeout(),
+        )
+        .ok()
+    };
+    let gtk_theme = std::env::var("GTK_THEME").ok().or_else(|| gsettings("gtk-theme"));
+    theme_from_gnome(gsettings("color-scheme").as_deref(), gtk_theme.as_deref())
+}
+
+/// GNOME 42+ sets `color-scheme`; older desktops and many others only
+/// name the GTK theme, where dark variants carry "dark" in the name.
+#[cfg(any(not(any(target_os = "windows", target_os = "macos")), test))]
+fn theme_from_gnome(color_scheme: Option<&str>, gtk_theme: Option<&str>) -> Option<PanelTheme> {
+    match color_scheme.map(|scheme| scheme.trim().trim_matches('\'')) {
+        Some("prefer-dark") => return Some(PanelTheme::Dark),
+        Some("prefer-light") => return Some(PanelTheme::Light),
+        _ => {}
+    }
+    let gtk_theme = gtk_theme?.trim().trim_matches('\'').to_lowercase();
+    Some(if gtk_theme.contains("dark") { PanelTheme::Dark } else { PanelTheme::Light })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_windows_theme_value() {
+        let output = "\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize\r\n    SystemUsesLightTheme    REG_DWORD    0x0\r\n";
+        assert_eq!(parse_reg_dword(output, "SystemUsesLightTheme"), Some(0));
+        assert_eq!(parse_reg_dword(output, "AppsUseLightTheme"), None);
+    }
+
+    #[test]
+    fn color_scheme_wins_over_the_gtk_theme() {
+        assert_eq!(theme_from_gnome(Some("'prefer-dark'\n"), Some("'Adwaita'")), Some(PanelTheme::Dark));
+        assert_eq!(theme_from_gnome(Some("'default'"), Some("'Adwaita-dark'")), Some(PanelTheme::Dark));
+        assert_eq!(theme_from_gnome(Some("'default'"), Some("'Yaru'")), Some(PanelTheme::Light));
+        assert_eq!(theme_from_gnome(None, None), None);
+    }
+}
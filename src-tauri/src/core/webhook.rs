@@ -0,0 +1,67 @@
+//! Opt-in HTTP notification of state changes, for home automation or
+//! logging services.
+
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use log::{debug, warn};
+use crate::core::command_queue::ActionSource;
+use crate::core::state::TouchpadState;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Serialize)]
+struct StateChange {
+    state: TouchpadState,
+    source: ActionSource,
+    /// Milliseconds since the Unix epoch.
+    timestamp: u64,
+}
+
+/// Accepts absolute http(s) URLs with a host.
+pub fn validate_url(url: &str) -> Result<(), String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .ok_or_else(|| format!("webhook_url must start with http:// or https://, got {}", url))?;
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return Err(format!("webhook_url has no valid host: {}", url));
+    }
+    Ok(())
+}
+
+/// Keeps only the scheme and host, since tokens usually live in the path
+/// or query.
+pub fn redact_url(url: &str) -> String {
+    match url.split_once("://") {
+        Some((scheme, rest)) => {
+            // Credentials may also sit before the host
+            let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+            let host = authority.rsplit('@').next().unwrap_or_default();
+            format!("{}://{}/<redacted>", scheme, host)
+        }
+        None => "<redacted>".to_string(),
+    }
+}
+
+/// POSTs the change on a background thread so the toggle path never waits.
+/// Failures are logged and not retried.
+pub fn notify_state_change(url: &str, state: TouchpadState, source: ActionSource) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let payload = StateChange { state, source, timestamp };
+    let url = url.to_string();
+    let spawned = thread::Builder::new().name("webhook".to_string()).spawn(move || {
+        let result = ureq::post(&url).timeout(WEBHOOK_TIMEOUT).send_json(&payload);
+        match result {
+            Ok(response) => debug!("Webhook {} returned {}", url, response.status()),
+            Err(e) => warn!("Webhook {} failed: {}", url, e),
+        }
+    });
+    if let Err(e) = spawned {
+        warn!("Failed to start webhook thread: {}", e);
+    }
+}
@@ -0,0 +1,70 @@
+//! Coalesces bursts of frontend events so a flapping device can't flood the
+//! UI. Only emission is throttled; the core logic, logs and history still see
+//! every change.
+
+use tauri::{AppHandle, Emitter};
+use serde::Serialize;
+use std::time::Duration;
+use crossbeam::channel::{unbounded, Sender};
+use log::warn;
+
+/// Emits at most once per `interval`, always with the latest value. One
+/// worker thread serves every burst and exits when the emitter is dropped.
+#[allow(dead_code)]
+pub struct ThrottledEmitter<T> {
+    sender: Sender<T>,
+}
+
+impl<T: Serialize + Clone + Send + 'static> ThrottledEmitter<T> {
+    /// Throttles the frontend event `event`.
+    pub fn new(app: AppHandle, event: &'static str, interval: Duration) -> Self {
+        Self::with_sink(interval, move |value| {
+            if let Err(e) = app.emit(event, value) {
+                warn!("Failed to emit {}: {}", event, e);
+            }
+        })
+    }
+
+    /// Throttles calls to `sink`. The first value of a burst starts the
+    /// interval; when it ends `sink` gets the latest value.
+    pub fn with_sink(interval: Duration, sink: impl Fn(T) + Send + 'static) -> Self {
+        let (sender, receiver) = unbounded::<T>();
+        std::thread::spawn(move || {
+            while let Ok(first) = receiver.recv() {
+                std::thread::sleep(interval);
+                let latest = receiver.try_iter().last().unwrap_or(first);
+                sink(latest);
+            }
+        });
+        Self { sender }
+    }
+
+    /// Queues `value`, replacing any value not yet emitted.
+    pub fn emit(&self, value: T) {
+        // Only fails once the worker is gone, which never happens first
+        let _ = self.sender.send(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam::channel::RecvTimeoutError;
+
+    #[test]
+    fn burst_yields_one_emit_with_the_final_value() {
+        let (emitted, received) = unbounded();
+        let emitter = ThrottledEmitter::with_sink(Duration::from_millis(100), move |value: u32| {
+            let _ = emitted.send(value);
+        });
+        for value in 0..50 {
+            emitter.emit(value);
+        }
+        assert_eq!(received.recv_timeout(Duration::from_secs(2)), Ok(49));
+        assert_eq!(received.recv_timeout(Duration::from_millis(300)), Err(RecvTimeoutError::Timeout));
+
+        // The same worker serves the next burst
+        emitter.emit(7);
+        assert_eq!(received.recv_timeout(Duration::from_secs(2)), Ok(7));
+    }
+}
@@ -0,0 +1,203 @@
+use tauri::{image::Image, path::BaseDirectory, AppHandle, Manager};
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use crate::core::settings::TrayIconStyle;
+use crate::core::state::TouchpadState;
+use crate::core::theme::{panel_theme, PanelTheme};
+
+// Custom icons dropped here (same file names, without `icons/`) take
+// precedence over the bundled ones
+pub const CUSTOM_ASSET_DIR: &str = "assets";
+
+// Optional per-state tray icons, looked up in the resource dir
+const ENABLED_ICON: &str = "icons/tray-enabled.png";
+const DISABLED_ICON: &str = "icons/tray-disabled.png";
+const MONO_ICON: &str = "icons/tray-mono.png";
+const MONO_ENABLED_ICON: &str = "icons/tray-mono-enabled.png";
+const MONO_DISABLED_ICON: &str = "icons/tray-mono-disabled.png";
+// Flashed while something needs attention; without it the icon blinks off
+const ALERT_ICON: &str = "icons/tray-alert.png";
+const MONO_ALERT_ICON: &str = "icons/tray-mono-alert.png";
+
+/// Tray icons resolved once at startup.
+///
+/// Each state icon falls back to the default window icon, and that to an
+/// embedded transparent 1x1 image, so lookups can never come up empty.
+pub struct IconSet {
+    enabled: Image<'static>,
+    disabled: Image<'static>,
+    default: Image<'static>,
+    alert: Image<'static>,
+    /// Monochrome icons are macOS template images that follow the menu bar theme.
+    pub template: bool,
+}
+
+impl IconSet {
+    pub fn load(app: &AppHandle, style: TrayIconStyle) -> Self {
+        let theme = panel_theme();
+        let default = app.default_window_icon().cloned().map(Image::to_owned);
+        if !style.is_monochrome(theme) {
+            let mut icons = Self::from_parts(
+                default,
+                load_resource(app, ENABLED_ICON),
+                load_resource(app, DISABLED_ICON),
+            );
+            if let Some(alert) = load_resource(app, ALERT_ICON) {
+                icons.alert = alert;
+            }
+            return icons;
+        }
+
+        let Some(mono) = load_resource(app, MONO_ICON) else {
+            warn!("No monochrome tray icon bundled, using the color icons");
+            return Self::load(app, TrayIconStyle::Color);
+        };
+        // The dark glyphs vanish on a dark panel; macOS inverts template
+        // images itself
+        let shade = |image: Image<'static>| {
+            if cfg!(not(target_os = "macos")) && theme == Some(PanelTheme::Dark) {
+                light_glyph(&image)
+            } else {
+                image
+            }
+        };
+        let mut icons = Self::from_parts(
+            Some(shade(mono)),
+            load_resource(app, MONO_ENABLED_ICON).map(shade),
+            load_resource(app, MONO_DISABLED_ICON).map(shade),
+        );
+        if let Some(alert) = load_resource(app, MONO_ALERT_ICON) {
+            icons.alert = shade(alert);
+        }
+        icons.template = cfg!(target_os = "macos");
+        icons
+    }
+
+    pub fn from_parts(
+        default: Option<Image<'static>>,
+        enabled: Option<Image<'static>>,
+        disabled: Option<Image<'static>>,
+    ) -> Self {
+        let default = default.unwrap_or_else(|| {
+            warn!("No default window icon, using an empty tray icon");
+            empty_icon()
+        });
+        let enabled = enabled.unwrap_or_else(|| {
+            info!("No enabled tray icon, using the default icon");
+            default.clone()
+        });
+        let disabled = disabled.unwrap_or_else(|| {
+            info!("No disabled tray icon, using the default icon");
+            default.clone()
+        });
+        Self { enabled, disabled, default, alert: empty_icon(), template: false }
+    }
+
+    pub fn default_icon(&self) -> Image<'static> {
+        self.default.clone()
+    }
+
+    pub fn alert_icon(&self) -> Image<'static> {
+        self.alert.clone()
+    }
+
+    pub fn for_state(&self, state: TouchpadState) -> Image<'static> {
+        match state {
+            TouchpadState::Enabled => self.enabled.clone(),
+            TouchpadState::Disabled => self.disabled.clone(),
+        }
+    }
+}
+
+/// `image` with every pixel turned white, keeping its transparency.
+fn light_glyph(image: &Image<'_>) -> Image<'static> {
+    let mut rgba = image.rgba().to_vec();
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel[..3].fill(255);
+    }
+    Image::new_owned(rgba, image.width(), image.height())
+}
+
+fn empty_icon() -> Image<'static> {
+    Image::new_owned(vec![0; 4], 1, 1)
+}
+
+/// The folder in the config dir holding user-supplied assets.
+pub fn custom_asset_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join(CUSTOM_ASSET_DIR))
+}
+
+/// Whether `path` decodes as an icon, so a broken file can be rejected
+/// before it replaces working icons.
+pub fn validate_icon(path: &Path) -> Result<(), String> {
+    Image::from_path(path).map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn load_custom(app: &AppHandle, path: &str) -> Option<Image<'static>> {
+    let file_name = Path::new(path).file_name()?;
+    let custom = custom_asset_dir(app)?.join(file_name);
+    if !custom.is_file() {
+        return None;
+    }
+    match Image::from_path(&custom) {
+        Ok(image) => Some(image),
+        Err(e) => {
+            warn!("Failed to load custom tray icon {}: {}", custom.display(), e);
+            None
+        }
+    }
+}
+
+fn load_resource(app: &AppHandle, path: &str) -> Option<Image<'static>> {
+    if let Some(image) = load_custom(app, path) {
+        return Some(image);
+    }
+    let resolved = app.path().resolve(path, BaseDirectory::Resource).ok()?;
+    if !resolved.is_file() {
+        return None;
+    }
+    match Image::from_path(&resolved) {
+        Ok(image) => Some(image),
+        Err(e) => {
+            warn!("Failed to load tray icon {}: {}", resolved.display(), e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_assets_still_yield_usable_icons() {
+        let icons = IconSet::from_parts(None, None, None);
+        for icon in [icons.default_icon(), icons.for_state(TouchpadState::Enabled), icons.for_state(TouchpadState::Disabled)] {
+            assert_eq!((icon.width(), icon.height()), (1, 1));
+            assert_eq!(icon.rgba().len(), 4);
+        }
+    }
+
+    #[test]
+    fn missing_state_icons_fall_back_to_the_default() {
+        let default = Image::new_owned(vec![255; 16], 2, 2);
+        let disabled = Image::new_owned(vec![0; 16], 2, 2);
+        let icons = IconSet::from_parts(Some(default.clone()), None, Some(disabled.clone()));
+        assert_eq!(icons.for_state(TouchpadState::Enabled).rgba(), default.rgba());
+        assert_eq!(icons.for_state(TouchpadState::Disabled).rgba(), disabled.rgba());
+    }
+
+    #[test]
+    fn light_glyph_keeps_the_shape() {
+        let glyph = Image::new_owned(vec![0, 0, 0, 255, 0, 0, 0, 0], 2, 1);
+        assert_eq!(light_glyph(&glyph).rgba(), &[255, 255, 255, 255, 255, 255, 255, 0]);
+    }
+
+    #[test]
+    fn bundled_state_icons_decode() {
+        for name in [ENABLED_ICON, DISABLED_ICON, MONO_ICON, MONO_ENABLED_ICON, MONO_DISABLED_ICON] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(name);
+            validate_icon(&path).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        }
+    }
+}
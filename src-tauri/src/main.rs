@@ -5,56 +5,167 @@ mod core;
 mod tray;
 mod commands;
 mod osd;
+mod window;
+mod sound;
+mod pause;
+mod icons;
+mod asset_watcher;
+mod app_dirs;
+mod event_throttle;
 
-use tauri::{Manager};
-use core::state::{AppState, SharedState};
-use core::input_controller::PlatformTouchpadController;
+use tauri::{Emitter, Manager, RunEvent};
+use core::state::{AppState, SharedState, TouchpadState, PERMISSION_ALERT};
+use core::input_controller::{display_available, set_allow_disable_without_mouse, set_applescript_retries, set_command_timeout, set_evdev_backend, set_macos_state_source, ControllerError, PlatformTouchpadController, TouchpadController};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 use core::hotkey_manager::{HotkeyManager, HotkeyEvent};
 use core::mouse_emulator::MouseEmulator;
+use core::mouse_keys::start_mouse_keys;
+use core::error_report;
+use core::led_indicator;
+use core::supervisor;
+use core::command_queue::start_action_worker;
+use core::mouse_watcher::{start_mouse_watcher, stop_mouse_watcher};
+use core::settings::PermissionPromptMode;
+use window::{setup_settings_window, show_settings_window};
+use sound::Sound;
 use osd::OSDManager;
-use tray::setup_tray;
+use asset_watcher::start_asset_watcher;
+use event_throttle::ThrottledEmitter;
+use tray::{setup_tray, show_device_lost, start_alert_flash, start_countdown_refresh, update_tray_icon, update_tray_tooltip};
 use log::{info, error, warn};
-use crossbeam::channel::{unbounded, Receiver};
+use crossbeam::channel::{select, unbounded, Receiver, Sender};
 use std::sync::Arc;
+use std::time::Duration;
+
+// How long settings must stay in use unchanged to count as known good
+const LAST_GOOD_GRACE: Duration = Duration::from_secs(5 * 60);
 
 fn main() {
-    if let Err(e) = simple_logger::init_with_level(log::Level::Info) {
+    if let Err(e) = core::logging::init(log::LevelFilter::Info) {
         eprintln!("Failed to initialize logger: {}", e);
         std::process::exit(1);
     }
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--safe-mode") {
+        args.retain(|arg| arg != "--safe-mode");
+        core::state::set_safe_mode(true);
+    }
+    if args.iter().any(|arg| arg == "--restore-last-good") {
+        args.retain(|arg| arg != "--restore-last-good");
+        core::state::request_last_good_restore();
+    }
+    if let Some(index) = args.iter().position(|arg| arg == "--status-format") {
+        let template = args.get(index + 1).map_or(core::status_line::DEFAULT_TEMPLATE, String::as_str);
+        std::process::exit(print_status_line(template));
+    }
+
     info!("Starting Touchpad Control");
 
-    if let Err(e) = tauri::Builder::default()
+    if !display_available() {
+        warn!("No display available, running without tray");
+        std::process::exit(run_headless(args));
+    }
+
+    let app = match tauri::Builder::default()
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
             let app_handle = app.handle().clone();
 
+            // Create the directories before anything writes under them
+            let dirs = app_dirs::init(&app_handle);
+            app.manage(dirs.clone());
+
             // Initialize shared state
-            let state = Arc::new(AppState::new(&app_handle));
+            let state = Arc::new(AppState::new(&app_handle, dirs.config.clone()));
             app.manage(state.clone());
+            set_command_timeout(Duration::from_millis(state.get_settings().command_timeout_ms));
+            core::logging::set_log_format(state.get_settings().log_format);
+            set_allow_disable_without_mouse(state.get_settings().allow_disable_without_mouse);
+            set_evdev_backend(state.get_settings().evdev_backend);
+            set_macos_state_source(state.get_settings().macos_state_source);
+            set_applescript_retries(state.get_settings().applescript_retries);
+            core::mouse_detector::set_ignored_devices(state.get_settings().ignored_mouse_devices);
+            state.select_backend(false);
+
+            // Local-only error report, see core::error_report
+            if let Some(dir) = dirs.data.clone() {
+                error_report::init(dir);
+            }
 
             // Initialize services
             let touchpad_controller = match PlatformTouchpadController::new() {
                 Ok(controller) => controller,
                 Err(e) => {
-                    error!("Failed to initialize touchpad controller: {:?}", e);
+                    // Display form, so e.g. a missing xinput comes with its install hint
+                    error!("Failed to initialize touchpad controller: {}", e);
+                    error_report::record_controller_error(&e);
+                    if let ControllerError::BackendToolMissing(_) = e {
+                        // Launched from a menu nobody sees the log, so say
+                        // what to install before quitting
+                        let handle = app_handle.clone();
+                        app_handle
+                            .dialog()
+                            .message(e.to_string())
+                            .title("Enable Touchpad can't start")
+                            .kind(MessageDialogKind::Error)
+                            .show(move |_| handle.exit(1));
+                        return Ok(());
+                    }
                     return Err(Box::new(e) as Box<dyn std::error::Error>);
                 }
             };
             
+            // Register the global shortcut plugin at runtime so a failure here
+            // (e.g. sandboxed Linux sessions) doesn't abort the whole app
+            if let Err(e) = app_handle.plugin(tauri_plugin_global_shortcut::Builder::new().build()) {
+                warn!("Failed to initialize global shortcut plugin: {}", e);
+            }
+
+            state.set_device_name(touchpad_controller.describe().name);
+
             let mouse_emulator = MouseEmulator::new();
             let osd_manager = OSDManager::new(app_handle.clone());
+            osd_manager.cleanup_stale_windows();
+            app.manage(osd_manager.clone());
             
             // Create event channel
             let (hotkey_tx, hotkey_rx) = unbounded();
+            // All state changes go through a single action queue
+            let (action_tx, action_rx) = unbounded();
             
             // Start hotkey manager
             let hotkey_manager = HotkeyManager::new(
                 state.clone(),
                 touchpad_controller.clone(),
                 mouse_emulator.clone(),
-                hotkey_tx
+                hotkey_tx.clone(),
+                action_tx
             );
+            start_action_worker(hotkey_manager.clone(), action_rx);
             hotkey_manager.start();
+            hotkey_manager.apply_device_settings(&state.get_settings());
+            hotkey_manager.apply_startup_state();
+            start_mouse_watcher(hotkey_manager.clone());
+            core::network_sync::configure(&hotkey_manager, &state.get_settings());
+            {
+                let (state, manager) = (state.clone(), hotkey_manager.clone());
+                core::power::start_watcher(state.power_source(), move |source| {
+                    info!("Power source changed to {:?}", source);
+                    state.set_power_source(source);
+                    commands::apply_effective_settings(&state, &manager);
+                });
+            }
+            #[cfg(target_os = "windows")]
+            {
+                let manager = hotkey_manager.clone();
+                if !core::registry_notify::start(move || manager.sync_external_state()) {
+                    warn!("Touchpad registry listener unavailable, external changes won't be noticed");
+                }
+            }
+            start_mouse_keys(state.clone(), mouse_emulator.clone());
+            start_last_good_snapshots(state.clone());
+            app.manage(hotkey_manager.clone());
 
             // Start OSD event listener
             start_osd_listener(
@@ -63,6 +174,8 @@ fn main() {
                 hotkey_rx
             );
 
+            setup_settings_window(&app_handle);
+
             // Hide main window (tray-only app)
             if let Some(window) = app.get_webview_window("main") {
                 if let Err(e) = window.hide() {
@@ -70,41 +183,255 @@ fn main() {
                 }
             }
             
+            handle_missing_permissions(&state, &hotkey_tx);
+
             // Setup system tray
             setup_tray(app)?;
 
+            // SIGINT/SIGTERM go through the normal exit path and its cleanup
+            let signal_handle = app_handle.clone();
+            if let Err(e) = ctrlc::set_handler(move || signal_handle.exit(0)) {
+                warn!("Failed to install signal handler: {}", e);
+            }
+            start_asset_watcher(app.handle());
+            start_countdown_refresh(app.handle());
+            start_alert_flash(app.handle());
+            update_tray_tooltip(&app_handle, &state.get_device_name(), state.get_touchpad_state());
+
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            commands::get_settings,
-            commands::save_settings,
-            commands::check_permissions,
-            commands::request_permissions
-        ])
-        .run(tauri::generate_context!())
+        .invoke_handler(commands::handler())
+        .build(tauri::generate_context!())
     {
-        error!("Error while running tauri application: {}", e);
-        std::process::exit(1);
+        Ok(app) => app,
+        Err(e) => {
+            error!("Error while running tauri application: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    app.run(|app_handle, event| {
+        if let RunEvent::Exit = event {
+            tray::cleanup_for_exit(app_handle);
+            if let Some(osd_manager) = app_handle.try_state::<Arc<OSDManager>>() {
+                osd_manager.close();
+            }
+            stop_mouse_watcher();
+            led_indicator::restore();
+            #[cfg(target_os = "windows")]
+            {
+                core::registry_notify::stop();
+                core::device_notify::stop_power();
+            }
+            supervisor::stop_all();
+            core::cursor_confine::release();
+            if let Some(manager) = app_handle.try_state::<Arc<HotkeyManager>>() {
+                manager.handle_exit();
+            }
+        }
+    });
+}
+
+/// Degraded mode for scripting without a display: applies a single action
+/// (`enable`, `disable`, `toggle` or `status`) and returns the exit code.
+fn run_headless(args: Vec<String>) -> i32 {
+    let controller = match PlatformTouchpadController::new() {
+        Ok(controller) => controller,
+        Err(e) => {
+            eprintln!("Touchpad control unavailable: {}", e);
+            return 1;
+        }
+    };
+
+    let result = match args.first().map(String::as_str) {
+        Some("enable") => controller.enable(),
+        Some("disable") => controller.disable(),
+        Some("toggle") => controller.get_state().and_then(|state| {
+            if state == TouchpadState::Enabled {
+                controller.disable()
+            } else {
+                controller.enable()
+            }
+        }),
+        Some("status") | None => controller.get_state().map(|state| println!("{:?}", state)),
+        Some(other) => {
+            eprintln!("Unknown action: {} (expected enable, disable, toggle or status)", other);
+            return 2;
+        }
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
     }
 }
 
+/// Prints the status line for `--status-format` with a single backend read.
+///
+/// The pause flag lives in the running app, so `{paused}` is always empty here.
+fn print_status_line(template: &str) -> i32 {
+    let controller = match PlatformTouchpadController::new() {
+        Ok(controller) => controller,
+        Err(e) => {
+            eprintln!("Touchpad control unavailable: {}", e);
+            return 1;
+        }
+    };
+    let state = controller.get_state().ok();
+    println!("{}", core::status_line::format_status(template, state, &controller.device_name(), false));
+    if state.is_some() { 0 } else { 1 }
+}
+
+fn handle_missing_permissions(state: &SharedState, tx: &Sender<HotkeyEvent>) {
+    if commands::permissions_granted() {
+        return;
+    }
+
+    match state.get_settings().permission_prompt_mode {
+        PermissionPromptMode::Auto => {
+            if let Err(e) = tx.send(HotkeyEvent::PermissionNeeded) {
+                error!("Failed to send permission event: {}", e);
+            }
+        }
+        PermissionPromptMode::Onboarding => show_settings_window(&state.app_handle),
+        PermissionPromptMode::Silent => info!("Permissions missing, waiting for the first failed action"),
+    }
+}
+
+/// Saves the settings as last known good once they have been in use,
+/// unchanged, for `LAST_GOOD_GRACE`, so `--restore-last-good` can undo a
+/// change that made the app unusable.
+fn start_last_good_snapshots(state: SharedState) {
+    supervisor::spawn("last-good-settings", move |token| {
+        let snapshot = |state: &SharedState| serde_json::to_value(state.get_stored_settings()).ok();
+        let mut previous = snapshot(&state);
+        let mut saved = None;
+        while token.sleep(LAST_GOOD_GRACE) {
+            let current = snapshot(&state);
+            if current == previous && current != saved {
+                match state.save_last_good_settings() {
+                    Ok(()) => {
+                        info!("Saved last known good settings");
+                        saved = current.clone();
+                    }
+                    Err(e) => warn!("Failed to save last known good settings: {}", e),
+                }
+            }
+            previous = current;
+        }
+    });
+}
+
+/// Minimum spacing of "touchpad-state-changed" events to the frontend.
+const STATE_EVENT_INTERVAL: Duration = Duration::from_millis(100);
+
 fn start_osd_listener(
-    _state: SharedState,
+    state: SharedState,
     osd_manager: Arc<OSDManager>,
     rx: Receiver<HotkeyEvent>
 ) {
-    std::thread::spawn(move || {
-        while let Ok(event) = rx.recv() {
-            match event {
-                HotkeyEvent::TouchpadEnabled => {
+    let state_events = ThrottledEmitter::new(state.app_handle.clone(), "touchpad-state-changed", STATE_EVENT_INTERVAL);
+    supervisor::spawn("osd-listener", move |token| loop {
+        let event = select! {
+            recv(rx) -> event => match event {
+                Ok(event) => event,
+                Err(_) => break,
+            },
+            recv(token.channel()) -> _ => break,
+        };
+        match event {
+            HotkeyEvent::TouchpadEnabled { silent } => {
+                // The device answered, so whatever was wrong is resolved
+                state.clear_alert();
+                if !silent {
                     osd_manager.show(true);
+                    sound::play(&state.app_handle, Sound::Enabled);
+                }
+                update_tray_tooltip(&state.app_handle, &state.get_device_name(), TouchpadState::Enabled);
+                update_tray_icon(&state.app_handle, TouchpadState::Enabled);
+                state_events.emit(TouchpadState::Enabled);
+                core::network_sync::announce(&state.get_settings(), TouchpadState::Enabled);
+                if state.get_settings().led_indicator {
+                    led_indicator::show(false);
                 }
-                HotkeyEvent::TouchpadDisabled => {
+            }
+            HotkeyEvent::TouchpadDisabled { silent } => {
+                state.clear_alert();
+                if !silent {
                     osd_manager.show(false);
+                    sound::play(&state.app_handle, Sound::Disabled);
+                }
+                update_tray_tooltip(&state.app_handle, &state.get_device_name(), TouchpadState::Disabled);
+                update_tray_icon(&state.app_handle, TouchpadState::Disabled);
+                state_events.emit(TouchpadState::Disabled);
+                core::network_sync::announce(&state.get_settings(), TouchpadState::Disabled);
+                if state.get_settings().led_indicator {
+                    led_indicator::show(true);
+                }
+            }
+            HotkeyEvent::PermissionNeeded => {
+                state.set_alert(PERMISSION_ALERT.to_string());
+                // Show persistent notification
+                osd_manager.show_permission_warning();
+                sound::play(&state.app_handle, Sound::Warning);
+            }
+            HotkeyEvent::HotkeysUnavailable(reason) => {
+                osd_manager.show_hotkeys_unavailable(&reason);
+            }
+            HotkeyEvent::ToggleFailed(message) => {
+                osd_manager.show_error(&message);
+            }
+            HotkeyEvent::TapToClickChanged(enabled) => {
+                osd_manager.show_tap_to_click(enabled);
+                update_tray_tooltip(&state.app_handle, &state.get_device_name(), state.get_touchpad_state());
+            }
+            HotkeyEvent::TemporarilyEnabled(Some(seconds)) => {
+                osd_manager.show_countdown(seconds);
+            }
+            HotkeyEvent::TemporarilyEnabled(None) => {
+                osd_manager.show(true);
+            }
+            HotkeyEvent::TapsDisabledChanged(active) => {
+                osd_manager.show_taps_disabled(active);
+                update_tray_tooltip(&state.app_handle, &state.get_device_name(), state.get_touchpad_state());
+            }
+            HotkeyEvent::NaturalScrollingChanged(enabled) => {
+                osd_manager.show_natural_scrolling(enabled);
+            }
+            HotkeyEvent::ScrollMethodChanged(method) => {
+                osd_manager.show_scroll_method(method);
+            }
+            HotkeyEvent::SensitivityChanged { level, levels, value } => {
+                osd_manager.show_sensitivity(level, levels, value);
+            }
+            HotkeyEvent::ConfirmDisable => {
+                osd_manager.show_confirm_disable();
+            }
+            HotkeyEvent::TrackpointChanged(enabled) => {
+                osd_manager.show_trackpoint(enabled);
+            }
+            HotkeyEvent::DeviceChanged(touchpad_state) => {
+                state.clear_alert();
+                update_tray_tooltip(&state.app_handle, &state.get_device_name(), touchpad_state);
+                update_tray_icon(&state.app_handle, touchpad_state);
+                state_events.emit(touchpad_state);
+            }
+            HotkeyEvent::DeviceLost(device_name) => {
+                state.set_alert(format!("{} lost", device_name));
+                show_device_lost(&state.app_handle, &device_name);
+                osd_manager.show_device_lost(&device_name);
+                if let Err(e) = state.app_handle.emit("touchpad-device-lost", &device_name) {
+                    warn!("Failed to emit device lost: {}", e);
                 }
-                HotkeyEvent::PermissionNeeded => {
-                    // Show persistent notification
-                    osd_manager.show_permission_warning();
+            }
+            HotkeyEvent::HotkeyConflict(conflict) => {
+                warn!("Hotkey {} ({}): {}", conflict.accelerator, conflict.setting, conflict.reason);
+                osd_manager.show_error(&format!("Hotkey {}: {}", conflict.accelerator, conflict.reason));
+                if let Err(e) = state.app_handle.emit("hotkey-conflict", &conflict) {
+                    warn!("Failed to emit hotkey conflict: {}", e);
                 }
             }
         }
@@ -119,5 +446,5 @@ fn ensure_usage() {
     // by the compiler during analysis
     let _controller_error = core::input_controller::ControllerError::LinuxDeviceNotFound;
     let _touchpad_state = core::state::TouchpadState::Enabled;
-    let _hotkey_event = core::hotkey_manager::HotkeyEvent::TouchpadEnabled;
+    let _hotkey_event = core::hotkey_manager::HotkeyEvent::TouchpadEnabled { silent: false };
 }
\ No newline at end of file
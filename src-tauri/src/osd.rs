@@ -1,23 +1,295 @@
-use tauri::AppHandle;
-use std::sync::Arc;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+use tauri::webview::PageLoadEvent;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use log::{error, info, warn};
+use crate::core::dnd;
+use crate::core::input_controller::ScrollMethod;
+use crate::core::settings::{Settings, MIN_OSD_OPACITY};
+use crate::core::state::SharedState;
+
+const OSD_LABEL: &str = "osd";
+const OSD_FADE: Duration = Duration::from_millis(200);
 
 #[allow(dead_code)]
 pub struct OSDManager {
     app_handle: AppHandle,
+    // Bumped on every show so a pending hide from an older show is skipped
+    generation: Arc<AtomicU64>,
+    // Whether osd.html has finished loading, so `window.showOsd` exists
+    loaded: Arc<AtomicBool>,
+    // The latest show requested before the page loaded, run once it has
+    pending: Arc<Mutex<Option<String>>>,
 }
 
 impl OSDManager {
     pub fn new(app_handle: AppHandle) -> Arc<Self> {
-        Arc::new(Self { app_handle })
+        Arc::new(Self {
+            app_handle,
+            generation: Arc::new(AtomicU64::new(0)),
+            loaded: Arc::new(AtomicBool::new(false)),
+            pending: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Destroys overlay windows left over from a previous run (e.g. after a
+    /// crash with the OSD visible) so a fresh one is created on first use.
+    pub fn cleanup_stale_windows(&self) {
+        self.destroy_windows("stale");
+    }
+
+    /// Releases the overlay on exit: pending hides and a queued first show are
+    /// dropped and the window is destroyed, so nothing outlives the app.
+    pub fn close(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.pending.lock().unwrap_or_else(|p| p.into_inner()).take();
+        self.destroy_windows("exiting");
+        self.loaded.store(false, Ordering::SeqCst);
+    }
+
+    fn destroy_windows(&self, reason: &str) {
+        for (label, window) in self.app_handle.webview_windows() {
+            if label != OSD_LABEL && !label.starts_with(&format!("{}-", OSD_LABEL)) {
+                continue;
+            }
+            match window.destroy() {
+                Ok(()) => info!("Destroyed {} OSD window {}", reason, label),
+                Err(e) => warn!("Failed to destroy {} OSD window {}: {}", reason, label, e),
+            }
+        }
     }
-    
+
     pub fn show(&self, enabled: bool) {
-        // Placeholder implementation - in a real app this would show an OSD notification
-        println!("Touchpad {}", if enabled { "enabled" } else { "disabled" });
+        let settings = self.settings();
+        if !self.should_show(&settings) {
+            return;
+        }
+
+        let message = format!("Touchpad {}", if enabled { "enabled" } else { "disabled" });
+        self.display(&message, &settings);
+    }
+
+    pub fn show_tap_to_click(&self, enabled: bool) {
+        let settings = self.settings();
+        if !self.should_show(&settings) {
+            return;
+        }
+
+        let message = format!("Tap-to-click {}", if enabled { "enabled" } else { "disabled" });
+        self.display(&message, &settings);
+    }
+
+    pub fn show_taps_disabled(&self, active: bool) {
+        let settings = self.settings();
+        if !self.should_show(&settings) {
+            return;
+        }
+
+        self.display(if active { "Taps disabled" } else { "Taps enabled" }, &settings);
     }
-    
+
+    pub fn show_scroll_method(&self, method: ScrollMethod) {
+        let settings = self.settings();
+        if !self.should_show(&settings) {
+            return;
+        }
+
+        let message = match method {
+            ScrollMethod::TwoFinger => "Two-finger scrolling",
+            ScrollMethod::Edge => "Edge scrolling",
+        };
+        self.display(message, &settings);
+    }
+
+    /// Counts down the remaining seconds of a temporary enable, once per
+    /// second, until the time is up or another message replaces it.
+    pub fn show_countdown(self: &Arc<Self>, seconds: u64) {
+        let settings = self.settings();
+        if !self.should_show(&settings) {
+            return;
+        }
+
+        let manager = self.clone();
+        std::thread::spawn(move || {
+            for remaining in (1..=seconds).rev() {
+                manager.display(&format!("Touchpad enabled, {}s left", remaining), &settings);
+                let generation = manager.generation.load(Ordering::SeqCst);
+                std::thread::sleep(Duration::from_secs(1));
+                if manager.generation.load(Ordering::SeqCst) != generation {
+                    return;
+                }
+            }
+        });
+    }
+
+    pub fn show_natural_scrolling(&self, enabled: bool) {
+        let settings = self.settings();
+        if !self.should_show(&settings) {
+            return;
+        }
+
+        let message = format!("Natural scrolling {}", if enabled { "on" } else { "off" });
+        self.display(&message, &settings);
+    }
+
     pub fn show_permission_warning(&self) {
         // Placeholder implementation - in a real app this would show a permission warning
         println!("Permission needed to control touchpad");
     }
-}
\ No newline at end of file
+
+    /// Shown even with the OSD off, since nothing else tells the user their
+    /// hotkeys do nothing.
+    pub fn show_hotkeys_unavailable(&self, reason: &str) {
+        let settings = self.settings();
+        let message = format!("Global hotkeys unavailable ({}); use the tray menu to toggle the touchpad", reason);
+        self.display(&message, &settings);
+    }
+
+    pub fn show_sensitivity(&self, level: usize, levels: usize, value: f64) {
+        let settings = self.settings();
+        if !self.should_show(&settings) {
+            return;
+        }
+
+        let message = format!("Pointer speed {}/{} ({:+.2})", level + 1, levels, value);
+        self.display(&message, &settings);
+    }
+
+    pub fn show_confirm_disable(&self) {
+        let settings = self.settings();
+        if !self.should_show(&settings) {
+            return;
+        }
+
+        self.display("Press again to disable", &settings);
+    }
+
+    pub fn show_trackpoint(&self, enabled: bool) {
+        let settings = self.settings();
+        if !self.should_show(&settings) {
+            return;
+        }
+
+        let message = format!("Trackpoint {}", if enabled { "enabled" } else { "disabled" });
+        self.display(&message, &settings);
+    }
+
+    pub fn show_device_lost(&self, device_name: &str) {
+        let settings = self.settings();
+        if !self.should_show(&settings) {
+            return;
+        }
+
+        self.display(&format!("{} disconnected", device_name), &settings);
+    }
+
+    pub fn show_error(&self, message: &str) {
+        let settings = self.settings();
+        let message = format!("{} (try system touchpad settings in the tray)", message);
+        self.display(&message, &settings);
+    }
+
+    // Permission warnings go through their own path and are never suppressed
+    fn should_show(&self, settings: &Settings) -> bool {
+        settings.show_osd && !(settings.respect_dnd && dnd::is_dnd_active().unwrap_or(false))
+    }
+
+    fn settings(&self) -> Settings {
+        self.app_handle
+            .try_state::<SharedState>()
+            .map(|state| state.get_settings())
+            .unwrap_or_default()
+    }
+
+    fn display(&self, message: &str, settings: &Settings) {
+        let Some(window) = self.overlay_window() else {
+            println!("{}", message);
+            return;
+        };
+
+        let opacity = settings.osd_opacity.clamp(MIN_OSD_OPACITY, 1.0);
+        let message_js = serde_json::to_string(message).unwrap_or_else(|_| "\"\"".to_string());
+        // The bar restarts from full on every show, so rapid toggles reset it
+        let progress_ms = if settings.osd_progress_bar { settings.osd_duration_ms } else { 0 };
+        let script = format!(
+            "window.showOsd && window.showOsd({}, {}, {}, {})",
+            message_js, opacity, settings.osd_animate, progress_ms
+        );
+        if self.loaded.load(Ordering::SeqCst) {
+            reveal(&window, &script);
+        } else {
+            // Shown from the page-load handler, see `overlay_window`
+            *self.pending.lock().unwrap_or_else(|p| p.into_inner()) = Some(script);
+        }
+
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let current = self.generation.clone();
+        let animate = settings.osd_animate;
+        let duration = Duration::from_millis(settings.osd_duration_ms);
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            if current.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let _ = window.eval("window.hideOsd && window.hideOsd()");
+            if animate {
+                // Let the fade-out finish before hiding the window
+                std::thread::sleep(OSD_FADE);
+                if current.load(Ordering::SeqCst) != generation {
+                    return;
+                }
+            }
+            if let Err(e) = window.hide() {
+                warn!("Failed to hide OSD window: {}", e);
+            }
+        });
+    }
+
+    fn overlay_window(&self) -> Option<WebviewWindow> {
+        if let Some(window) = self.app_handle.get_webview_window(OSD_LABEL) {
+            return Some(window);
+        }
+
+        self.loaded.store(false, Ordering::SeqCst);
+        let (loaded, pending) = (self.loaded.clone(), self.pending.clone());
+        match WebviewWindowBuilder::new(&self.app_handle, OSD_LABEL, WebviewUrl::App("osd.html".into()))
+            .title("OSD")
+            .transparent(true)
+            .inner_size(240.0, 64.0)
+            .decorations(false)
+            .always_on_top(true)
+            .skip_taskbar(true)
+            .resizable(false)
+            .focused(false)
+            .visible(false)
+            .center()
+            .on_page_load(move |window, payload| {
+                if payload.event() != PageLoadEvent::Finished {
+                    return;
+                }
+                loaded.store(true, Ordering::SeqCst);
+                if let Some(script) = pending.lock().unwrap_or_else(|p| p.into_inner()).take() {
+                    reveal(&window, &script);
+                }
+            })
+            .build()
+        {
+            Ok(window) => Some(window),
+            Err(e) => {
+                error!("Failed to create OSD window: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Runs a `showOsd` script and makes the overlay visible.
+fn reveal(window: &WebviewWindow, script: &str) {
+    if let Err(e) = window.eval(script) {
+        warn!("Failed to update OSD window: {}", e);
+    }
+    if let Err(e) = window.show() {
+        warn!("Failed to show OSD window: {}", e);
+    }
+}
@@ -0,0 +1,48 @@
+use tauri::{AppHandle, Manager};
+use std::time::Duration;
+use log::info;
+use crate::core::state::SharedState;
+use crate::core::supervisor;
+use crate::tray::update_pause_item;
+
+const TIMER_NAME: &str = "pause-timer";
+// How often the tray label is refreshed while a timed pause runs
+const LABEL_REFRESH: Duration = Duration::from_secs(30);
+
+pub fn set_paused(app: &AppHandle, paused: bool) {
+    let Some(state) = app.try_state::<SharedState>() else {
+        return;
+    };
+    supervisor::stop(TIMER_NAME);
+    state.set_paused(paused);
+    info!("Auto-toggle {}", if paused { "paused" } else { "resumed" });
+    update_pause_item(app);
+}
+
+/// Pauses auto-toggling for `duration`; a manual unpause cancels the timer.
+pub fn pause_for(app: &AppHandle, duration: Duration) -> Result<(), String> {
+    let Some(state) = app.try_state::<SharedState>() else {
+        return Ok(());
+    };
+    let state = state.inner().clone();
+    let generation = state.pause.pause_for(duration)?;
+    info!("Auto-toggle paused for {:?}", duration);
+    update_pause_item(app);
+
+    // Replaces the timer of an earlier pause
+    supervisor::stop(TIMER_NAME);
+    let app = app.clone();
+    supervisor::spawn(TIMER_NAME, move |token| {
+        // The label is set on the main thread without waiting for it, so
+        // stopping the timer from a tray click can't deadlock
+        let refresh = || {
+            let handle = app.clone();
+            let _ = app.run_on_main_thread(move || update_pause_item(&handle));
+        };
+        if state.pause.run_timer(generation, LABEL_REFRESH, |duration| token.sleep(duration), refresh) {
+            info!("Timed pause expired, auto-toggle resumed");
+            refresh();
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use tauri::{path::BaseDirectory, AppHandle, Manager};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+use log::{info, warn};
+use crate::core::dnd;
+use crate::core::settings::Settings;
+use crate::core::state::{lock_recovering, SharedState};
+
+/// Validated copies of the sounds in the custom asset folder, by file name.
+static CUSTOM: Lazy<Mutex<HashMap<String, PathBuf>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy)]
+pub enum Sound {
+    Enabled,
+    Disabled,
+    Warning,
+}
+
+impl Sound {
+    fn bundled(&self) -> &'static str {
+        match self {
+            Sound::Enabled => "sounds/enable.wav",
+            Sound::Disabled => "sounds/disable.wav",
+            Sound::Warning => "sounds/warning.wav",
+        }
+    }
+
+    /// The name a replacement in the custom asset folder must have.
+    fn file_name(&self) -> &'static str {
+        self.bundled().trim_start_matches("sounds/")
+    }
+
+    fn custom<'a>(&self, settings: &'a Settings) -> Option<&'a String> {
+        match self {
+            Sound::Enabled => settings.sound_enabled_path.as_ref(),
+            Sound::Disabled => settings.sound_disabled_path.as_ref(),
+            Sound::Warning => settings.sound_warning_path.as_ref(),
+        }
+    }
+}
+
+/// Plays `sound` without blocking if `play_sound` is on and DND allows it.
+pub fn play(app_handle: &AppHandle, sound: Sound) {
+    let Some(state) = app_handle.try_state::<SharedState>() else {
+        return;
+    };
+    let settings = state.get_settings();
+    if !settings.play_sound {
+        return;
+    }
+    if settings.respect_dnd && dnd::is_dnd_active().unwrap_or(false) {
+        return;
+    }
+
+    // A path set in the settings wins over the asset folder
+    let from_assets = || lock_recovering(&CUSTOM).get(sound.file_name()).cloned();
+    let path = match sound.custom(&settings).map(PathBuf::from).or_else(from_assets) {
+        Some(path) => path,
+        None => match app_handle.path().resolve(sound.bundled(), BaseDirectory::Resource) {
+            Ok(path) => path,
+            Err(e) => {
+                warn!("Failed to resolve bundled sound: {}", e);
+                return;
+            }
+        },
+    };
+
+    std::thread::spawn(move || {
+        if let Err(e) = player_command(&path)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+        {
+            warn!("Failed to play sound {}: {}", path.display(), e);
+        }
+    });
+}
+
+/// Whether `path` holds a WAV file, the format every platform player handles.
+pub fn validate_sound(path: &Path) -> Result<(), String> {
+    let mut header = [0; 12];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .map_err(|e| e.to_string())?;
+    if is_wav(&header) {
+        Ok(())
+    } else {
+        Err("not a WAV file".to_string())
+    }
+}
+
+fn is_wav(header: &[u8; 12]) -> bool {
+    &header[0..4] == b"RIFF" && &header[8..12] == b"WAVE"
+}
+
+/// Re-reads the custom sounds in `dir`. Valid files are copied to the cache
+/// dir and played from there, so a broken or half-written replacement
+/// leaves the previous sound in use.
+pub fn reload_custom(app_handle: &AppHandle, dir: &Path) {
+    let cache = match app_handle.path().app_cache_dir() {
+        Ok(cache) => cache.join("sounds"),
+        Err(e) => {
+            warn!("Failed to resolve cache dir for custom sounds: {}", e);
+            return;
+        }
+    };
+    let mut custom = lock_recovering(&CUSTOM);
+    for sound in [Sound::Enabled, Sound::Disabled, Sound::Warning] {
+        let name = sound.file_name();
+        let source = dir.join(name);
+        if !source.is_file() {
+            if custom.remove(name).is_some() {
+                info!("Custom sound {} removed, using the bundled one", name);
+            }
+            continue;
+        }
+        if let Err(e) = validate_sound(&source) {
+            warn!("Ignoring invalid sound {}: {}", source.display(), e);
+            continue;
+        }
+        let copy = cache.join(name);
+        match fs::create_dir_all(&cache).and_then(|_| fs::copy(&source, &copy)) {
+            Ok(_) => {
+                info!("Loaded custom sound {}", source.display());
+                custom.insert(name.to_string(), copy);
+            }
+            Err(e) => warn!("Failed to load custom sound {}: {}", source.display(), e),
+        }
+    }
+}
+
+// The platform players honour the system volume and mute state
+#[cfg(target_os = "linux")]
+fn player_command(path: &PathBuf) -> Command {
+    let mut command = Command::new("paplay");
+    command.arg(path);
+    command
+}
+
+#[cfg(target_os = "macos")]
+fn player_command(path: &PathBuf) -> Command {
+    let mut command = Command::new("afplay");
+    command.arg(path);
+    command
+}
+
+#[cfg(target_os = "windows")]
+fn player_command(path: &PathBuf) -> Command {
+    let mut command = Command::new("powershell");
+    command.args([
+        "-NoProfile",
+        "-Command",
+        &format!(
+            "(New-Object Media.SoundPlayer '{}').PlaySync()",
+            path.display().to_string().replace('\'', "''")
+        ),
+    ]);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_wav_headers_pass() {
+        assert!(is_wav(b"RIFF\x24\x08\x00\x00WAVE"));
+        // Ogg and PNG files dropped under a sound's name
+        assert!(!is_wav(b"OggS\x00\x02\x00\x00\x00\x00\x00\x00"));
+        assert!(!is_wav(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0d"));
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        let path = std::env::temp_dir().join("enable-touchpad-truncated.wav");
+        fs::write(&path, b"RIFF").unwrap();
+        assert!(validate_sound(&path).is_err());
+        let _ = fs::remove_file(path);
+    }
+}
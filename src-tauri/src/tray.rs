@@ -1,40 +1,362 @@
 use tauri::{
-    menu::{Menu, MenuItem},
-    tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
-    App, AppHandle, Result as TauriResult,
+    menu::{Menu, MenuItem, Submenu},
+    tray::{MouseButton, TrayIcon, TrayIconBuilder, TrayIconEvent},
+    App, AppHandle, Manager, Result as TauriResult, Wry,
 };
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use crate::core::hotkey_manager::HotkeyManager;
+use crate::core::mouse_detector;
+use crate::core::command_queue::{ActionSource, TouchpadAction};
+use crate::core::settings::TrayIconStyle;
+use crate::core::state::{lock_recovering, safe_mode, set_safe_mode, PendingTimer, PointerMode, SharedState, TouchpadState};
+use crate::core::supervisor;
+use crate::icons::IconSet;
+use crate::pause;
+use crate::window::{open_system_touchpad_settings, show_settings_window};
+
+pub const TRAY_ID: &str = "main";
+const MAX_DEVICE_NAME_LEN: usize = 40;
+const COUNTDOWN_REFRESH: Duration = Duration::from_secs(1);
+const ALERT_FLASH_INTERVAL: Duration = Duration::from_millis(800);
+
+/// Set by `show_device_lost` until a state update shows the device is back.
+static DEVICE_LOST: AtomicBool = AtomicBool::new(false);
+
+/// Icons for the current `tray_icon_style`, swapped when the setting changes.
+pub struct TrayIcons(Mutex<IconSet>);
+
+/// Owns the tray icon so it lives until `cleanup_for_exit` removes it,
+/// instead of relying on Tauri to keep an unowned icon alive.
+pub struct TrayHandle(Mutex<Option<TrayIcon>>);
+
+/// Menu items whose labels change at runtime.
+pub struct TrayMenuItems {
+    pub pause: MenuItem<Wry>,
+    pub safe_mode: MenuItem<Wry>,
+}
 
 #[allow(dead_code)]
 pub fn setup_tray(app: &App) -> TauriResult<()> {
     let quit_item = MenuItem::with_id(app, "quit", "quit", true, None::<&str>)?;
     let settings_item = MenuItem::with_id(app, "settings", "settings", true, None::<&str>)?;
     let pause_item = MenuItem::with_id(app, "pause", "pause", true, None::<&str>)?;
+    let safe_mode_item = MenuItem::with_id(app, "safe_mode", safe_mode_label(), true, None::<&str>)?;
+    let toggle_item = MenuItem::with_id(app, "toggle", "toggle touchpad", true, None::<&str>)?;
+    let system_settings_item = MenuItem::with_id(app, "system_settings", "system touchpad settings", true, None::<&str>)?;
+    let pause_for_menu = Submenu::with_items(app, "pause for", true, &[
+        &MenuItem::with_id(app, "pause_15", "15 minutes", true, None::<&str>)?,
+        &MenuItem::with_id(app, "pause_30", "30 minutes", true, None::<&str>)?,
+        &MenuItem::with_id(app, "pause_60", "1 hour", true, None::<&str>)?,
+    ])?;
     
-    let menu = Menu::with_items(app, &[&quit_item, &settings_item, &pause_item])?;
+    let enable_for_menu = Submenu::with_items(app, "enable for", true, &[
+        &MenuItem::with_id(app, "enable_30", "30 seconds", true, None::<&str>)?,
+        &MenuItem::with_id(app, "enable_60", "1 minute", true, None::<&str>)?,
+        &MenuItem::with_id(app, "enable_300", "5 minutes", true, None::<&str>)?,
+    ])?;
+    
+    let menu = Menu::with_items(app, &[&toggle_item, &enable_for_menu, &quit_item, &settings_item, &system_settings_item, &pause_item, &pause_for_menu, &safe_mode_item])?;
+    app.manage(TrayMenuItems { pause: pause_item.clone(), safe_mode: safe_mode_item.clone() });
+    let state = app.try_state::<SharedState>();
+    let style = state.as_ref().map_or(TrayIconStyle::Auto, |state| state.get_settings().tray_icon_style);
+    let icons = IconSet::load(app.handle(), style);
+    let icon = match &state {
+        Some(state) => icons.for_state(state.get_touchpad_state()),
+        None => icons.default_icon(),
+    };
+    let template = icons.template;
+    app.manage(TrayIcons(Mutex::new(icons)));
     
     // 创建托盘图标
-    let _tray = TrayIconBuilder::new()
+    let tray = TrayIconBuilder::with_id(TRAY_ID)
         .menu(&menu)
-        .icon(app.default_window_icon().unwrap().clone())
+        .icon(icon)
+        .icon_as_template(template)
         .tooltip("Enable Touchpad")
         .on_menu_event(handle_menu_event)
         .on_tray_icon_event(handle_tray_event)
         .build(app)?;
+    app.manage(TrayHandle(Mutex::new(Some(tray))));
 
     Ok(())
 }
 
+/// Unregisters the global shortcuts and removes the tray icon so neither
+/// outlives the process on platforms that don't clean up after it. Safe to
+/// call more than once.
+pub fn cleanup_for_exit(app: &AppHandle) {
+    if let Some(manager) = app.try_state::<Arc<HotkeyManager>>() {
+        manager.unregister_hotkeys();
+    }
+    let tray = app.try_state::<TrayHandle>().and_then(|handle| lock_recovering(&handle.0).take());
+    if let Some(tray) = tray {
+        if let Err(e) = tray.set_visible(false) {
+            log::warn!("Failed to hide tray icon: {}", e);
+        }
+    }
+    let _ = app.remove_tray_by_id(TRAY_ID);
+}
+
+/// Shows the managed device and its state, e.g. "SynPS/2 Synaptics TouchPad — Enabled",
+/// or "— Taps disabled" while enabled with tap-to-click off.
+#[allow(dead_code)]
+pub fn update_tray_tooltip(app: &AppHandle, device_name: &str, state: TouchpadState) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+
+    let name = if device_name.is_empty() {
+        "Enable Touchpad".to_string()
+    } else if device_name.chars().count() > MAX_DEVICE_NAME_LEN {
+        let truncated: String = device_name.chars().take(MAX_DEVICE_NAME_LEN - 1).collect();
+        format!("{}…", truncated.trim_end())
+    } else {
+        device_name.to_string()
+    };
+    let tap_to_click = app.try_state::<SharedState>().is_none_or(|shared| shared.tap_to_click());
+    let label = match PointerMode::from_parts(state, tap_to_click) {
+        PointerMode::Enabled => "Enabled",
+        PointerMode::TapsDisabled => "Taps disabled",
+        PointerMode::Disabled => "Disabled",
+    };
+    let mut tooltip = format!("{} — {}", name, label);
+    if safe_mode() {
+        tooltip.push_str(" (safe mode)");
+    }
+    if let Some(countdown) = app.try_state::<SharedState>().and_then(|shared| countdown_label(&shared)) {
+        tooltip.push_str(&format!(" — {}", countdown));
+    }
+    if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+        log::warn!("Failed to update tray tooltip: {}", e);
+    }
+}
+
+/// Switches the tray icon to the one for `state`.
+pub fn update_tray_icon(app: &AppHandle, state: TouchpadState) {
+    // A known state means the device answered again
+    DEVICE_LOST.store(false, Ordering::SeqCst);
+    restore_tray_icon(app, state);
+}
+
+/// Shows the icon for `state`, or the device-lost icon while the device is
+/// missing, without changing either.
+fn restore_tray_icon(app: &AppHandle, state: TouchpadState) {
+    let (Some(tray), Some(icons)) = (app.tray_by_id(TRAY_ID), app.try_state::<TrayIcons>()) else {
+        return;
+    };
+    let icons = lock_recovering(&icons.0);
+    let icon = if DEVICE_LOST.load(Ordering::SeqCst) { icons.default_icon() } else { icons.for_state(state) };
+    if let Err(e) = tray.set_icon(Some(icon)) {
+        log::warn!("Failed to update tray icon: {}", e);
+    }
+    if let Err(e) = tray.set_icon_as_template(icons.template) {
+        log::warn!("Failed to update tray icon template flag: {}", e);
+    }
+}
+
+// e.g. "disables in 4:05", for the timer due soonest
+fn countdown_label(state: &SharedState) -> Option<String> {
+    if !state.get_settings().tray_countdown {
+        return None;
+    }
+    let (timer, remaining) = state.next_timer()?;
+    let what = match timer {
+        PendingTimer::TemporaryEnable => "disables",
+        PendingTimer::Safeguard => "re-enables",
+        PendingTimer::Pause => "auto-toggle resumes",
+    };
+    let secs = remaining.as_secs();
+    Some(format!("{} in {}:{:02}", what, secs / 60, secs % 60))
+}
+
+/// Refreshes the tooltip and pause item once a second while a timer runs,
+/// plus once after the last one ends.
+pub fn start_countdown_refresh(app: &AppHandle) {
+    let app = app.clone();
+    supervisor::spawn("tray-countdown", move |token| {
+        let mut was_active = false;
+        while token.sleep(COUNTDOWN_REFRESH) {
+            let Some(state) = app.try_state::<SharedState>() else {
+                continue;
+            };
+            let active = state.next_timer().is_some();
+            if active || was_active {
+                update_tray_tooltip(&app, &state.get_device_name(), state.get_touchpad_state());
+                update_pause_item(&app);
+            }
+            was_active = active;
+        }
+    });
+}
+
+/// Swaps the tray icon between the state icon and the alert icon while
+/// `AppState::alert` is set, and restores the state icon once it clears.
+/// While the device is lost the device-lost icon stands in for the state icon.
+pub fn start_alert_flash(app: &AppHandle) {
+    let app = app.clone();
+    supervisor::spawn("tray-alert", move |token| {
+        let mut showing_alert = false;
+        while token.sleep(ALERT_FLASH_INTERVAL) {
+            let Some(state) = app.try_state::<SharedState>() else {
+                continue;
+            };
+            let flash = state.alert().is_some() && state.get_settings().tray_alert_flash;
+            if flash && !showing_alert {
+                let (Some(tray), Some(icons)) = (app.tray_by_id(TRAY_ID), app.try_state::<TrayIcons>()) else {
+                    continue;
+                };
+                if let Err(e) = tray.set_icon(Some(lock_recovering(&icons.0).alert_icon())) {
+                    log::warn!("Failed to update tray icon: {}", e);
+                }
+                showing_alert = true;
+            } else if showing_alert {
+                restore_tray_icon(&app, state.get_touchpad_state());
+                showing_alert = false;
+            }
+        }
+    });
+}
+
+/// Marks the tray as not knowing the state until the device is found again.
+pub fn show_device_lost(app: &AppHandle, device_name: &str) {
+    DEVICE_LOST.store(true, Ordering::SeqCst);
+    let (Some(tray), Some(icons)) = (app.tray_by_id(TRAY_ID), app.try_state::<TrayIcons>()) else {
+        return;
+    };
+    if let Err(e) = tray.set_tooltip(Some(format!("{} — Device lost", device_name))) {
+        log::warn!("Failed to update tray tooltip: {}", e);
+    }
+    let icons = lock_recovering(&icons.0);
+    if let Err(e) = tray.set_icon(Some(icons.default_icon())) {
+        log::warn!("Failed to update tray icon: {}", e);
+    }
+}
+
+/// Reloads the icons for `style` and applies them without a restart.
+pub fn apply_tray_icon_style(app: &AppHandle, style: TrayIconStyle) {
+    let (Some(icons), Some(state)) = (app.try_state::<TrayIcons>(), app.try_state::<SharedState>()) else {
+        return;
+    };
+    *lock_recovering(&icons.0) = IconSet::load(app, style);
+    restore_tray_icon(app, state.get_touchpad_state());
+}
+
+/// Reflects the pause flag and any remaining timed pause in the pause item label.
+pub fn update_pause_item(app: &AppHandle) {
+    let (Some(items), Some(state)) = (app.try_state::<TrayMenuItems>(), app.try_state::<SharedState>()) else {
+        return;
+    };
+    let label = match (state.is_paused(), state.pause_remaining()) {
+        (false, _) => "pause".to_string(),
+        (true, Some(remaining)) => format!("resume ({} min left)", remaining.as_secs().div_ceil(60)),
+        (true, None) => "resume".to_string(),
+    };
+    if let Err(e) = items.pause.set_text(label) {
+        log::warn!("Failed to update pause item: {}", e);
+    }
+}
+
+fn safe_mode_label() -> &'static str {
+    if safe_mode() {
+        "leave safe mode"
+    } else {
+        "safe mode"
+    }
+}
+
+/// Turns safe mode on or off and refreshes the menu label and tooltip.
+fn toggle_safe_mode(app: &AppHandle) {
+    set_safe_mode(!safe_mode());
+    if let Some(items) = app.try_state::<TrayMenuItems>() {
+        if let Err(e) = items.safe_mode.set_text(safe_mode_label()) {
+            log::warn!("Failed to update safe mode item: {}", e);
+        }
+    }
+    if let Some(state) = app.try_state::<SharedState>() {
+        update_tray_tooltip(app, &state.get_device_name(), state.get_touchpad_state());
+    }
+}
+
 #[allow(dead_code)]
 fn handle_menu_event(app: &AppHandle, event: tauri::menu::MenuEvent) {
     match event.id.as_ref() {
-        "quit" => {
-            app.exit(0);
+        "quit" => quit(app),
+        "settings" => {
+            show_settings_window(app);
+        }
+        "system_settings" => {
+            if let Err(e) = open_system_touchpad_settings(app) {
+                log::warn!("Failed to open system touchpad settings: {}", e);
+            }
+        }
+        "pause" => {
+            let paused = app.try_state::<SharedState>().is_some_and(|state| state.is_paused());
+            pause::set_paused(app, !paused);
+        }
+        "safe_mode" => toggle_safe_mode(app),
+        "pause_15" => pause_for_minutes(app, 15),
+        "pause_30" => pause_for_minutes(app, 30),
+        "pause_60" => pause_for_minutes(app, 60),
+        "enable_30" => enable_temporarily(app, 30),
+        "enable_60" => enable_temporarily(app, 60),
+        "enable_300" => enable_temporarily(app, 300),
+        "toggle" => {
+            // Always available, even when global hotkeys failed to register
+            if let Some(manager) = app.try_state::<Arc<HotkeyManager>>() {
+                manager.request(TouchpadAction::Toggle, ActionSource::Tray);
+            }
         }
 
         _ => {}
     }
 }
 
+fn pause_for_minutes(app: &AppHandle, minutes: u64) {
+    if let Err(e) = pause::pause_for(app, Duration::from_secs(minutes * 60)) {
+        log::warn!("Failed to pause auto-toggle: {}", e);
+    }
+}
+
+fn enable_temporarily(app: &AppHandle, seconds: u64) {
+    if let Some(manager) = app.try_state::<Arc<HotkeyManager>>() {
+        manager.request(TouchpadAction::EnableTemporarily(seconds), ActionSource::Tray);
+    }
+}
+
+/// Quits, first asking for confirmation if the touchpad is disabled since
+/// the re-enable hotkey goes away with the app.
+fn quit(app: &AppHandle) {
+    let needs_confirmation = app.try_state::<SharedState>().is_some_and(|state| {
+        state.get_settings().confirm_quit && state.get_touchpad_state() == TouchpadState::Disabled
+    });
+    if !needs_confirmation {
+        cleanup_for_exit(app);
+        app.exit(0);
+        return;
+    }
+
+    let message = if mouse_detector::is_mouse_connected() {
+        "The touchpad is disabled. After quitting, the hotkey can no longer re-enable it."
+    } else {
+        "The touchpad is disabled and no mouse is connected. After quitting, the hotkey can no longer re-enable it and you may have no pointer."
+    };
+    let handle = app.clone();
+    app.dialog()
+        .message(message)
+        .title("Quit Enable Touchpad?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("Quit".to_string(), "Cancel".to_string()))
+        .show(move |confirmed| {
+            if confirmed {
+                cleanup_for_exit(&handle);
+                handle.exit(0);
+            }
+        });
+}
+
 #[allow(dead_code)]
 fn handle_tray_event(_tray: &tauri::tray::TrayIcon, event: TrayIconEvent) {
     if let TrayIconEvent::Click {
@@ -0,0 +1,150 @@
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewWindow, WindowEvent};
+use log::{info, warn};
+use crate::core::state::{SharedState, WindowGeometry};
+
+const MAIN_WINDOW: &str = "main";
+
+/// Keeps the settings window alive for the tray: closing it saves its
+/// geometry and hides it instead of destroying it.
+pub fn setup_settings_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+    let handle = window.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::CloseRequested { api, .. } = event {
+            api.prevent_close();
+            hide_settings_window(&handle);
+        }
+    });
+}
+
+pub fn show_settings_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        warn!("Settings window is not available");
+        return;
+    };
+    if !window.is_visible().unwrap_or(false) {
+        restore_geometry(app, &window);
+    }
+    if let Err(e) = window.show() {
+        warn!("Failed to show settings window: {}", e);
+    }
+    if let Err(e) = window.unminimize() {
+        warn!("Failed to unminimize settings window: {}", e);
+    }
+    if let Err(e) = window.set_focus() {
+        warn!("Failed to focus settings window: {}", e);
+    }
+}
+
+/// Hides the settings window if it is visible and focused, otherwise brings it up.
+pub fn toggle_settings_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        warn!("Settings window is not available");
+        return;
+    };
+    let visible = window.is_visible().unwrap_or(false);
+    let focused = window.is_focused().unwrap_or(false);
+    if visible && focused {
+        hide_settings_window(&window);
+    } else {
+        show_settings_window(app);
+    }
+}
+
+fn hide_settings_window(window: &WebviewWindow) {
+    save_geometry(window);
+    if let Err(e) = window.hide() {
+        warn!("Failed to hide settings window: {}", e);
+    }
+}
+
+fn save_geometry(window: &WebviewWindow) {
+    let Some(state) = window.app_handle().try_state::<SharedState>() else {
+        return;
+    };
+    match (window.outer_position(), window.inner_size()) {
+        (Ok(position), Ok(size)) => state.persist_window_geometry(WindowGeometry {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+        }),
+        (Err(e), _) | (_, Err(e)) => warn!("Failed to read settings window geometry: {}", e),
+    }
+}
+
+fn restore_geometry(app: &AppHandle, window: &WebviewWindow) {
+    let Some(geometry) = app.try_state::<SharedState>().and_then(|state| state.load_window_geometry()) else {
+        return;
+    };
+    let geometry = clamp_to_monitors(window, geometry);
+    if let Err(e) = window.set_size(PhysicalSize::new(geometry.width, geometry.height)) {
+        warn!("Failed to restore settings window size: {}", e);
+    }
+    if let Err(e) = window.set_position(PhysicalPosition::new(geometry.x, geometry.y)) {
+        warn!("Failed to restore settings window position: {}", e);
+    }
+}
+
+// A saved position on a since-removed monitor would open the window off-screen
+fn clamp_to_monitors(window: &WebviewWindow, geometry: WindowGeometry) -> WindowGeometry {
+    let monitors = window.available_monitors().unwrap_or_default();
+    let visible = monitors.iter().any(|monitor| {
+        let (position, size) = (monitor.position(), monitor.size());
+        geometry.x >= position.x
+            && geometry.y >= position.y
+            && geometry.x < position.x + size.width as i32
+            && geometry.y < position.y + size.height as i32
+    });
+    if visible {
+        return geometry;
+    }
+
+    let Some(monitor) = window.primary_monitor().ok().flatten().or_else(|| monitors.into_iter().next()) else {
+        return geometry;
+    };
+    let name = monitor.name().map_or("the primary monitor", |name| name.as_str());
+    info!("Saved settings window position is off-screen, moving it to {}", name);
+    let (position, size) = (monitor.position(), monitor.size());
+    let width = geometry.width.min(size.width);
+    let height = geometry.height.min(size.height);
+    WindowGeometry {
+        x: position.x + ((size.width - width) / 2) as i32,
+        y: position.y + ((size.height - height) / 2) as i32,
+        width,
+        height,
+    }
+}
+
+/// Opens the OS touchpad settings, as a fallback when our own control fails.
+pub fn open_system_touchpad_settings(app: &AppHandle) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        use tauri_plugin_opener::OpenerExt;
+        app.opener()
+            .open_url("ms-settings:devices-touchpad", None::<&str>)
+            .map_err(|e| e.to_string())
+    }
+    #[cfg(target_os = "macos")]
+    {
+        use tauri_plugin_opener::OpenerExt;
+        app.opener()
+            .open_url("x-apple.systempreferences:com.apple.preference.trackpad", None::<&str>)
+            .map_err(|e| e.to_string())
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let _ = app;
+        let desktop = std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+        if !desktop.to_uppercase().contains("GNOME") {
+            return Err(format!("No touchpad settings page known for this desktop ({})", desktop));
+        }
+        std::process::Command::new("gnome-control-center")
+            .arg("mouse")
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to open gnome-control-center: {}", e))
+    }
+}
@@ -1,17 +1,58 @@
-import { useState } from "preact/hooks";
+import { useEffect, useState } from "preact/hooks";
 import preactLogo from "./assets/preact.svg";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import "./App.css";
 
+const LOG_LINES = 200;
+
 function App() {
-  const [greetMsg, setGreetMsg] = useState("");
-  const [name, setName] = useState("");
+  const [touchpadState, setTouchpadState] = useState("");
+  const [about, setAbout] = useState("");
+  const [showLogs, setShowLogs] = useState(false);
+  const [logs, setLogs] = useState<string[]>([]);
+
+  async function refreshState() {
+    try {
+      setTouchpadState(await invoke("get_touchpad_state"));
+    } catch (e) {
+      setTouchpadState(String(e));
+    }
+  }
+
+  async function toggle() {
+    await invoke("toggle_touchpad");
+    refreshState();
+  }
 
-  async function greet() {
-    // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-    setGreetMsg(await invoke("greet", { name }));
+  async function showAbout() {
+    try {
+      setAbout(JSON.stringify(await invoke("about_info"), null, 2));
+    } catch (e) {
+      setAbout(String(e));
+    }
   }
 
+  useEffect(() => {
+    refreshState();
+  }, []);
+
+  // Streams log lines only while the panel is open
+  useEffect(() => {
+    if (!showLogs) {
+      return;
+    }
+    const unlisten = listen<string>("log-line", (event) => {
+      setLogs((lines) => [...lines.slice(-(LOG_LINES - 1)), event.payload]);
+    });
+    invoke<string[]>("get_recent_logs", { count: LOG_LINES }).then(setLogs);
+    invoke("start_log_stream");
+    return () => {
+      unlisten.then((stop) => stop());
+      invoke("stop_log_stream");
+    };
+  }, [showLogs]);
+
   return (
     <main class="container">
       <h1>Welcome to Tauri + Preact</h1>
@@ -29,21 +70,14 @@ function App() {
       </div>
       <p>Click on the Tauri, Vite, and Preact logos to learn more.</p>
 
-      <form
-        class="row"
-        onSubmit={(e) => {
-          e.preventDefault();
-          greet();
-        }}
-      >
-        <input
-          id="greet-input"
-          onInput={(e) => setName(e.currentTarget.value)}
-          placeholder="Enter a name..."
-        />
-        <button type="submit">Greet</button>
-      </form>
-      <p>{greetMsg}</p>
+      <div class="row">
+        <button onClick={toggle}>Toggle touchpad</button>
+        <button onClick={showAbout}>About</button>
+        <button onClick={() => setShowLogs(!showLogs)}>{showLogs ? "Hide logs" : "Logs"}</button>
+      </div>
+      <p>Touchpad: {touchpadState}</p>
+      {about && <pre class="about">{about}</pre>}
+      {showLogs && <pre class="logs">{logs.join("\n")}</pre>}
     </main>
   );
 }